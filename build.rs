@@ -1,4 +1,7 @@
-use std::{env, fs, io, path::Path};
+use std::io;
+#[cfg(windows)]
+use std::{env, fs, path::Path};
+#[cfg(windows)]
 use winres::WindowsResource;
 
 fn main() -> io::Result<()> {
@@ -6,12 +9,12 @@ fn main() -> io::Result<()> {
     #[cfg(windows)]
     {
         WindowsResource::new().set_icon("hidden_tb.ico").compile()?;
+        copy_icon_to_target();
     }
-
-    copy_icon_to_target();
     Ok(())
 }
 
+#[cfg(windows)]
 fn copy_icon_to_target() {
     let mut source = env::var("CARGO_MANIFEST_DIR").unwrap();
     source.push_str("\\hidden_tb.ico");
@@ -0,0 +1,233 @@
+use serde::{Deserialize, Serialize};
+use std::fmt::Debug;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/* source of time for animations. now() is the time elapsed since an arbitrary start. */
+pub trait Clock: Debug {
+    fn now(&self) -> Duration;
+    /* a clock with the same time source, every taskbar owns its own clock. */
+    fn clone_box(&self) -> Box<dyn Clock>;
+}
+
+#[derive(Debug, Clone)]
+pub struct SystemClock {
+    start: Instant,
+}
+
+impl Default for SystemClock {
+    fn default() -> Self {
+        SystemClock {
+            start: Instant::now(),
+        }
+    }
+}
+
+impl Clock for SystemClock {
+    fn now(&self) -> Duration {
+        self.start.elapsed()
+    }
+
+    fn clone_box(&self) -> Box<dyn Clock> {
+        Box::new(self.clone())
+    }
+}
+
+/* a clock that only moves when advanced. Clones share the same time. */
+#[derive(Debug, Clone, Default)]
+pub struct ManualClock {
+    now: Arc<Mutex<Duration>>,
+}
+
+impl ManualClock {
+    pub fn new() -> Self {
+        ManualClock::default()
+    }
+
+    pub fn advance(&self, duration: Duration) {
+        *self.now.lock().expect("manual clock poisoned") += duration;
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Duration {
+        *self.now.lock().expect("manual clock poisoned")
+    }
+
+    fn clone_box(&self) -> Box<dyn Clock> {
+        Box::new(self.clone())
+    }
+}
+
+/* timing curve of the fade. The named curves use the same control points as their css counterparts. */
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Easing {
+    #[default]
+    Linear,
+    EaseIn,
+    EaseOut,
+    EaseInOut,
+    CubicBezier {
+        x1: f64,
+        y1: f64,
+        x2: f64,
+        y2: f64,
+    },
+}
+
+impl Easing {
+    /* maps the linear progress {t} between 0.0 and 1.0 onto the curve. */
+    pub fn apply(&self, t: f64) -> f64 {
+        let t = t.clamp(0.0, 1.0);
+        match *self {
+            Easing::Linear => t,
+            Easing::EaseIn => cubic_bezier(0.42, 0.0, 1.0, 1.0, t),
+            Easing::EaseOut => cubic_bezier(0.0, 0.0, 0.58, 1.0, t),
+            Easing::EaseInOut => cubic_bezier(0.42, 0.0, 0.58, 1.0, t),
+            Easing::CubicBezier { x1, y1, x2, y2 } => cubic_bezier(x1, y1, x2, y2, t),
+        }
+    }
+}
+
+/* one dimension of a cubic bezier from 0 to 1 with the control points {p1} and {p2}. */
+fn bezier_component(p1: f64, p2: f64, t: f64) -> f64 {
+    let u = 1.0 - t;
+    3.0 * u * u * t * p1 + 3.0 * u * t * t * p2 + t * t * t
+}
+
+fn bezier_component_derivative(p1: f64, p2: f64, t: f64) -> f64 {
+    let u = 1.0 - t;
+    3.0 * u * u * p1 + 6.0 * u * t * (p2 - p1) + 3.0 * t * t * (1.0 - p2)
+}
+
+/* solves the curve for x = {x} and returns y. x control values are clamped to 0..=1 so x is monotonic. */
+fn cubic_bezier(x1: f64, y1: f64, x2: f64, y2: f64, x: f64) -> f64 {
+    if x <= 0.0 || x >= 1.0 {
+        return x;
+    }
+    let x1 = x1.clamp(0.0, 1.0);
+    let x2 = x2.clamp(0.0, 1.0);
+
+    /* newton first, it converges in a few iterations for most curves */
+    let mut t = x;
+    for _ in 0..8 {
+        let error = bezier_component(x1, x2, t) - x;
+        if error.abs() < 1e-7 {
+            return bezier_component(y1, y2, t);
+        }
+        let slope = bezier_component_derivative(x1, x2, t);
+        if slope.abs() < 1e-6 {
+            break;
+        }
+        t -= error / slope;
+    }
+
+    /* bisection as fallback for flat parts of the curve */
+    let (mut low, mut high) = (0.0, 1.0);
+    t = x;
+    for _ in 0..50 {
+        let value = bezier_component(x1, x2, t);
+        if (value - x).abs() < 1e-7 {
+            break;
+        }
+        if value < x {
+            low = t;
+        } else {
+            high = t;
+        }
+        t = (low + high) / 2.0;
+    }
+    bezier_component(y1, y2, t)
+}
+
+/*
+Alpha fade that advances by elapsed time instead of sleeping.
+A full fade from 0 to 255 takes the duration passed to start, split into {steps} even steps
+whose alpha follows the easing curve.
+Starting a new fade while one is running continues from the current alpha, so a fade out can be
+reversed mid-flight and only takes the time for the remaining distance.
+*/
+#[derive(Debug, Clone)]
+pub struct FadeAnimation {
+    from: u8,
+    to: u8,
+    current: u8,
+    steps: u8,
+    easing: Easing,
+    start: Duration,
+    duration: Duration,
+    running: bool,
+}
+
+impl FadeAnimation {
+    pub fn new(alpha: u8, steps: u8, easing: Easing) -> Self {
+        FadeAnimation {
+            from: alpha,
+            to: alpha,
+            current: alpha,
+            steps: steps.max(1),
+            easing,
+            start: Duration::ZERO,
+            duration: Duration::ZERO,
+            running: false,
+        }
+    }
+
+    /* starts fading from the current alpha to {target}. {full_duration} is the time for a complete fade. */
+    pub fn start(&mut self, target: u8, full_duration: Duration, now: Duration) {
+        let distance = (target as i32 - self.current as i32).unsigned_abs();
+        self.from = self.current;
+        self.to = target;
+        self.start = now;
+        self.duration = full_duration.mul_f64(distance as f64 / 255.0);
+        self.running = distance != 0;
+    }
+
+    /* advances the fade to {now} and returns the alpha to apply. */
+    pub fn tick(&mut self, now: Duration) -> u8 {
+        if !self.running {
+            return self.current;
+        }
+        let progress = self.progress(now);
+        if progress >= 1.0 {
+            self.current = self.to;
+            self.running = false;
+            return self.current;
+        }
+        /* the alpha only changes once per step, the last step always lands exactly on the target */
+        let stepped_progress = (progress * self.steps as f64).floor() / self.steps as f64;
+        let delta = (self.to as f64 - self.from as f64) * self.easing.apply(stepped_progress);
+        self.current = (self.from as f64 + delta).round().clamp(0.0, 255.0) as u8;
+        self.current
+    }
+
+    /* progress of the current fade between 0.0 and 1.0. A finished fade reports 1.0. */
+    pub fn progress(&self, now: Duration) -> f64 {
+        if !self.running || self.duration.is_zero() {
+            return 1.0;
+        }
+        let elapsed = now.saturating_sub(self.start);
+        (elapsed.as_secs_f64() / self.duration.as_secs_f64()).min(1.0)
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.running
+    }
+
+    pub fn get_alpha(&self) -> u8 {
+        self.current
+    }
+
+    pub fn get_target(&self) -> u8 {
+        self.to
+    }
+
+    pub fn set_steps(&mut self, steps: u8) {
+        self.steps = steps.max(1);
+    }
+
+    pub fn set_easing(&mut self, easing: Easing) {
+        self.easing = easing;
+    }
+}
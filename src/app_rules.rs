@@ -0,0 +1,79 @@
+use regex::Regex;
+
+use crate::shell_backend::ForegroundWindow;
+use crate::tb_settings::{ForegroundRule, RuleAction};
+
+/* a foreground rule with its title regex compiled once instead of on every check. */
+#[derive(Debug, Clone)]
+pub struct CompiledRule {
+    rule: ForegroundRule,
+    title: Option<Regex>,
+}
+
+impl CompiledRule {
+    /* returns None if the title regex is invalid. */
+    pub fn new(rule: &ForegroundRule) -> Option<Self> {
+        let title = match rule.title_regex.is_empty() {
+            true => None,
+            false => Some(Regex::new(&rule.title_regex).ok()?),
+        };
+        Some(CompiledRule {
+            rule: rule.clone(),
+            title,
+        })
+    }
+
+    /* a rule without any field to check never matches. */
+    pub fn matches(&self, window: &ForegroundWindow) -> bool {
+        if self.rule.exe_name.is_empty()
+            && self.rule.window_class.is_empty()
+            && self.title.is_none()
+        {
+            return false;
+        }
+        if !self.rule.exe_name.is_empty() {
+            match &window.exe_name {
+                Some(exe_name) if exe_name.eq_ignore_ascii_case(&self.rule.exe_name) => {}
+                _ => return false,
+            }
+        }
+        if !self.rule.window_class.is_empty() && window.class_name != self.rule.window_class {
+            return false;
+        }
+        if let Some(title) = &self.title {
+            if !title.is_match(&window.title) {
+                return false;
+            }
+        }
+        true
+    }
+
+    pub fn get_action(&self) -> RuleAction {
+        self.rule.action
+    }
+}
+
+/* compiles all rules, rules with an invalid title regex are skipped. */
+pub fn compile_rules(rules: &[ForegroundRule]) -> Vec<CompiledRule> {
+    rules
+        .iter()
+        .filter_map(|rule| {
+            let compiled = CompiledRule::new(rule);
+            if compiled.is_none() {
+                eprintln!(
+                    "invalid title regex in foreground rule: {}",
+                    rule.title_regex
+                );
+            }
+            compiled
+        })
+        .collect()
+}
+
+/* the action of the first rule that matches {window}. */
+pub fn find_action(rules: &[CompiledRule], window: &ForegroundWindow) -> Option<RuleAction> {
+    rules
+        .iter()
+        .find(|rule| rule.matches(window))
+        .map(|rule| rule.get_action())
+}
@@ -0,0 +1,356 @@
+use std::io;
+use std::path::Path;
+
+use serde_json::Value;
+
+use crate::control::{self, Endpoint, Request, Response, Status};
+use crate::geometry::{Edge, Physical, Point, Rect};
+use crate::settings_registry;
+use crate::shell_backend::{ShellBackend, PRIMARY_TASKBAR_CLASS, SECONDARY_TASKBAR_CLASS};
+use crate::taskbars::Taskbars;
+use crate::tb_settings::TbSettings;
+
+pub const EXIT_OK: i32 = 0;
+/* the command failed, e.g. an invalid value or a request the running hidden_tb rejected. */
+pub const EXIT_FAILURE: i32 = 1;
+/* unknown command or missing arguments. */
+pub const EXIT_USAGE: i32 = 2;
+/* the command needs a running hidden_tb. */
+pub const EXIT_NOT_RUNNING: i32 = 3;
+/* run was started while another hidden_tb is running. */
+pub const EXIT_ALREADY_RUNNING: i32 = 4;
+
+pub const USAGE: &str = "\
+usage: hidden_tb [--config <path>] [command]
+
+  run                      runs hidden_tb, the default without a command. Only one hidden_tb runs at a time
+  settings                 opens the settings ui
+  show | hide | toggle     shows or hides the taskbars of the running hidden_tb until the cursor enters or leaves them
+  pin | unpin              keeps the taskbars of the running hidden_tb shown
+  status                   prints the state of the running hidden_tb
+  reload                   makes the running hidden_tb read the settings file again
+  profile <name>           makes <name> the active profile of the running hidden_tb
+  quit                     ends the running hidden_tb
+  restore                  restores the taskbars, e.g. after hidden_tb crashed
+  config get <key>         prints a setting of the settings file
+  config set <key> <value> changes a setting of the settings file, the value is json or a plain text
+  config list              prints every setting of the settings file
+  config validate          checks the settings file
+  config path              prints the path of the settings file
+
+exit codes: 0 success, 1 failure, 2 usage error, 3 hidden_tb is not running, 4 hidden_tb is already running";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    Run,
+    Settings,
+    Restore,
+    Help,
+    Remote(RemoteCommand),
+    Config(ConfigCommand),
+}
+
+/* commands for the running hidden_tb, sent over the control channel. */
+#[derive(Debug, Clone, PartialEq)]
+pub enum RemoteCommand {
+    Send(Request),
+    /* hides shown taskbars and shows hidden ones. */
+    Toggle,
+}
+
+/* commands on the settings file, they work without a running hidden_tb. */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigCommand {
+    Get(String),
+    Set(String, String),
+    List,
+    Validate,
+    Path,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CliError {
+    pub exit_code: i32,
+    pub message: String,
+}
+
+impl CliError {
+    pub fn new(exit_code: i32, message: impl Into<String>) -> Self {
+        CliError {
+            exit_code,
+            message: message.into(),
+        }
+    }
+
+    fn usage(message: impl Into<String>) -> Self {
+        Self::new(EXIT_USAGE, format!("{}\n\n{}", message.into(), USAGE))
+    }
+}
+
+/* parses the arguments without the program name. --config is left to the settings location. */
+pub fn parse(args: &[String]) -> Result<Command, CliError> {
+    let mut words = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if arg == "--config" {
+            args.next();
+        } else if !arg.starts_with("--config=") {
+            words.push(arg.as_str());
+        }
+    }
+    let send = |request| Ok(Command::Remote(RemoteCommand::Send(request)));
+    match words.as_slice() {
+        [] | ["run"] => Ok(Command::Run),
+        /* the argument of earlier versions */
+        ["settings"] | ["--settings"] => Ok(Command::Settings),
+        ["restore"] => Ok(Command::Restore),
+        ["help"] | ["--help"] | ["-h"] => Ok(Command::Help),
+        ["show"] => send(Request::Show),
+        ["hide"] => send(Request::Hide),
+        ["toggle"] => Ok(Command::Remote(RemoteCommand::Toggle)),
+        ["pin"] => send(Request::Pin),
+        ["unpin"] => send(Request::Unpin),
+        ["status"] => send(Request::Status),
+        ["reload"] => send(Request::Reload),
+        ["quit"] => send(Request::Quit),
+        ["profile", name] => send(Request::Profile {
+            name: name.to_string(),
+        }),
+        ["config", "get", key] => Ok(Command::Config(ConfigCommand::Get(key.to_string()))),
+        ["config", "set", key, value] => Ok(Command::Config(ConfigCommand::Set(
+            key.to_string(),
+            value.to_string(),
+        ))),
+        ["config", "list"] => Ok(Command::Config(ConfigCommand::List)),
+        ["config", "validate"] => Ok(Command::Config(ConfigCommand::Validate)),
+        ["config", "path"] => Ok(Command::Config(ConfigCommand::Path)),
+        words => Err(CliError::usage(format!(
+            "unknown command: {}",
+            words.join(" ")
+        ))),
+    }
+}
+
+/* prints the output of a command and returns its exit code. */
+pub fn report(result: Result<String, CliError>) -> i32 {
+    match result {
+        Ok(output) => {
+            if !output.is_empty() {
+                println!("{}", output);
+            }
+            EXIT_OK
+        }
+        Err(e) => {
+            eprintln!("{}", e.message);
+            e.exit_code
+        }
+    }
+}
+
+pub fn remote(command: &RemoteCommand, endpoint: &Endpoint) -> Result<String, CliError> {
+    let request = match command {
+        RemoteCommand::Send(request) => request.clone(),
+        RemoteCommand::Toggle => match send(endpoint, &Request::Status)?.status {
+            Some(status) if status.hidden => Request::Show,
+            _ => Request::Hide,
+        },
+    };
+    let response = send(endpoint, &request)?;
+    Ok(response.status.map(format_status).unwrap_or_default())
+}
+
+/* sends {request}, a rejected request is an error. */
+fn send(endpoint: &Endpoint, request: &Request) -> Result<Response, CliError> {
+    let response = control::send(endpoint, request).map_err(|e| match e.kind() {
+        io::ErrorKind::NotFound | io::ErrorKind::ConnectionRefused => {
+            CliError::new(EXIT_NOT_RUNNING, "hidden_tb is not running")
+        }
+        _ => CliError::new(EXIT_FAILURE, format!("could not reach hidden_tb: {}", e)),
+    })?;
+    if response.ok {
+        return Ok(response);
+    }
+    let code = response
+        .code
+        .and_then(|code| serde_json::to_value(code).ok())
+        .and_then(|code| code.as_str().map(str::to_string))
+        .unwrap_or_default();
+    Err(CliError::new(
+        EXIT_FAILURE,
+        format!("{}: {}", code, response.error.unwrap_or_default()),
+    ))
+}
+
+fn format_status(status: Status) -> String {
+    let mut lines = vec![
+        format!("profile: {}", status.active_profile),
+        format!("hidden: {}", status.hidden),
+        format!("pinned: {}", status.pinned),
+        format!("previewing: {}", status.previewing),
+        format!("taskbars: {}", status.taskbars),
+    ];
+    if let Some(settings_file) = status.settings_file {
+        lines.push(format!("settings file: {}", settings_file));
+    }
+    lines.join("\n")
+}
+
+/* runs a config command on the settings file at {path}, the defaults are used while it does not exist. */
+pub fn config(
+    command: &ConfigCommand,
+    path: &Path,
+    endpoint: &Endpoint,
+) -> Result<String, CliError> {
+    let failure = |message: String| CliError::new(EXIT_FAILURE, message);
+    let load = || match path.exists() {
+        true => TbSettings::load_from(path)
+            .map_err(|e| failure(format!("could not read {}: {}", path.display(), e))),
+        false => Ok(TbSettings::load_defaults()),
+    };
+    match command {
+        ConfigCommand::Get(key) => {
+            let settings = load()?;
+            match (settings_registry::find(key), settings.get_value(key)) {
+                (Some(_), Some(value)) => Ok(format_value(&value)),
+                _ => Err(failure(format!(
+                    "unknown setting {}, the settings are {}",
+                    key,
+                    keys()
+                ))),
+            }
+        }
+        ConfigCommand::Set(key, text) => {
+            let mut settings = load()?;
+            /* plain text like "slide" does not have to be quoted */
+            let value = serde_json::from_str(text).unwrap_or_else(|_| Value::from(text.as_str()));
+            settings
+                .set_value(key, value)
+                .map_err(|e| failure(e.to_string()))?;
+            settings
+                .save_to(path)
+                .map_err(|e| failure(format!("settings not saved: {}", e)))?;
+            /* a running hidden_tb applies the change right away instead of with its next check */
+            control::send(endpoint, &Request::Reload).ok();
+            Ok(String::new())
+        }
+        ConfigCommand::List => {
+            let settings = load()?;
+            Ok(settings_registry::all()
+                .iter()
+                .map(|setting| {
+                    let value = settings
+                        .get_value(setting.key)
+                        .expect("every setting has a value");
+                    format!("{} = {}", setting.key, format_value(&value))
+                })
+                .collect::<Vec<_>>()
+                .join("\n"))
+        }
+        ConfigCommand::Validate => {
+            if !path.exists() {
+                return Ok(format!(
+                    "{} does not exist, the defaults are used",
+                    path.display()
+                ));
+            }
+            let settings = load()?;
+            match settings.validate() {
+                Ok(()) => Ok(format!("{} is valid", path.display())),
+                Err(errors) => Err(failure(
+                    errors
+                        .iter()
+                        .map(|error| error.to_string())
+                        .collect::<Vec<_>>()
+                        .join("\n"),
+                )),
+            }
+        }
+        ConfigCommand::Path => Ok(path.display().to_string()),
+    }
+}
+
+/* strings are printed without quotes, everything else as json. */
+fn format_value(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        value => value.to_string(),
+    }
+}
+
+fn keys() -> String {
+    settings_registry::all()
+        .iter()
+        .map(|setting| setting.key)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/*
+A running hidden_tb restores its taskbars when it quits. Without one, the styles, regions and the work area
+a crashed hidden_tb left behind are reset here.
+*/
+pub fn restore<B: ShellBackend + Clone>(
+    endpoint: &Endpoint,
+    backend: B,
+) -> Result<String, CliError> {
+    if control::send(endpoint, &Request::Quit).is_ok_and(|response| response.ok) {
+        return Ok("the running hidden_tb quits and restores the taskbars".to_string());
+    }
+    redock_taskbars(&backend);
+    let mut taskbars = Taskbars::new(TbSettings::load_defaults(), backend);
+    taskbars.sync_monitors();
+    taskbars.clean_up();
+    Ok(format!(
+        "restored {} taskbars",
+        1 + taskbars.secondaries().len()
+    ))
+}
+
+/*
+A taskbar a crashed hidden_tb left slid away is not on its monitor anymore and would not be found.
+It is moved back onto the edge of the nearest monitor first.
+*/
+fn redock_taskbars<B: ShellBackend>(backend: &B) {
+    let displays: Vec<_> = backend
+        .get_monitors()
+        .iter()
+        .map(|monitor| monitor.get_display())
+        .collect();
+    let taskbars = backend
+        .find_windows(None, PRIMARY_TASKBAR_CLASS)
+        .into_iter()
+        .chain(backend.find_windows(None, SECONDARY_TASKBAR_CLASS));
+    for taskbar in taskbars {
+        let rect = taskbar.rect;
+        let Some(display) = displays
+            .iter()
+            .min_by_key(|display| distance(display, &rect.center()))
+        else {
+            return;
+        };
+        let edge = Edge::of(&rect, display);
+        let thickness = match edge.is_vertical() {
+            true => Physical(rect.width()),
+            false => Physical(rect.height()),
+        };
+        let docked = display.strip(edge, thickness);
+        let (x, y) = match edge.is_vertical() {
+            true => (docked.left, rect.top),
+            false => (rect.left, docked.top),
+        };
+        if (x, y) != (rect.left, rect.top) {
+            backend.set_window_position(taskbar.hwnd, x, y);
+        }
+    }
+}
+
+/* how far {point} is outside of {display}, 0 inside of it. */
+fn distance(display: &Rect, point: &Point) -> i32 {
+    let dx = (display.left - point.x)
+        .max(point.x - display.right + 1)
+        .max(0);
+    let dy = (display.top - point.y)
+        .max(point.y - display.bottom + 1)
+        .max(0);
+    dx + dy
+}
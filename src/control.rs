@@ -0,0 +1,320 @@
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/* how long a connection waits for the main loop to answer a request. */
+const ANSWER_TIMEOUT: Duration = Duration::from_secs(5);
+
+/*
+Local control channel of a running hidden_tb, one json object per line in both directions.
+On windows it is a named pipe, elsewhere a unix socket stands in for it so the channel can be tested on any platform.
+*/
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Endpoint {
+    /* the pipe name on windows, the socket path elsewhere. */
+    pub name: String,
+}
+
+impl Endpoint {
+    pub fn new(name: impl Into<String>) -> Self {
+        Endpoint { name: name.into() }
+    }
+
+    /* the endpoint of the hidden_tb of the current user. */
+    pub fn for_user() -> Self {
+        let user =
+            std::env::var(if cfg!(windows) { "USERNAME" } else { "USER" }).unwrap_or_default();
+        if cfg!(windows) {
+            Self::new(format!(r"\\.\pipe\hidden_tb-{}", user))
+        } else {
+            let path = std::env::temp_dir().join(format!("hidden_tb-{}.sock", user));
+            Self::new(path.to_string_lossy())
+        }
+    }
+}
+
+/*
+The requests, e.g. {"command":"pin"}, {"command":"set","key":"margin_top","value":4} or {"command":"profile","name":"focus"}.
+Every request is answered with one response line.
+*/
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "command", rename_all = "snake_case")]
+pub enum Request {
+    /* shows the taskbars until the cursor enters or leaves them, then autohide takes over again. */
+    Show,
+    /* hides the taskbars until the cursor enters or leaves them. */
+    Hide,
+    /* keeps the taskbars shown until unpin. */
+    Pin,
+    Unpin,
+    /* reads the settings file again, even if it did not change. */
+    Reload,
+    Status,
+    /* changes a setting of the saved settings, the file is saved and the change applied right away. */
+    Set { key: String, value: Value },
+    /* makes {name} the active profile, the file is saved and the profile applied right away. */
+    Profile { name: String },
+    /* ends the running hidden_tb, the taskbars are restored first. */
+    Quit,
+    /* applies {values} over the saved settings without saving them. Every preview replaces the previous one. */
+    Preview { values: Map<String, Value> },
+    /* drops the preview and applies the saved settings again. */
+    Revert,
+}
+
+impl Request {
+    pub const COMMANDS: [&'static str; 11] = [
+        "show", "hide", "pin", "unpin", "reload", "status", "set", "profile", "quit", "preview",
+        "revert",
+    ];
+
+    /* parses one request line, the error response tells unknown commands apart from malformed requests. */
+    pub fn parse(line: &str) -> Result<Request, Response> {
+        let value: Value = serde_json::from_str(line)
+            .map_err(|e| Response::error(ErrorCode::InvalidRequest, format!("not json: {}", e)))?;
+        match value.get("command").and_then(Value::as_str) {
+            Some(command) if !Self::COMMANDS.contains(&command) => {
+                return Err(Response::error(
+                    ErrorCode::UnknownCommand,
+                    format!(
+                        "unknown command {}, the commands are {}",
+                        command,
+                        Self::COMMANDS.join(", ")
+                    ),
+                ))
+            }
+            Some(_) => {}
+            None => {
+                return Err(Response::error(
+                    ErrorCode::InvalidRequest,
+                    "the request has no command",
+                ))
+            }
+        }
+        serde_json::from_value(value)
+            .map_err(|e| Response::error(ErrorCode::InvalidRequest, e.to_string()))
+    }
+}
+
+/* why a request failed, scripts can check the code instead of the message. */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    /* the line is not json or misses a field of its command. */
+    InvalidRequest,
+    UnknownCommand,
+    /* a set or preview value that is not allowed for its setting, or an unknown key. */
+    InvalidValue,
+    UnknownProfile,
+    /* the settings file could not be read or written. */
+    SettingsFile,
+    /* the main loop did not answer in time or is shutting down. */
+    Unavailable,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Response {
+    pub ok: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub code: Option<ErrorCode>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /* the answer of the status command. */
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<Status>,
+}
+
+impl Response {
+    pub fn ok() -> Self {
+        Response {
+            ok: true,
+            code: None,
+            error: None,
+            status: None,
+        }
+    }
+
+    pub fn error(code: ErrorCode, message: impl Into<String>) -> Self {
+        Response {
+            ok: false,
+            code: Some(code),
+            error: Some(message.into()),
+            status: None,
+        }
+    }
+
+    pub fn with_status(status: Status) -> Self {
+        Response {
+            status: Some(status),
+            ..Self::ok()
+        }
+    }
+}
+
+/* the state of the running hidden_tb. */
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Status {
+    pub active_profile: String,
+    /* the primary taskbar is hidden. */
+    pub hidden: bool,
+    pub pinned: bool,
+    /* the settings ui previews unsaved settings. */
+    pub previewing: bool,
+    pub taskbars: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub settings_file: Option<String>,
+}
+
+/* a request the main loop has to answer. */
+pub struct PendingRequest {
+    pub request: Request,
+    answer: Sender<Response>,
+}
+
+impl PendingRequest {
+    pub fn respond(self, response: Response) {
+        /* the client may have given up waiting */
+        self.answer.send(response).ok();
+    }
+}
+
+/*
+Accepts connections on a background thread. The requests are handed to the main loop,
+which takes them with try_next so the taskbars are only ever changed from the main loop.
+*/
+pub struct ControlServer {
+    requests: Receiver<PendingRequest>,
+}
+
+impl ControlServer {
+    pub fn start(endpoint: &Endpoint) -> io::Result<Self> {
+        let listener = transport::Listener::bind(endpoint)?;
+        let (sender, requests) = mpsc::channel();
+        thread::spawn(move || loop {
+            match listener.accept() {
+                Ok(stream) => {
+                    let sender = sender.clone();
+                    thread::spawn(move || serve(stream, sender));
+                }
+                Err(e) => {
+                    eprintln!("Control channel stopped: {}", e);
+                    return;
+                }
+            }
+        });
+        Ok(ControlServer { requests })
+    }
+
+    pub fn try_next(&self) -> Option<PendingRequest> {
+        self.requests.try_recv().ok()
+    }
+}
+
+/* answers the requests of one connection until the client closes it. */
+fn serve<S>(stream: S, requests: Sender<PendingRequest>)
+where
+    for<'a> &'a S: Read + Write,
+{
+    let mut writer = &stream;
+    for line in BufReader::new(&stream).lines() {
+        let Ok(line) = line else {
+            return;
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+        let response = match Request::parse(&line) {
+            Ok(request) => forward(request, &requests),
+            Err(response) => response,
+        };
+        let mut text = serde_json::to_string(&response).expect("responses serialize");
+        text.push('\n');
+        if writer.write_all(text.as_bytes()).is_err() || writer.flush().is_err() {
+            return;
+        }
+    }
+}
+
+fn forward(request: Request, requests: &Sender<PendingRequest>) -> Response {
+    let (answer, answer_receiver) = mpsc::channel();
+    if requests.send(PendingRequest { request, answer }).is_err() {
+        return Response::error(ErrorCode::Unavailable, "hidden_tb is shutting down");
+    }
+    answer_receiver
+        .recv_timeout(ANSWER_TIMEOUT)
+        .unwrap_or_else(|_| Response::error(ErrorCode::Unavailable, "hidden_tb did not answer"))
+}
+
+/* sends {request} to the hidden_tb listening on {endpoint} and waits for its response. */
+pub fn send(endpoint: &Endpoint, request: &Request) -> io::Result<Response> {
+    let stream = transport::connect(endpoint)?;
+    let mut text = serde_json::to_string(request)?;
+    text.push('\n');
+    let mut writer = &stream;
+    writer.write_all(text.as_bytes())?;
+    writer.flush()?;
+    let mut line = String::new();
+    BufReader::new(&stream).read_line(&mut line)?;
+    if line.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "hidden_tb closed the connection",
+        ));
+    }
+    Ok(serde_json::from_str(&line)?)
+}
+
+#[cfg(windows)]
+mod transport {
+    pub use crate::named_pipe::{connect, Listener};
+}
+
+#[cfg(unix)]
+mod transport {
+    use std::io;
+    use std::os::unix::fs::FileTypeExt;
+    use std::os::unix::net::{UnixListener, UnixStream};
+
+    use super::Endpoint;
+
+    pub struct Listener(UnixListener);
+
+    impl Listener {
+        pub fn bind(endpoint: &Endpoint) -> io::Result<Self> {
+            match UnixStream::connect(&endpoint.name) {
+                Ok(_) => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::AddrInUse,
+                        format!("{} is already used by another process", endpoint.name),
+                    ))
+                }
+                /* a socket file left behind by a hidden_tb that did not exit cleanly */
+                Err(e)
+                    if e.kind() == io::ErrorKind::ConnectionRefused
+                        && is_socket(&endpoint.name) =>
+                {
+                    std::fs::remove_file(&endpoint.name)?;
+                }
+                Err(_) => {}
+            }
+            UnixListener::bind(&endpoint.name).map(Listener)
+        }
+
+        pub fn accept(&self) -> io::Result<UnixStream> {
+            self.0.accept().map(|(stream, _)| stream)
+        }
+    }
+
+    /* only sockets are removed, connecting to any other file is refused as well. */
+    fn is_socket(path: &str) -> bool {
+        std::fs::symlink_metadata(path).is_ok_and(|metadata| metadata.file_type().is_socket())
+    }
+
+    pub fn connect(endpoint: &Endpoint) -> io::Result<UnixStream> {
+        UnixStream::connect(&endpoint.name)
+    }
+}
@@ -0,0 +1,228 @@
+use std::ops::{Add, Neg, Sub};
+
+use serde::{Deserialize, Serialize};
+
+/*
+Platform independent geometry types. The windows layer converts them from and to RECT and POINT.
+Rects and points are physical pixels of the monitor, as the shell reports them for a per monitor dpi aware process.
+Lengths from the settings are Logical and only become Physical through the Dpi of the monitor they are used on,
+the rect methods take Physical lengths so an unscaled setting does not compile.
+*/
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rect {
+    pub left: i32,
+    pub top: i32,
+    pub right: i32,
+    pub bottom: i32,
+}
+
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Point {
+    pub x: i32,
+    pub y: i32,
+}
+
+/* a length in logical (device independent) pixels, one logical pixel is one physical pixel at 96 dpi. */
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Logical(pub i32);
+
+/* a length in physical pixels of a monitor. */
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Physical(pub i32);
+
+/* lengths add up with lengths of the same unit only. */
+macro_rules! length_ops {
+    ($($unit:ident),*) => {$(
+        impl Add for $unit {
+            type Output = $unit;
+            fn add(self, other: $unit) -> $unit {
+                $unit(self.0 + other.0)
+            }
+        }
+
+        impl Sub for $unit {
+            type Output = $unit;
+            fn sub(self, other: $unit) -> $unit {
+                $unit(self.0 - other.0)
+            }
+        }
+
+        impl Neg for $unit {
+            type Output = $unit;
+            fn neg(self) -> $unit {
+                $unit(-self.0)
+            }
+        }
+    )*};
+}
+
+length_ops!(Logical, Physical);
+
+/* dots per inch of a monitor, 96 is a scale of 100%. */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Dpi(pub u32);
+
+impl Default for Dpi {
+    fn default() -> Self {
+        Dpi::DEFAULT
+    }
+}
+
+impl Dpi {
+    pub const DEFAULT: Dpi = Dpi(96);
+
+    /* 0 is returned by the shell for windows it can not query, it is treated as the default. */
+    pub fn new(dpi: u32) -> Self {
+        match dpi {
+            0 => Dpi::DEFAULT,
+            dpi => Dpi(dpi),
+        }
+    }
+
+    pub fn scale(&self) -> f64 {
+        self.0 as f64 / Dpi::DEFAULT.0 as f64
+    }
+
+    pub fn to_physical(&self, length: Logical) -> Physical {
+        Physical((length.0 as f64 * self.scale()).round() as i32)
+    }
+
+    pub fn to_logical(&self, length: Physical) -> Logical {
+        Logical((length.0 as f64 / self.scale()).round() as i32)
+    }
+}
+
+/* the screen edge a taskbar is docked to. */
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Edge {
+    Left,
+    Top,
+    Right,
+    #[default]
+    Bottom,
+}
+
+impl Edge {
+    /* the edge of {display} the {rect} is docked to. Wide rects dock to the top or bottom, tall rects to the left or right. */
+    pub fn of(rect: &Rect, display: &Rect) -> Edge {
+        if rect.width() >= rect.height() {
+            match rect.top - display.top < display.bottom - rect.bottom {
+                true => Edge::Top,
+                false => Edge::Bottom,
+            }
+        } else {
+            match rect.left - display.left < display.right - rect.right {
+                true => Edge::Left,
+                false => Edge::Right,
+            }
+        }
+    }
+
+    pub fn is_vertical(&self) -> bool {
+        matches!(self, Edge::Left | Edge::Right)
+    }
+
+    /* unit step pointing off the screen. */
+    pub fn outward(&self) -> Point {
+        match self {
+            Edge::Left => Point::new(-1, 0),
+            Edge::Top => Point::new(0, -1),
+            Edge::Right => Point::new(1, 0),
+            Edge::Bottom => Point::new(0, 1),
+        }
+    }
+}
+
+impl Rect {
+    pub fn new(left: i32, top: i32, right: i32, bottom: i32) -> Self {
+        Rect {
+            left,
+            top,
+            right,
+            bottom,
+        }
+    }
+
+    pub fn width(&self) -> i32 {
+        self.right - self.left
+    }
+
+    pub fn height(&self) -> i32 {
+        self.bottom - self.top
+    }
+
+    pub fn offset(&self, dx: i32, dy: i32) -> Rect {
+        Rect::new(
+            self.left + dx,
+            self.top + dy,
+            self.right + dx,
+            self.bottom + dy,
+        )
+    }
+
+    pub fn center(&self) -> Point {
+        Point::new(self.left + self.width() / 2, self.top + self.height() / 2)
+    }
+
+    /* the {size} pixels wide part of the rect along {edge}. */
+    pub fn strip(&self, edge: Edge, size: Physical) -> Rect {
+        let Physical(size) = size;
+        let mut strip = *self;
+        match edge {
+            Edge::Left => strip.right = self.left + size,
+            Edge::Top => strip.bottom = self.top + size,
+            Edge::Right => strip.left = self.right - size,
+            Edge::Bottom => strip.top = self.bottom - size,
+        }
+        strip
+    }
+
+    /* moves {edge} outwards by {amount}, a negative amount moves it inwards. */
+    pub fn grow_edge(&self, edge: Edge, amount: Physical) -> Rect {
+        let Physical(amount) = amount;
+        let mut grown = *self;
+        match edge {
+            Edge::Left => grown.left -= amount,
+            Edge::Top => grown.top -= amount,
+            Edge::Right => grown.right += amount,
+            Edge::Bottom => grown.bottom += amount,
+        }
+        grown
+    }
+
+    /* swaps the x and y axis. Vertical taskbars are computed like horizontal ones in the transposed space. */
+    pub fn transposed(&self) -> Rect {
+        Rect::new(self.top, self.left, self.bottom, self.right)
+    }
+
+    /* grows the rect by {margin} on every side, a negative margin shrinks it. */
+    pub fn expand(&self, margin: Physical) -> Rect {
+        let Physical(margin) = margin;
+        Rect::new(
+            self.left - margin,
+            self.top - margin,
+            self.right + margin,
+            self.bottom + margin,
+        )
+    }
+
+    /* true if both rects share at least one pixel. */
+    pub fn intersects(&self, other: &Rect) -> bool {
+        self.left < other.right
+            && other.left < self.right
+            && self.top < other.bottom
+            && other.top < self.bottom
+    }
+
+    /* same semantics as PtInRect: the right and bottom edges are exclusive. */
+    pub fn contains(&self, point: &Point) -> bool {
+        point.x >= self.left && point.x < self.right && point.y >= self.top && point.y < self.bottom
+    }
+}
+
+impl Point {
+    pub fn new(x: i32, y: i32) -> Self {
+        Point { x, y }
+    }
+}
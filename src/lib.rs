@@ -0,0 +1,36 @@
+/*
+Platform independent core of hidden_tb: settings, geometry, the taskbar state machine and the main loop.
+The windows adapters are only compiled on windows, everything else builds and tests on any platform.
+*/
+pub mod animation;
+pub mod app_rules;
+pub mod cli;
+pub mod control;
+pub mod geometry;
+pub mod region;
+pub mod scheduler;
+pub mod settings_location;
+pub mod settings_registry;
+pub mod settings_ui;
+pub mod settings_validation;
+pub mod settings_watcher;
+pub mod shell_backend;
+pub mod signaling;
+pub mod simulated_shell;
+pub mod single_instance;
+pub mod taskbar;
+pub mod taskbars;
+pub mod tb_settings;
+
+#[cfg(windows)]
+pub mod monitors;
+#[cfg(windows)]
+pub mod named_pipe;
+#[cfg(windows)]
+pub mod restart_process;
+#[cfg(windows)]
+pub mod top_level_windows;
+#[cfg(windows)]
+pub mod tray;
+#[cfg(windows)]
+pub mod windows_calls;
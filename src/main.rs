@@ -1,138 +1,111 @@
 #![windows_subsystem = "windows"]
 
-use crate::tb_settings::TbSettings;
-use std::{thread, time};
-use taskbar::Taskbar;
-mod monitors;
-mod restart_process;
-mod settings_ui;
-mod signaling;
-mod taskbar;
-mod tb_settings;
-mod tray;
-mod windows_calls;
-#[inline(always)]
-fn update_handles_of_tb(taskbar: &mut Taskbar) {
-    let new_handles = taskbar.fetch_new_handles();
-    if !new_handles.contains_none() {
-        taskbar.insert_handles(new_handles);
-    }
-}
-
-#[inline(always)]
-fn infrequent_routine(
-    settings: &TbSettings,
-    taskbar: &mut Taskbar,
-    update_handles_in_infrequent_routine: &bool,
-) {
-    if settings.get_autohide() || settings.get_enable_dynamic_borders() {
-        taskbar.check_and_set_taskbar_transparency_state();
-        if settings.get_autohide() {
-            windows_calls::check_and_update_workspace_region_for_autohide(
-                taskbar,
-                settings.get_workspace_offset_top(),
-            );
-        }
-        if settings.get_enable_dynamic_borders() {
-            taskbar.call_dynamic_update(taskbar.is_hovering_on_tray(), false);
-        }
-    }
-    if *update_handles_in_infrequent_routine {
-        update_handles_of_tb(taskbar);
-    }
-}
-
-#[inline(always)]
-fn check_and_init_taskbar_state(settings: &TbSettings, taskbar: &mut Taskbar) -> bool {
-    if (settings.get_autohide() || settings.get_enable_dynamic_borders())
-        && !taskbar.check_and_set_taskbar_transparency_state()
-    {
-        return false;
-    }
-    true
-}
+#[cfg(windows)]
+fn start_hidden_tb() {
+    use hidden_tb::control::{ControlServer, Endpoint};
+    use hidden_tb::scheduler::Scheduler;
+    use hidden_tb::settings_watcher::SettingsWatcher;
+    use hidden_tb::taskbars::Taskbars;
+    use hidden_tb::tb_settings::TbSettings;
+    use hidden_tb::{signaling, tray, windows_calls};
 
-#[inline(always)]
-fn init_tb_state_routine(
-    settings: &TbSettings,
-    taskbar: &mut Taskbar,
-    signaling: &signaling::Signaling,
-) {
-    let mut infr_cnter = 0;
-    while !check_and_init_taskbar_state(settings, taskbar) && !signaling.get_exit_called() {
-        infr_cnter += 1;
-        thread::sleep(time::Duration::from_millis(100));
-        if infr_cnter > 100 {
-            eprintln!("Could not initialize taskbar state, trying to refresh the handles");
-            infr_cnter = 0;
-            taskbar.refresh_handles();
-        }
+    let (settings, warning) = TbSettings::new_with_warning();
+    let mut taskbars = Taskbars::new(settings.clone(), windows_calls::WindowsShell);
+    let mut scheduler = Scheduler::new(&settings);
+    scheduler.watch_settings(SettingsWatcher::new(TbSettings::get_path()));
+    /* the settings ui and scripts control the running hidden_tb through the control channel */
+    match ControlServer::start(&Endpoint::for_user()) {
+        Ok(control_server) => scheduler.listen(control_server),
+        Err(e) => eprintln!("Could not open the control channel: {}", e),
     }
-}
-
-fn start_hidden_tb() {
-    let settings = TbSettings::new();
-    let dur = time::Duration::from_millis(settings.get_sleep_time_in_ms());
-    let mut taskbar = Taskbar::new();
-    let mut infrequent_counter: usize = 0;
     let signaling = signaling::get_signaling_struct();
     //spawn system tray icon
-    let ui_handle = std::thread::spawn(|| {
-        tray::start_tray_icon();
+    let ui_handle = std::thread::spawn(move || {
+        tray::start_tray_icon(warning);
     });
-    // wait until all handles are available
-    while taskbar.contains_none() && !signaling.get_exit_called() {
-        taskbar.print_which_is_none();
-        eprintln!("Waiting for handles...");
-        thread::sleep(time::Duration::from_millis(100));
-        taskbar.refresh_handles();
-    }
 
-    println!("got handles, initializing taskbar state");
-    init_tb_state_routine(&settings, &mut taskbar, &signaling);
-    println!("entering main loop");
+    scheduler.run(&settings, &mut taskbars, &signaling);
 
-    //handles have to be updated on every loop if a merging option is enabled, to react to applist changes.
-    let update_handles_in_infrequent_routine = !(settings.get_merge_tray()
-        || settings.get_merge_widgets()
-        || settings.get_enable_dynamic_borders());
-
-    taskbar.automation_routine();
-    taskbar.send_restarts();
-    loop {
-        if signaling.get_exit_called() {
-            break;
-        }
+    taskbars.clean_up();
+    println!("Cleaned up");
+    /* the quit command ends the main loop without the tray */
+    tray::stop_tray_icon(&ui_handle);
+    ui_handle.join().expect("tray thread finished");
+}
 
-        infrequent_counter %= settings.get_infrequent_count();
-        if infrequent_counter == 0 {
-            infrequent_routine(
-                &settings,
-                &mut taskbar,
-                &update_handles_in_infrequent_routine,
-            );
-        }
+/* the commands that need no taskbar, they work on every platform. */
+fn run_command(command: hidden_tb::cli::Command) -> Result<String, hidden_tb::cli::CliError> {
+    use hidden_tb::cli::{self, CliError, Command};
+    use hidden_tb::control::Endpoint;
+    use hidden_tb::settings_location::SettingsLocation;
 
-        if !update_handles_in_infrequent_routine {
-            update_handles_of_tb(&mut taskbar);
+    match command {
+        Command::Help => Ok(cli::USAGE.to_string()),
+        Command::Remote(command) => cli::remote(&command, &Endpoint::for_user()),
+        Command::Config(command) => {
+            /* without the "settings file:" line of settings_location::get, the output is for scripts */
+            let location = SettingsLocation::from_env();
+            cli::config(&command, &location.path, &Endpoint::for_user())
         }
-
-        taskbar.handle_taskbar_state();
-
-        infrequent_counter += 1;
-        thread::sleep(dur);
+        Command::Run | Command::Settings | Command::Restore => Err(CliError::new(
+            cli::EXIT_FAILURE,
+            "hidden_tb controls the windows taskbar and can only run on windows.",
+        )),
     }
-    taskbar.clean_up();
-    println!("Cleaned up");
-    ui_handle.join().expect("tray thread finished");
+}
+
+#[cfg(windows)]
+fn main() {
+    use hidden_tb::cli::{self, Command};
+    use hidden_tb::control::Endpoint;
+    use hidden_tb::{settings_ui, single_instance, windows_calls};
 
-    if signaling.get_settings_called() {
-        println!("Opening UI");
-        settings_ui::open_ui();
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let command = cli::parse(&args);
+    if !matches!(command, Ok(Command::Run | Command::Settings)) {
+        windows_calls::attach_parent_console();
     }
+    let result = match command {
+        Ok(Command::Run) => {
+            /* a second main loop would fight the running one over the taskbars */
+            let _guard = match single_instance::acquire(&single_instance::name_for_user()) {
+                Ok(Some(guard)) => Some(guard),
+                Ok(None) => {
+                    let message =
+                        "hidden_tb is already running. Use its tray icon, \"hidden_tb settings\" \
+                        or \"hidden_tb quit\" to control it.";
+                    match windows_calls::attach_parent_console() {
+                        true => eprintln!("{}", message),
+                        false => windows_calls::show_message_box(message),
+                    }
+                    std::process::exit(cli::EXIT_ALREADY_RUNNING);
+                }
+                Err(e) => {
+                    eprintln!("Could not check for a running hidden_tb: {}", e);
+                    None
+                }
+            };
+            windows_calls::initialize_windows_calls();
+            start_hidden_tb();
+            return;
+        }
+        /* the settings ui runs in its own process, the running hidden_tb applies its previews and saved settings */
+        Ok(Command::Settings) => {
+            settings_ui::open_ui();
+            return;
+        }
+        Ok(Command::Restore) => {
+            windows_calls::initialize_windows_calls();
+            cli::restore(&Endpoint::for_user(), windows_calls::WindowsShell)
+        }
+        command => command.and_then(run_command),
+    };
+    std::process::exit(cli::report(result));
 }
 
+#[cfg(not(windows))]
 fn main() {
-    windows_calls::initialize_windows_calls();
-    start_hidden_tb();
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let result = hidden_tb::cli::parse(&args).and_then(run_command);
+    std::process::exit(hidden_tb::cli::report(result));
 }
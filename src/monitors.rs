@@ -1,36 +1,23 @@
 use std::mem;
-use windows::Win32::Foundation::RECT;
 
-#[derive(Debug)]
-pub struct Monitor {
-    display: RECT,
-    is_primary: bool,
-}
-
-impl Monitor {
-    pub fn get_display(&self) -> RECT {
-        self.display
-    }
-    pub fn is_primary(&self) -> bool {
-        self.is_primary
-    }
-}
+use crate::geometry::Rect;
+use crate::shell_backend::Monitor;
 
 pub fn get_monitors() -> Vec<Monitor> {
     let mut monitors = vec![];
     println!("Enumerating monitors");
     for monitor in enumerate_monitors() {
         /* currently only the display form is needed. Many more infos can be retrieved. */
-        monitors.push(Monitor {
-            display: RECT {
-                left: monitor.monitorInfo.rcMonitor.left,
-                top: monitor.monitorInfo.rcMonitor.top,
-                right: monitor.monitorInfo.rcMonitor.right,
-                bottom: monitor.monitorInfo.rcMonitor.bottom,
-            },
-            is_primary: monitor.monitorInfo.dwFlags
+        monitors.push(Monitor::new(
+            Rect::new(
+                monitor.monitorInfo.rcMonitor.left,
+                monitor.monitorInfo.rcMonitor.top,
+                monitor.monitorInfo.rcMonitor.right,
+                monitor.monitorInfo.rcMonitor.bottom,
+            ),
+            monitor.monitorInfo.dwFlags
                 == windows::Win32::UI::WindowsAndMessaging::MONITORINFOF_PRIMARY,
-        })
+        ))
     }
     println!("Monitors: {:?}", monitors);
     monitors
@@ -0,0 +1,193 @@
+use std::ffi::c_void;
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::mem;
+use std::os::windows::io::{AsRawHandle, FromRawHandle, RawHandle};
+use std::sync::Mutex;
+use std::{thread, time};
+
+use windows::core::{HSTRING, PWSTR};
+use windows::Win32::Foundation::{
+    CloseHandle, LocalFree, ERROR_ACCESS_DENIED, ERROR_PIPE_BUSY, ERROR_PIPE_CONNECTED, HANDLE,
+    HLOCAL,
+};
+use windows::Win32::Security::Authorization::{
+    ConvertSidToStringSidW, ConvertStringSecurityDescriptorToSecurityDescriptorW, SDDL_REVISION_1,
+};
+use windows::Win32::Security::{
+    GetTokenInformation, TokenUser, PSECURITY_DESCRIPTOR, SECURITY_ATTRIBUTES, TOKEN_QUERY,
+    TOKEN_USER,
+};
+use windows::Win32::Storage::FileSystem::{FILE_FLAG_FIRST_PIPE_INSTANCE, PIPE_ACCESS_DUPLEX};
+use windows::Win32::System::Pipes::{
+    ConnectNamedPipe, CreateNamedPipeW, PIPE_READMODE_BYTE, PIPE_REJECT_REMOTE_CLIENTS,
+    PIPE_TYPE_BYTE, PIPE_UNLIMITED_INSTANCES, PIPE_WAIT,
+};
+use windows::Win32::System::Threading::{GetCurrentProcess, OpenProcessToken};
+
+use crate::control::Endpoint;
+
+const BUFFER_SIZE: u32 = 4096;
+/* how often a client retries while every instance of the pipe is connected. */
+const BUSY_RETRIES: usize = 10;
+
+/*
+The server end of the control channel, every accepted connection gets its own pipe instance.
+The instance for the next client is created before a connected one is handed off, so there is no moment
+without a waiting instance in which a client would find no pipe and think hidden_tb is not running.
+The pipe name is easy to guess, so the first instance must be ours and every instance only lets the current user in.
+*/
+pub struct Listener {
+    name: HSTRING,
+    security: SecurityDescriptor,
+    pending: Mutex<File>,
+}
+
+impl Listener {
+    pub fn bind(endpoint: &Endpoint) -> io::Result<Self> {
+        let name = HSTRING::from(endpoint.name.as_str());
+        let security = SecurityDescriptor::for_current_user()?;
+        let pending = create_instance(&name, &security, true).map_err(|e| {
+            match e.raw_os_error() == Some(ERROR_ACCESS_DENIED.0 as i32) {
+                /* another process, maybe of another user, created the pipe first */
+                true => io::Error::new(
+                    io::ErrorKind::AlreadyExists,
+                    format!("{} is already used by another process", endpoint.name),
+                ),
+                false => e,
+            }
+        })?;
+        Ok(Listener {
+            name,
+            security,
+            pending: Mutex::new(pending),
+        })
+    }
+
+    /* blocks until a client connects to the waiting instance of the pipe. */
+    pub fn accept(&self) -> io::Result<File> {
+        let mut pending = self.pending.lock().expect("pending pipe instance poisoned");
+        /* Safety: the handle is owned by the pending file, which stays open during the call. */
+        if let Err(e) = unsafe { ConnectNamedPipe(HANDLE(pending.as_raw_handle() as _), None) } {
+            /* the client connected between creating and connecting the pipe */
+            if e.code() != ERROR_PIPE_CONNECTED.to_hresult() {
+                return Err(e.into());
+            }
+        }
+        let next = create_instance(&self.name, &self.security, false)?;
+        Ok(std::mem::replace(&mut *pending, next))
+    }
+}
+
+/* {first} fails with access denied if an instance of the pipe exists already. */
+fn create_instance(name: &HSTRING, security: &SecurityDescriptor, first: bool) -> io::Result<File> {
+    let mut open_mode = PIPE_ACCESS_DUPLEX;
+    if first {
+        open_mode |= FILE_FLAG_FIRST_PIPE_INSTANCE;
+    }
+    let attributes = SECURITY_ATTRIBUTES {
+        nLength: mem::size_of::<SECURITY_ATTRIBUTES>() as u32,
+        lpSecurityDescriptor: security.0 .0,
+        bInheritHandle: false.into(),
+    };
+    /*
+    Safety: the name is a valid null terminated string and the security descriptor outlives the call.
+    The handle is owned by the returned file.
+    */
+    unsafe {
+        let pipe = CreateNamedPipeW(
+            name,
+            open_mode,
+            PIPE_TYPE_BYTE | PIPE_READMODE_BYTE | PIPE_WAIT | PIPE_REJECT_REMOTE_CLIENTS,
+            PIPE_UNLIMITED_INSTANCES,
+            BUFFER_SIZE,
+            BUFFER_SIZE,
+            0,
+            Some(&attributes as *const SECURITY_ATTRIBUTES),
+        );
+        if pipe.is_invalid() {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(File::from_raw_handle(pipe.0 as RawHandle))
+    }
+}
+
+/* a security descriptor whose dacl only grants the current user access. */
+struct SecurityDescriptor(PSECURITY_DESCRIPTOR);
+
+/* Safety: the descriptor is only read after it was created and freed once on drop. */
+unsafe impl Send for SecurityDescriptor {}
+
+impl SecurityDescriptor {
+    fn for_current_user() -> io::Result<Self> {
+        let sddl = format!("D:P(A;;GA;;;{})", current_user_sid()?);
+        let mut descriptor = PSECURITY_DESCRIPTOR::default();
+        /* Safety: the sddl is a valid null terminated string, the descriptor is freed on drop. */
+        unsafe {
+            ConvertStringSecurityDescriptorToSecurityDescriptorW(
+                &HSTRING::from(sddl),
+                SDDL_REVISION_1,
+                &mut descriptor,
+                None,
+            )?;
+        }
+        Ok(SecurityDescriptor(descriptor))
+    }
+}
+
+impl Drop for SecurityDescriptor {
+    fn drop(&mut self) {
+        /* Safety: the descriptor was allocated by ConvertStringSecurityDescriptorToSecurityDescriptorW. */
+        unsafe {
+            LocalFree(HLOCAL(self.0 .0));
+        }
+    }
+}
+
+/* the sid of the user running hidden_tb as a string, e.g. "S-1-5-21-...". */
+fn current_user_sid() -> io::Result<String> {
+    /* Safety: the token is closed before returning, the buffer is u64 aligned and as large as asked for. */
+    unsafe {
+        let mut token = HANDLE::default();
+        OpenProcessToken(GetCurrentProcess(), TOKEN_QUERY, &mut token)?;
+        let mut length = 0;
+        /* the first call only asks for the size of the buffer and fails */
+        GetTokenInformation(token, TokenUser, None, 0, &mut length).ok();
+        let mut buffer = vec![0u64; (length as usize).div_ceil(8)];
+        let result = GetTokenInformation(
+            token,
+            TokenUser,
+            Some(buffer.as_mut_ptr() as *mut c_void),
+            length,
+            &mut length,
+        );
+        CloseHandle(token).ok();
+        result?;
+        let user = &*(buffer.as_ptr() as *const TOKEN_USER);
+        let mut sid = PWSTR::null();
+        ConvertSidToStringSidW(user.User.Sid, &mut sid)?;
+        let text = sid.to_string();
+        LocalFree(HLOCAL(sid.0 as *mut c_void));
+        text.map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+}
+
+/* opens the client end of the pipe, a pipe is read and written like a file. */
+pub fn connect(endpoint: &Endpoint) -> io::Result<File> {
+    let mut retries = 0;
+    loop {
+        match OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(&endpoint.name)
+        {
+            Err(e)
+                if e.raw_os_error() == Some(ERROR_PIPE_BUSY.0 as i32) && retries < BUSY_RETRIES =>
+            {
+                retries += 1;
+                thread::sleep(time::Duration::from_millis(50));
+            }
+            result => return result,
+        }
+    }
+}
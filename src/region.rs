@@ -0,0 +1,158 @@
+use crate::geometry::{Dpi, Edge, Logical, Physical, Rect};
+use crate::shell_backend::TaskbarData;
+use crate::tb_settings::TbSettings;
+
+/* same values as the win32 RGN_COMBINE_MODE constants. */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CombineMode {
+    And = 1,
+    Or = 2,
+    Xor = 3,
+    Diff = 4,
+    Copy = 5,
+}
+
+/* a rect with rounded corners in window coordinates of the taskbar. */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RoundedRect {
+    pub rect: Rect,
+    pub corner_width: Physical,
+    pub corner_height: Physical,
+}
+
+impl RoundedRect {
+    pub fn transposed(&self) -> RoundedRect {
+        RoundedRect {
+            rect: self.rect.transposed(),
+            corner_width: self.corner_height,
+            corner_height: self.corner_width,
+        }
+    }
+}
+
+/* the shape of the taskbar window: the base rect combined in order with each following rect. */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RegionSpec {
+    pub base: RoundedRect,
+    pub combines: Vec<(CombineMode, RoundedRect)>,
+}
+
+impl RegionSpec {
+    pub fn transposed(&self) -> RegionSpec {
+        RegionSpec {
+            base: self.base.transposed(),
+            combines: self
+                .combines
+                .iter()
+                .map(|(mode, rounded_rect)| (*mode, rounded_rect.transposed()))
+                .collect(),
+        }
+    }
+}
+
+fn show_tray(settings: &TbSettings, hovering_over_tray: bool) -> bool {
+    if settings.get_dynamic_borders_show_tray() {
+        return true;
+    }
+    settings.get_dynamic_borders_show_tray_if_disabled_on_hover() && hovering_over_tray
+}
+
+/*
+Computes the dynamic borders island of the applist and, if shown, the tray island.
+All rects are physical screen coordinates of the taskbar handles, the margins and corners are logical pixels
+converted with the {dpi} of the taskbar. The result is in physical pixels relative to the taskbar window.
+*/
+pub fn create_rounded_region(
+    settings: &TbSettings,
+    taskbar_rect: &Rect,
+    tray_rect: Option<&Rect>,
+    applist_rect: &Rect,
+    dpi: Dpi,
+    hovering_over_tray: bool,
+    _hovering_over_widgets: bool,
+) -> RegionSpec {
+    let px = |length: Logical| dpi.to_physical(length).0;
+    let top = px(Logical(1) + settings.get_margin_top());
+    let bottom = taskbar_rect.height() + px(Logical(1) - settings.get_margin_bottom());
+    let corners = dpi.to_physical(settings.get_rounded_corners_size());
+    /* the region is relative to the window, taskbars on secondary monitors do not start at 0 */
+    let left = taskbar_rect.left;
+
+    let applist_island = RoundedRect {
+        rect: Rect::new(
+            applist_rect.left - left
+                + px(settings.get_margin_left() - settings.get_margin_offset_left()),
+            top,
+            applist_rect.right - left
+                + px(Logical(2) + settings.get_margin_offset_right() - settings.get_margin_right()),
+            bottom,
+        ),
+        corner_width: corners,
+        corner_height: corners,
+    };
+
+    let mut combines = Vec::new();
+    if let Some(tray_rect) = tray_rect.filter(|_| show_tray(settings, hovering_over_tray)) {
+        let tray_island = RoundedRect {
+            rect: Rect::new(
+                tray_rect.left - left + px(settings.get_margin_left() - Logical(3)),
+                top,
+                tray_rect.right - left - px(settings.get_margin_right()),
+                bottom,
+            ),
+            corner_width: corners,
+            corner_height: corners,
+        };
+        combines.push((CombineMode::Or, tray_island));
+    }
+
+    RegionSpec {
+        base: applist_island,
+        combines,
+    }
+}
+
+/*
+None if the taskbar or applist handle is missing. Secondary taskbars have no tray.
+A taskbar docked to the left or right is computed transposed, so the top and bottom margins apply to its left and right side.
+*/
+pub fn create_rounded_region_for_taskbar(
+    settings: &TbSettings,
+    tb_data: &TaskbarData,
+    edge: Edge,
+    hovering_over_tray: bool,
+    hovering_over_widgets: bool,
+) -> Option<RegionSpec> {
+    let transpose = |rect: &Rect| match edge.is_vertical() {
+        true => rect.transposed(),
+        false => *rect,
+    };
+    let taskbar = transpose(&tb_data.taskbar.as_ref()?.rect);
+    let tray = tb_data.tray.as_ref().map(|tray| transpose(&tray.rect));
+    let applist = transpose(&tb_data.applist.as_ref()?.rect);
+    let region = create_rounded_region(
+        settings,
+        &taskbar,
+        tray.as_ref(),
+        &applist,
+        tb_data.dpi,
+        hovering_over_tray,
+        hovering_over_widgets,
+    );
+    match edge.is_vertical() {
+        true => Some(region.transposed()),
+        false => Some(region),
+    }
+}
+
+/* a region that covers the whole taskbar, used while dynamic borders are disabled at runtime. */
+pub fn create_full_region(taskbar_rect: &Rect) -> RegionSpec {
+    RegionSpec {
+        base: RoundedRect {
+            rect: Rect::new(0, 0, taskbar_rect.width() + 1, taskbar_rect.height() + 1),
+            corner_width: Physical(0),
+            corner_height: Physical(0),
+        },
+        combines: Vec::new(),
+    }
+}
@@ -53,27 +53,8 @@ unsafe fn find_process_path(process_name: &str) -> Option<PathBuf> {
         );
 
         if current_name.to_lowercase() == process_name.to_lowercase() {
-            let process_handle = OpenProcess(
-                PROCESS_QUERY_LIMITED_INFORMATION,
-                false,
-                process_entry.th32ProcessID,
-            );
-
-            if let Ok(process_handle) = process_handle {
-                let mut buffer = [0u16; MAX_PATH as usize];
-                let mut size = buffer.len() as u32;
-
-                if QueryFullProcessImageNameW(
-                    process_handle,
-                    PROCESS_NAME_FORMAT(0),
-                    PWSTR(buffer.as_mut_ptr()),
-                    &mut size,
-                )
-                .is_ok()
-                {
-                    let path = String::from_utf16_lossy(&buffer[..size as usize]);
-                    return Some(PathBuf::from(path));
-                }
+            if let Some(path) = query_process_path(process_entry.th32ProcessID) {
+                return Some(path);
             }
         }
 
@@ -85,6 +66,29 @@ unsafe fn find_process_path(process_name: &str) -> Option<PathBuf> {
     None
 }
 
+/* full executable path of the process with the id {process_id}. */
+pub fn get_process_path(process_id: u32) -> Option<PathBuf> {
+    unsafe { query_process_path(process_id) }
+}
+
+unsafe fn query_process_path(process_id: u32) -> Option<PathBuf> {
+    let process_handle = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, process_id).ok()?;
+
+    let mut buffer = [0u16; MAX_PATH as usize];
+    let mut size = buffer.len() as u32;
+
+    let result = QueryFullProcessImageNameW(
+        process_handle,
+        PROCESS_NAME_FORMAT(0),
+        PWSTR(buffer.as_mut_ptr()),
+        &mut size,
+    );
+    CloseHandle(process_handle).ok();
+    result.ok()?;
+    let path = String::from_utf16_lossy(&buffer[..size as usize]);
+    Some(PathBuf::from(path))
+}
+
 unsafe fn terminate_processes(process_name: &str) -> Result<(), ()> {
     let Ok(h_snapshot) = CreateToolhelp32Snapshot(TH32CS_SNAPPROCESS, 0) else {
         return Err(());
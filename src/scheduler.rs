@@ -0,0 +1,414 @@
+use std::{thread, time};
+
+use serde_json::{Map, Value};
+
+use crate::control::{ControlServer, ErrorCode, Request, Response, Status};
+use crate::settings_validation::SettingsError;
+use crate::settings_watcher::SettingsWatcher;
+use crate::shell_backend::ShellBackend;
+use crate::signaling::Signaling;
+use crate::taskbar::Taskbar;
+use crate::taskbars::Taskbars;
+use crate::tb_settings::{AutohidePolicy, TbSettings};
+
+#[inline(always)]
+fn update_handles_of_tb<B: ShellBackend>(taskbar: &mut Taskbar<B>) {
+    let new_handles = taskbar.fetch_new_handles();
+    if !new_handles.contains_none() {
+        taskbar.insert_handles(new_handles);
+    }
+}
+
+#[inline(always)]
+fn infrequent_routine<B: ShellBackend + Clone>(
+    settings: &TbSettings,
+    taskbars: &mut Taskbars<B>,
+    update_handles_in_infrequent_routine: &bool,
+) {
+    taskbars.sync_monitors();
+    if settings.get_autohide() && settings.get_autohide_policy() == AutohidePolicy::Intellihide {
+        for taskbar in taskbars.iter_mut() {
+            taskbar.update_window_overlap();
+        }
+    }
+    if settings.get_autohide() || settings.get_enable_dynamic_borders() {
+        if settings.get_autohide() {
+            taskbars
+                .primary_mut()
+                .check_and_update_workspace_region_for_autohide();
+        }
+        for taskbar in taskbars.iter_mut() {
+            taskbar.check_and_set_taskbar_transparency_state();
+            if settings.get_enable_dynamic_borders() {
+                taskbar.call_dynamic_update(taskbar.is_hovering_on_tray(), false);
+            }
+        }
+    }
+    if *update_handles_in_infrequent_routine {
+        for taskbar in taskbars.iter_mut() {
+            update_handles_of_tb(taskbar);
+        }
+    }
+}
+
+#[inline(always)]
+fn check_and_init_taskbar_state<B: ShellBackend>(
+    settings: &TbSettings,
+    taskbar: &mut Taskbar<B>,
+) -> bool {
+    if (settings.get_autohide() || settings.get_enable_dynamic_borders())
+        && !taskbar.check_and_set_taskbar_transparency_state()
+    {
+        return false;
+    }
+    true
+}
+
+#[inline(always)]
+fn init_tb_state_routine<B: ShellBackend>(
+    settings: &TbSettings,
+    taskbar: &mut Taskbar<B>,
+    signaling: &Signaling,
+) {
+    let mut infr_cnter = 0;
+    while !check_and_init_taskbar_state(settings, taskbar) && !signaling.get_exit_called() {
+        infr_cnter += 1;
+        thread::sleep(time::Duration::from_millis(100));
+        if infr_cnter > 100 {
+            eprintln!("Could not initialize taskbar state, trying to refresh the handles");
+            infr_cnter = 0;
+            taskbar.refresh_handles();
+        }
+    }
+}
+
+fn describe(errors: &[SettingsError]) -> String {
+    errors
+        .iter()
+        .map(|error| error.to_string())
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+/* Scheduling of the taskbar routines. Every tick handles the taskbar state, every infrequent_count ticks the lower priority checks run. */
+pub struct Scheduler {
+    sleep_duration: time::Duration,
+    infrequent_counter: usize,
+    update_handles_in_infrequent_routine: bool,
+    settings_watcher: Option<SettingsWatcher>,
+    control_server: Option<ControlServer>,
+    /* the saved settings while a preview is applied, None while the applied settings are the saved ones. */
+    saved_settings: Option<TbSettings>,
+}
+
+//handles have to be updated on every loop if a merging option is enabled, to react to applist changes.
+fn update_handles_in_infrequent_routine(settings: &TbSettings) -> bool {
+    !(settings.get_merge_tray()
+        || settings.get_merge_widgets()
+        || settings.get_enable_dynamic_borders())
+}
+
+impl Scheduler {
+    pub fn new(settings: &TbSettings) -> Self {
+        Scheduler {
+            sleep_duration: time::Duration::from_millis(settings.get_sleep_time_in_ms()),
+            infrequent_counter: 0,
+            update_handles_in_infrequent_routine: update_handles_in_infrequent_routine(settings),
+            settings_watcher: None,
+            control_server: None,
+            saved_settings: None,
+        }
+    }
+
+    /* the main loop reloads the settings whenever the watched file changes. */
+    pub fn watch_settings(&mut self, settings_watcher: SettingsWatcher) {
+        self.settings_watcher = Some(settings_watcher);
+    }
+
+    /* the main loop answers the requests of the control channel, e.g. the previews of the settings ui. */
+    pub fn listen(&mut self, control_server: ControlServer) {
+        self.control_server = Some(control_server);
+    }
+
+    /* applies reloaded settings to the loop and every taskbar. The hidden state of the taskbars is kept. */
+    pub fn apply_settings<B: ShellBackend + Clone>(
+        &mut self,
+        settings: &TbSettings,
+        taskbars: &mut Taskbars<B>,
+    ) {
+        self.sleep_duration = time::Duration::from_millis(settings.get_sleep_time_in_ms());
+        self.update_handles_in_infrequent_routine = update_handles_in_infrequent_routine(settings);
+        /* run the infrequent routine on the next tick to pick up changed handles and styles */
+        self.infrequent_counter = 0;
+        taskbars.apply_settings(settings);
+    }
+
+    /* polls the settings file with the infrequent routine, or right away if a reload was requested. */
+    fn poll_settings(
+        &mut self,
+        settings: &TbSettings,
+        signaling: &Signaling,
+    ) -> Option<TbSettings> {
+        if !signaling.take_reload_called()
+            && !self
+                .infrequent_counter
+                .is_multiple_of(settings.get_infrequent_count())
+        {
+            return None;
+        }
+        self.settings_watcher.as_mut()?.poll()
+    }
+
+    /*
+    Applies {values} over the saved settings without saving them, {settings} are the applied settings.
+    Each preview starts from the saved settings, so a value that is no longer previewed goes back to its saved value.
+    */
+    pub fn preview<B: ShellBackend + Clone>(
+        &mut self,
+        values: &Map<String, Value>,
+        settings: &mut TbSettings,
+        taskbars: &mut Taskbars<B>,
+    ) -> Result<(), Vec<SettingsError>> {
+        let saved = self
+            .saved_settings
+            .clone()
+            .unwrap_or_else(|| settings.clone());
+        let mut previewed = saved.clone();
+        let errors: Vec<_> = values
+            .iter()
+            .filter_map(|(key, value)| previewed.set_value(key, value.clone()).err())
+            .collect();
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+        previewed.validate()?;
+        self.apply_settings(&previewed, taskbars);
+        *settings = previewed;
+        self.saved_settings = Some(saved);
+        Ok(())
+    }
+
+    /* drops the preview. A settings file saved in the meantime is applied right away instead of the old saved settings. */
+    pub fn revert<B: ShellBackend + Clone>(
+        &mut self,
+        settings: &mut TbSettings,
+        taskbars: &mut Taskbars<B>,
+    ) {
+        let saved = self.saved_settings.take();
+        let reloaded = self
+            .settings_watcher
+            .as_mut()
+            .and_then(SettingsWatcher::poll);
+        if let Some(saved) = reloaded.or(saved) {
+            self.apply_settings(&saved, taskbars);
+            *settings = saved;
+        }
+    }
+
+    /* saves {saved} to the watched settings file and applies it, a preview is dropped. */
+    fn store<B: ShellBackend + Clone>(
+        &mut self,
+        saved: TbSettings,
+        settings: &mut TbSettings,
+        taskbars: &mut Taskbars<B>,
+    ) -> Response {
+        if let Some(settings_watcher) = self.settings_watcher.as_mut() {
+            if let Err(e) = settings_watcher.save(&saved) {
+                return Response::error(ErrorCode::SettingsFile, e);
+            }
+        }
+        self.saved_settings = None;
+        self.apply_settings(&saved, taskbars);
+        *settings = saved;
+        Response::ok()
+    }
+
+    pub fn status<B: ShellBackend + Clone>(
+        &self,
+        settings: &TbSettings,
+        taskbars: &Taskbars<B>,
+    ) -> Status {
+        Status {
+            active_profile: settings.get_active_profile().to_string(),
+            hidden: taskbars.primary().is_hidden(),
+            pinned: taskbars.is_pinned(),
+            previewing: self.saved_settings.is_some(),
+            taskbars: 1 + taskbars.secondaries().len(),
+            settings_file: self
+                .settings_watcher
+                .as_ref()
+                .map(|settings_watcher| settings_watcher.get_path().display().to_string()),
+        }
+    }
+
+    /* answers a request of the control channel, {settings} are the applied settings. */
+    pub fn handle_request<B: ShellBackend + Clone>(
+        &mut self,
+        request: &Request,
+        settings: &mut TbSettings,
+        taskbars: &mut Taskbars<B>,
+        signaling: &Signaling,
+    ) -> Response {
+        let saved = self
+            .saved_settings
+            .clone()
+            .unwrap_or_else(|| settings.clone());
+        match request {
+            Request::Show | Request::Hide => {
+                taskbars.request_visibility(*request == Request::Show);
+                Response::ok()
+            }
+            Request::Pin | Request::Unpin => {
+                taskbars.set_pinned(*request == Request::Pin);
+                Response::ok()
+            }
+            Request::Reload => {
+                let reloaded = match self.settings_watcher.as_mut() {
+                    Some(settings_watcher) => settings_watcher.reload(),
+                    None => Ok(saved),
+                };
+                match reloaded {
+                    Ok(reloaded) => {
+                        self.saved_settings = None;
+                        self.apply_settings(&reloaded, taskbars);
+                        *settings = reloaded;
+                        Response::ok()
+                    }
+                    Err(e) => Response::error(ErrorCode::SettingsFile, e),
+                }
+            }
+            Request::Status => Response::with_status(self.status(settings, taskbars)),
+            Request::Set { key, value } => {
+                let mut changed = saved;
+                if let Err(e) = changed.set_value(key, value.clone()) {
+                    return Response::error(ErrorCode::InvalidValue, e.to_string());
+                }
+                if let Err(errors) = changed.validate() {
+                    return Response::error(ErrorCode::InvalidValue, describe(&errors));
+                }
+                self.store(changed, settings, taskbars)
+            }
+            Request::Profile { name } => match saved.with_profile(name) {
+                Ok(profile) => self.store(profile, settings, taskbars),
+                Err(errors) => {
+                    let code = match errors.iter().any(|error| error.field == "active_profile") {
+                        true => ErrorCode::UnknownProfile,
+                        false => ErrorCode::InvalidValue,
+                    };
+                    Response::error(code, describe(&errors))
+                }
+            },
+            Request::Quit => {
+                signaling.set_exit_called(true);
+                Response::ok()
+            }
+            Request::Preview { values } => match self.preview(values, settings, taskbars) {
+                Ok(()) => Response::ok(),
+                Err(errors) => Response::error(ErrorCode::InvalidValue, describe(&errors)),
+            },
+            Request::Revert => {
+                self.revert(settings, taskbars);
+                Response::ok()
+            }
+        }
+    }
+
+    /* answers every request that arrived since the last tick. */
+    fn handle_requests<B: ShellBackend + Clone>(
+        &mut self,
+        settings: &mut TbSettings,
+        taskbars: &mut Taskbars<B>,
+        signaling: &Signaling,
+    ) {
+        while let Some(pending) = self
+            .control_server
+            .as_ref()
+            .and_then(ControlServer::try_next)
+        {
+            let response = self.handle_request(&pending.request, settings, taskbars, signaling);
+            pending.respond(response);
+        }
+    }
+
+    /*
+    waits until all wanted handles of the primary taskbar are available and the taskbar is prepared for transparency.
+    Secondary taskbars are picked up without waiting, missing ones are looked up again in the infrequent routine.
+    */
+    pub fn init<B: ShellBackend + Clone>(
+        &self,
+        settings: &TbSettings,
+        taskbars: &mut Taskbars<B>,
+        signaling: &Signaling,
+    ) {
+        let taskbar = taskbars.primary_mut();
+        // wait until all handles are available
+        while taskbar.contains_none() && !signaling.get_exit_called() {
+            taskbar.print_which_is_none();
+            eprintln!("Waiting for handles...");
+            thread::sleep(time::Duration::from_millis(100));
+            taskbar.refresh_handles();
+        }
+
+        println!("got handles, initializing taskbar state");
+        init_tb_state_routine(settings, taskbar, signaling);
+
+        taskbar.automation_routine();
+        taskbar.send_restarts();
+        taskbars.sync_monitors();
+    }
+
+    /* runs one iteration of the main loop without sleeping. */
+    pub fn tick<B: ShellBackend + Clone>(
+        &mut self,
+        settings: &TbSettings,
+        taskbars: &mut Taskbars<B>,
+    ) {
+        self.infrequent_counter %= settings.get_infrequent_count();
+        if self.infrequent_counter == 0 {
+            infrequent_routine(
+                settings,
+                taskbars,
+                &self.update_handles_in_infrequent_routine,
+            );
+        }
+
+        let foreground = taskbars.update_foreground_window().cloned();
+        for taskbar in taskbars.iter_mut() {
+            if !self.update_handles_in_infrequent_routine {
+                update_handles_of_tb(taskbar);
+            }
+            taskbar.handle_taskbar_state(foreground.as_ref());
+        }
+
+        self.infrequent_counter += 1;
+    }
+
+    pub fn run<B: ShellBackend + Clone>(
+        &mut self,
+        settings: &TbSettings,
+        taskbars: &mut Taskbars<B>,
+        signaling: &Signaling,
+    ) {
+        self.init(settings, taskbars, signaling);
+        let mut settings = settings.clone();
+        println!("entering main loop");
+        loop {
+            if signaling.get_exit_called() {
+                break;
+            }
+            if let Some(new_settings) = self.poll_settings(&settings, signaling) {
+                println!(
+                    "settings changed, applying profile {}",
+                    new_settings.get_active_profile()
+                );
+                self.apply_settings(&new_settings, taskbars);
+                settings = new_settings;
+                /* the saved file replaces a preview */
+                self.saved_settings = None;
+            }
+            self.handle_requests(&mut settings, taskbars, signaling);
+            self.tick(&settings, taskbars);
+            thread::sleep(self.sleep_duration);
+        }
+    }
+}
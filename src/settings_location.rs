@@ -0,0 +1,131 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use once_cell::sync::OnceCell;
+
+pub const FILE_NAME: &str = "settings.json";
+/* overrides the location of the settings file, unless --config is given. */
+pub const CONFIG_ENV_VAR: &str = "HIDDEN_TB_CONFIG";
+const CONFIG_ARGUMENT: &str = "--config";
+const APP_DIR_NAME: &str = "hidden_tb";
+
+/* where the path of the settings file came from. */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LocationSource {
+    Argument,
+    Environment,
+    UserConfigDir,
+    Portable,
+}
+
+impl fmt::Display for LocationSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text = match self {
+            LocationSource::Argument => "--config argument",
+            LocationSource::Environment => CONFIG_ENV_VAR,
+            LocationSource::UserConfigDir => "user config directory",
+            LocationSource::Portable => "next to the executable",
+        };
+        write!(f, "{}", text)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SettingsLocation {
+    pub path: PathBuf,
+    pub source: LocationSource,
+}
+
+impl fmt::Display for SettingsLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.path.display(), self.source)
+    }
+}
+
+impl SettingsLocation {
+    /*
+    Lookup order: the --config argument, the environment variable, the settings.json in the per-user config directory
+    and the portable settings.json next to the executable. The per-user file is used if it exists or no portable file exists,
+    so existing portable installs keep their settings and new installs never write into the install directory.
+    */
+    pub fn resolve(
+        config_argument: Option<PathBuf>,
+        environment: Option<PathBuf>,
+        user_config_dir: Option<PathBuf>,
+        exe_dir: Option<PathBuf>,
+    ) -> Self {
+        if let Some(path) = config_argument {
+            return Self::new(path, LocationSource::Argument);
+        }
+        if let Some(path) = environment.filter(|path| !path.as_os_str().is_empty()) {
+            return Self::new(path, LocationSource::Environment);
+        }
+        let user_file = user_config_dir.map(|dir| dir.join(APP_DIR_NAME).join(FILE_NAME));
+        let portable_file = exe_dir.map(|dir| dir.join(FILE_NAME));
+        match (user_file, portable_file) {
+            (Some(user_file), Some(portable_file))
+                if !user_file.exists() && portable_file.exists() =>
+            {
+                Self::new(portable_file, LocationSource::Portable)
+            }
+            (Some(user_file), _) => Self::new(user_file, LocationSource::UserConfigDir),
+            (None, Some(portable_file)) => Self::new(portable_file, LocationSource::Portable),
+            (None, None) => Self::new(PathBuf::from(FILE_NAME), LocationSource::Portable),
+        }
+    }
+
+    /* resolves the location for this process from its arguments and environment. */
+    pub fn from_env() -> Self {
+        let args: Vec<String> = std::env::args().collect();
+        Self::resolve(
+            config_argument(&args),
+            std::env::var_os(CONFIG_ENV_VAR).map(PathBuf::from),
+            user_config_dir(),
+            std::env::current_exe()
+                .ok()
+                .and_then(|exe| exe.parent().map(Path::to_path_buf)),
+        )
+    }
+
+    fn new(path: PathBuf, source: LocationSource) -> Self {
+        SettingsLocation { path, source }
+    }
+}
+
+/* the path after --config, given as "--config <path>" or "--config=<path>". */
+pub fn config_argument(args: &[String]) -> Option<PathBuf> {
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if arg == CONFIG_ARGUMENT {
+            return args.next().map(PathBuf::from);
+        }
+        if let Some(path) = arg.strip_prefix("--config=") {
+            return Some(PathBuf::from(path));
+        }
+    }
+    None
+}
+
+/* the roaming app data directory on windows, the xdg config directory elsewhere. */
+fn user_config_dir() -> Option<PathBuf> {
+    let non_empty = |name: &str| {
+        std::env::var_os(name)
+            .filter(|value| !value.is_empty())
+            .map(PathBuf::from)
+    };
+    if cfg!(windows) {
+        non_empty("APPDATA")
+    } else {
+        non_empty("XDG_CONFIG_HOME").or_else(|| non_empty("HOME").map(|home| home.join(".config")))
+    }
+}
+
+/* the location of this process, resolved once on first use. */
+pub fn get() -> &'static SettingsLocation {
+    static LOCATION: OnceCell<SettingsLocation> = OnceCell::new();
+    LOCATION.get_or_init(|| {
+        let location = SettingsLocation::from_env();
+        println!("settings file: {}", location);
+        location
+    })
+}
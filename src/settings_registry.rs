@@ -0,0 +1,146 @@
+use std::ops::RangeInclusive;
+
+use crate::tb_settings::SETTINGS;
+
+/* the group a setting belongs to, the settings ui shows the settings of a section together. */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Section {
+    Autohide,
+    DynamicBorders,
+    Performance,
+    Restarts,
+    Advanced,
+}
+
+impl Section {
+    pub const ALL: [Section; 5] = [
+        Section::Autohide,
+        Section::DynamicBorders,
+        Section::Performance,
+        Section::Restarts,
+        Section::Advanced,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Section::Autohide => "Autohide",
+            Section::DynamicBorders => "Dynamic borders",
+            Section::Performance => "Performance",
+            Section::Restarts => "Restarts",
+            Section::Advanced => "Advanced",
+        }
+    }
+}
+
+/* how a setting is stored in the file and edited in the settings ui. */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Kind {
+    Toggle,
+    /*
+    A whole number, {unsigned} if its type has no negative values. Values outside of {min}..={max} are invalid
+    for {reason}, None is unbounded. The slider of the settings ui covers {slider} in steps of {step}.
+    */
+    Number {
+        unsigned: bool,
+        min: Option<i64>,
+        max: Option<i64>,
+        reason: &'static str,
+        slider: (i64, i64),
+        step: f64,
+    },
+    /* one of the (value in the file, label) pairs. */
+    Choice(&'static [(&'static str, &'static str)]),
+    Easing,
+    /* a list of names, one per line in the settings ui. */
+    List,
+    ForegroundRules,
+}
+
+/* everything hidden_tb knows about a setting besides its value. */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Setting {
+    /* the key in settings.json. */
+    pub key: &'static str,
+    pub label: &'static str,
+    pub description: &'static str,
+    pub section: Section,
+    pub kind: Kind,
+    /* the setting is only used while the toggle {enabled_by} is on, the settings ui hides it otherwise. */
+    pub enabled_by: Option<&'static str>,
+    /* a running hidden_tb only applies a change after a restart. */
+    pub restart_needed: bool,
+    /* not shown in the settings ui, e.g. features that are not implemented yet. */
+    pub hidden: bool,
+}
+
+impl Setting {
+    /* base of the registry entries, they set at least the label, the description and the section. */
+    pub const NEW: Setting = Setting {
+        key: "",
+        label: "",
+        description: "",
+        section: Section::Advanced,
+        kind: Kind::Toggle,
+        enabled_by: None,
+        restart_needed: false,
+        hidden: false,
+    };
+
+    /* true if the key, label or description contains {search}, ignoring case. An empty search matches everything. */
+    pub fn matches(&self, search: &str) -> bool {
+        let search = search.trim().to_lowercase();
+        [self.key, self.label, self.description]
+            .iter()
+            .any(|text| text.to_lowercase().contains(&search))
+    }
+
+    /* the range of the settings ui slider of a number, limited to the allowed values. */
+    pub fn slider_range(&self) -> Option<RangeInclusive<i64>> {
+        match self.kind {
+            Kind::Number {
+                min, max, slider, ..
+            } => {
+                let start = min.map_or(slider.0, |min| slider.0.max(min));
+                let end = max.map_or(slider.1, |max| slider.1.min(max));
+                Some(start..=end.max(start))
+            }
+            _ => None,
+        }
+    }
+
+    /* the allowed values as they are shown in errors, e.g. "1..=255". */
+    pub fn allowed(&self) -> String {
+        match self.kind {
+            Kind::Toggle => "true or false".to_string(),
+            Kind::Number { min, max, .. } => match (min, max) {
+                (Some(min), Some(max)) => format!("{}..={}", min, max),
+                (Some(min), None) => format!("{}..", min),
+                (None, Some(max)) => format!("..={}", max),
+                (None, None) => "a whole number".to_string(),
+            },
+            Kind::Choice(choices) => choices
+                .iter()
+                .map(|(value, _)| *value)
+                .collect::<Vec<_>>()
+                .join(", "),
+            Kind::Easing => "linear, ease_in, ease_out, ease_in_out or a cubic_bezier".to_string(),
+            Kind::List => "a list of names".to_string(),
+            Kind::ForegroundRules => "a list of foreground rules".to_string(),
+        }
+    }
+}
+
+/* every setting in the order of the settings file. */
+pub fn all() -> &'static [Setting] {
+    SETTINGS
+}
+
+pub fn find(key: &str) -> Option<&'static Setting> {
+    SETTINGS.iter().find(|setting| setting.key == key)
+}
+
+pub fn in_section(section: Section) -> impl Iterator<Item = &'static Setting> {
+    SETTINGS
+        .iter()
+        .filter(move |setting| setting.section == section)
+}
@@ -1,6 +1,17 @@
-use crate::tb_settings::TbSettings;
+use std::sync::mpsc::{self, Sender};
+use std::thread;
+
+use crate::animation::Easing;
+use crate::control::{self, Endpoint, Request, Response};
+use crate::settings_location;
+use crate::settings_registry::{self, Kind, Section, Setting};
+use crate::settings_validation;
+use crate::tb_settings::{ForegroundRule, RuleAction, TbSettings};
 use eframe::egui;
 use egui::FontId;
+use serde_json::{Map, Value};
+
+const SPACING: f32 = 10.0;
 
 fn load_icon(path: &str) -> eframe::IconData {
     let (icon_rgba, icon_width, icon_height) = {
@@ -40,506 +51,521 @@ pub fn open_ui() {
     );
 }
 
-pub struct TbAccessibleSettings {
-    autohide: bool,
-    workspace_offset_top: u32,
-    merge_tray: bool,
-    merge_widgets: bool,
-    sleep_time_in_ms: u64,
-    animation_time_in_ms: u64,
-    animation_steps: u8,
-    infrequent_count: usize,
-    tb_rect_bottom_offset: i32,
-    tb_rect_detection_size_in_pixel: i32,
-    enable_dynamic_borders: bool,
-    dynamic_borders_show_tray: bool,
-    dynamic_borders_show_tray_if_disabled_on_hover: bool,
-    dynamic_borders_show_widgets: bool,
-    dynamic_borders_show_widgets_if_disabled_on_hover: bool,
-    rounded_corners_size: i32,
-    margin_left: i32,
-    margin_right: i32,
-    margin_bottom: i32,
-    margin_top: i32,
-    margin_offset_left: i32,
-    margin_offset_right: i32,
-    windows_11_bugfix: bool,
-    restart_executables: Vec<String>,
-}
-
-impl TbAccessibleSettings {
-    fn from(settings: &TbSettings) -> Self {
-        Self {
-            autohide: settings.get_autohide(),
-            workspace_offset_top: settings.get_workspace_offset_top(),
-            sleep_time_in_ms: settings.get_sleep_time_in_ms(),
-            merge_tray: settings.get_merge_tray(),
-            merge_widgets: settings.get_merge_widgets(),
-            animation_time_in_ms: settings.get_animation_time_in_ms(),
-            animation_steps: settings.get_animation_steps(),
-            infrequent_count: settings.get_infrequent_count(),
-            tb_rect_bottom_offset: settings.get_tb_rect_bottom_offset(),
-            tb_rect_detection_size_in_pixel: settings.get_tb_rect_detection_size_in_pixel(),
-            enable_dynamic_borders: settings.get_enable_dynamic_borders(),
-            dynamic_borders_show_tray: settings.get_dynamic_borders_show_tray(),
-            dynamic_borders_show_tray_if_disabled_on_hover: settings
-                .get_dynamic_borders_show_tray_if_disabled_on_hover(),
-            dynamic_borders_show_widgets: settings.get_dynamic_borders_show_widgets(),
-            dynamic_borders_show_widgets_if_disabled_on_hover: settings
-                .get_dynamic_borders_show_widgets_if_disabled_on_hover(),
-            rounded_corners_size: settings.get_rounded_corners_size(),
-            margin_left: settings.get_margin_left(),
-            margin_right: settings.get_margin_right(),
-            margin_bottom: settings.get_margin_bottom(),
-            margin_top: settings.get_margin_top(),
-            margin_offset_left: settings.get_margin_offset_left(),
-            margin_offset_right: settings.get_margin_offset_right(),
-            windows_11_bugfix: settings.get_windows_11_bugfix(),
-            restart_executables: settings.get_restart_executables(),
-        }
-    }
-
-    fn is_equal(&self, settings: &TbSettings) -> bool {
-        self.autohide == settings.get_autohide()
-            && self.merge_tray == settings.get_merge_tray()
-            && self.merge_widgets == settings.get_merge_widgets()
-            && self.sleep_time_in_ms == settings.get_sleep_time_in_ms()
-            && self.animation_time_in_ms == settings.get_animation_time_in_ms()
-            && self.animation_steps == settings.get_animation_steps()
-            && self.infrequent_count == settings.get_infrequent_count()
-            && self.tb_rect_bottom_offset == settings.get_tb_rect_bottom_offset()
-            && self.tb_rect_detection_size_in_pixel
-                == settings.get_tb_rect_detection_size_in_pixel()
-            && self.enable_dynamic_borders == settings.get_enable_dynamic_borders()
-            && self.dynamic_borders_show_tray == settings.get_dynamic_borders_show_tray()
-            && self.dynamic_borders_show_tray_if_disabled_on_hover
-                == settings.get_dynamic_borders_show_tray_if_disabled_on_hover()
-            && self.dynamic_borders_show_widgets == settings.get_dynamic_borders_show_widgets()
-            && self.dynamic_borders_show_widgets_if_disabled_on_hover
-                == settings.get_dynamic_borders_show_widgets_if_disabled_on_hover()
-            && self.rounded_corners_size == settings.get_rounded_corners_size()
-            && self.margin_left == settings.get_margin_left()
-            && self.margin_right == settings.get_margin_right()
-            && self.margin_bottom == settings.get_margin_bottom()
-            && self.margin_top == settings.get_margin_top()
-            && self.margin_offset_left == settings.get_margin_offset_left()
-            && self.margin_offset_right == settings.get_margin_offset_right()
-            && self.workspace_offset_top == settings.get_workspace_offset_top()
-            && self.windows_11_bugfix == settings.get_windows_11_bugfix()
-    }
-}
-
 struct MyApp {
+    /* the settings as they are saved in the file */
     global_settings: TbSettings,
-    settings: TbAccessibleSettings,
+    /* the edited settings, written to the file by the save button */
+    settings: TbSettings,
     font_id: FontId,
     small_font_id: FontId,
     info_string: egui::widget_text::RichText,
+    new_profile_name: String,
+    defaults: TbSettings,
+    /* the tab that is shown while nothing is searched */
+    section: Section,
+    search: String,
+    /* the changed values the running hidden_tb currently previews */
+    previewed: Map<String, Value>,
+    preview_requests: Sender<Request>,
 }
 
 impl Default for MyApp {
     fn default() -> Self {
-        let tb_settings = TbSettings::new();
+        /* the running hidden_tb migrates or backs up the file, the settings ui only reads it */
+        let path = TbSettings::get_path();
+        let (tb_settings, warning) = TbSettings::load_read_only(&path);
         dbg!(&tb_settings);
+        /* invalid values were replaced by their defaults, show the ones of the file so they are highlighted */
+        let file_settings = match warning {
+            Some(_) => TbSettings::load_from(&path).unwrap_or(tb_settings.clone()),
+            None => tb_settings.clone(),
+        };
+        let small_font_id = FontId::monospace(17.0);
+        let info_string = match warning {
+            Some(warning) => egui::RichText::new(warning).font(small_font_id.clone()),
+            None => egui::widget_text::RichText::default(),
+        };
         Self {
-            global_settings: tb_settings.clone(),
-            settings: TbAccessibleSettings::from(&tb_settings),
+            global_settings: tb_settings,
+            settings: file_settings,
             font_id: FontId::monospace(22.0),
-            small_font_id: FontId::monospace(17.0),
-            info_string: egui::widget_text::RichText::default(),
+            small_font_id,
+            info_string,
+            new_profile_name: String::new(),
+            defaults: TbSettings::load_defaults(),
+            section: Section::Autohide,
+            search: String::new(),
+            previewed: Map::new(),
+            preview_requests: start_preview_thread(),
         }
     }
 }
 
 impl MyApp {
-    fn call_settings_update(&mut self) {
-        self.global_settings
-            .set_merge_tray(self.settings.merge_tray);
-        self.global_settings
-            .set_workspace_offset_top(self.settings.workspace_offset_top);
-        self.global_settings
-            .set_merge_widgets(self.settings.merge_widgets);
-        self.global_settings.set_autohide(self.settings.autohide);
-        self.global_settings
-            .set_animation_steps(self.settings.animation_steps);
-        self.global_settings
-            .set_animation_time_in_ms(self.settings.animation_time_in_ms);
-        self.global_settings
-            .set_sleep_time_in_ms(self.settings.sleep_time_in_ms);
-        self.global_settings
-            .set_infrequent_count(self.settings.infrequent_count);
-        self.global_settings
-            .set_tb_rect_bottom_offset(self.settings.tb_rect_bottom_offset);
-        self.global_settings
-            .set_tb_rect_detection_size_in_pixel(self.settings.tb_rect_detection_size_in_pixel);
-        self.global_settings
-            .set_enable_dynamic_borders(self.settings.enable_dynamic_borders);
-        self.global_settings
-            .set_dynamic_borders_show_tray(self.settings.dynamic_borders_show_tray);
-        self.global_settings
-            .set_dynamic_borders_show_widgets_if_disabled_on_hover(
-                self.settings
-                    .dynamic_borders_show_widgets_if_disabled_on_hover,
-            );
-        self.global_settings
-            .set_dynamic_borders_show_tray_if_disabled_on_hover(
-                self.settings.dynamic_borders_show_tray_if_disabled_on_hover,
-            );
-        self.global_settings
-            .set_dynamic_borders_show_widgets(self.settings.dynamic_borders_show_widgets);
-        self.global_settings
-            .set_rounded_corners_size(self.settings.rounded_corners_size);
-        self.global_settings
-            .set_margin_left(self.settings.margin_left);
-        self.global_settings
-            .set_margin_right(self.settings.margin_right);
-        self.global_settings
-            .set_margin_bottom(self.settings.margin_bottom);
-        self.global_settings
-            .set_margin_top(self.settings.margin_top);
-        self.global_settings
-            .set_margin_offset_left(self.settings.margin_offset_left);
-        self.global_settings
-            .set_margin_offset_right(self.settings.margin_offset_right);
-        self.global_settings
-            .set_windows_11_bugfix(self.settings.windows_11_bugfix);
-        self.global_settings
-            .set_restart_executables(self.settings.restart_executables.clone());
+    /* a setting is shown while the toggle it is enabled by, and every toggle that one depends on, is on. */
+    fn is_enabled(&self, setting: &Setting) -> bool {
+        let mut enabled_by = setting.enabled_by;
+        while let Some(key) = enabled_by {
+            if self.settings.get_value(key) != Some(Value::Bool(true)) {
+                return false;
+            }
+            enabled_by = settings_registry::find(key).and_then(|toggle| toggle.enabled_by);
+        }
+        true
     }
 
-    fn formatted_string(&self, str: &str) -> egui::widget_text::RichText {
-        egui::RichText::new(str).font(self.font_id.clone())
+    /* the settings of {section} that match the search, hidden ones and ones of a disabled toggle are left out. */
+    fn visible_settings(&self, section: Section) -> Vec<&'static Setting> {
+        settings_registry
+            ::in_section(section)
+            .filter(|setting| !setting.hidden && self.is_enabled(setting))
+            .filter(|setting| setting.matches(&self.search))
+            .collect()
     }
 
-    fn formatted_small_string(&self, str: &str) -> egui::widget_text::RichText {
-        egui::RichText::new(str).font(self.small_font_id.clone())
+    fn section_editor(&mut self, ui: &mut egui::Ui, settings: &[&'static Setting]) {
+        for setting in settings {
+            self.setting_editor(ui, setting);
+            if setting.key == "infrequent_count" {
+                ui.label(
+                    self.formatted_small_string(
+                        format!(
+                            "Current infrequent check timeout in seconds: {} s",
+                            (
+                                (self.settings.get_infrequent_count() *
+                                    (self.settings.get_sleep_time_in_ms() as usize)) as f64
+                            ) / 1000.0
+                        ).as_str()
+                    )
+                );
+            }
+            ui.add_space(SPACING);
+        }
     }
-}
 
-impl eframe::App for MyApp {
-    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        const SPACING: f32 = 10.0;
-
-        egui::CentralPanel::default().show(ctx, |ui| {
-            if !self.settings.is_equal(&self.global_settings) {
-                self.info_string = self.formatted_small_string("Currently unsaved settings");
+    /*
+    The label with the description as tooltip, a reset to default button and the widget of {setting},
+    the widget is picked by the kind of the setting.
+    */
+    fn setting_editor(&mut self, ui: &mut egui::Ui, setting: &Setting) {
+        let value = self.settings.get_value(setting.key).expect("every setting has a value");
+        let default = self.defaults.get_value(setting.key).expect("every setting has a default");
+        let errors = settings_validation::check(setting, &value);
+        let mut new_value = None;
+        ui.horizontal(|ui| {
+            let response = match setting.kind {
+                Kind::Toggle => {
+                    let mut checked = value.as_bool().unwrap_or_default();
+                    let text = self.formatted_string(setting.label);
+                    let response = ui.checkbox(&mut checked, text);
+                    if response.changed() {
+                        new_value = Some(Value::from(checked));
+                    }
+                    response
+                }
+                _ => {
+                    let mut text = self.formatted_string(&format!("{}:", setting.label));
+                    if !errors.is_empty() {
+                        text = text.color(egui::Color32::RED);
+                    }
+                    ui.label(text)
+                }
+            };
+            response.on_hover_text(tooltip(setting));
+            let reset = ui
+                .add_enabled(value != default, egui::Button::new("reset"))
+                .on_hover_text(format!("Reset to the default {}", default));
+            if reset.clicked() {
+                new_value = Some(default.clone());
             }
-            let core_scroll_area = egui::ScrollArea
-                ::new([true, true])
-                .max_height(800.0)
-                .id_source("options_scroll_area");
-            core_scroll_area.show(ui, |ui| {
-                ui.vertical(|ui| {
-                    let options_scroll_area = egui::ScrollArea
-                        ::new([true, true])
-                        .max_height(750.0)
-                        .id_source("options_scroll_area");
-                    options_scroll_area.show(ui, |ui| {
-                        ui.vertical(|ui| {
-                            let autohide_scroll_area = egui::ScrollArea
-                                ::new([true, true])
-                                .max_height(200.0)
-                                .id_source("autohide_scroll_area");
-                            autohide_scroll_area.show(ui, |ui| {
-                                ui.vertical(|ui| {
-                                    let check_box_text = self.formatted_string("autohide");
-                                    ui.checkbox(&mut self.settings.autohide, check_box_text);
-                                    ui.add_space(SPACING);
-                                    if self.settings.autohide {
-                                        ui.label(
-                                            self.formatted_string(
-                                                "Animation Steps on hover and fade away:"
-                                            )
-                                        );
-                                        ui.add(
-                                            egui::Slider
-                                                ::new(&mut self.settings.animation_steps, 0..=20)
-                                                .step_by(1.0)
-                                        );
-                                        ui.add_space(SPACING);
-                                        ui.label(
-                                            self.formatted_string("Animation Step Time in MS:")
-                                        );
-                                        ui.add(
-                                            egui::Slider
-                                                ::new(
-                                                    &mut self.settings.animation_time_in_ms,
-                                                    0..=100
-                                                )
-                                                .step_by(1.0)
-                                        );
-                                        ui.label(
-                                            self.formatted_small_string(
-                                                format!(
-                                                    "Current hide/show animation time in seconds: {} s",
-                                                    (
-                                                        (self.settings.animation_time_in_ms *
-                                                            (
-                                                                self.settings.animation_steps as u64
-                                                            )) as f64
-                                                    ) / 1000.0
-                                                ).as_str()
-                                            )
-                                        );
-                                        ui.add_space(SPACING);
-                                        ui.label(
-                                            self.formatted_string("Detection Size of Hidden_TB:")
-                                        );
-                                        ui.add(
-                                            egui::Slider
-                                                ::new(
-                                                    &mut self.settings.tb_rect_detection_size_in_pixel,
-                                                    1..=100
-                                                )
-                                                .step_by(1.0)
-                                        );
-                                        ui.add_space(SPACING);
-                                        ui.label(self.formatted_string("Bottom Rect Offset:"));
-                                        ui.label(
-                                            self.formatted_small_string(
-                                                "Leave this value at 1 or 0. If the tb isn't detected when your mouse hits the bottom of the screen, increase it by one and test again."
-                                            )
-                                        );
-                                        ui.add(
-                                            egui::Slider
-                                                ::new(
-                                                    &mut self.settings.tb_rect_bottom_offset,
-                                                    0..=5
-                                                )
-                                                .step_by(1.0)
-                                        );
-                                        ui.add_space(SPACING);
-                                        ui.label(
-                                            self.formatted_string("Window maximizing Offset Top:")
-                                        );
-                                        ui.label(
-                                            self.formatted_small_string(
-                                                "Offset to change the maximize window behavior. Leave it on 0 to fill the screen on maximizing a window, and bigger then 0 to use a top bar like rainmeter, so it will always show even when maximizing a window."
-                                            )
-                                        );
-                                        ui.add(
-                                            egui::Slider
-                                                ::new(
-                                                    &mut self.settings.workspace_offset_top,
-                                                    0..=200
-                                                )
-                                                .step_by(1.0)
-                                        );
-                                    }
-                                });
-                            });
-                            ui.separator();
-
-                            let scroll_area: egui::ScrollArea = egui::ScrollArea::new([true, true]).max_height(200.0);
-
-                            scroll_area.show(ui, |ui| {
-                                ui.vertical(|ui| {
-                                    let check_box_text =
-                                        self.formatted_string("enable dynamic borders");
-                                    ui.checkbox(
-                                        &mut self.settings.enable_dynamic_borders,
-                                        check_box_text
-                                    );
-                                    ui.add_space(SPACING);
-                                    if self.settings.enable_dynamic_borders {
-                                        let check_box_text = self.formatted_string(
-                                            "dynamic borders show tray"
-                                        );
-                                        ui.checkbox(
-                                            &mut self.settings.dynamic_borders_show_tray,
-                                            check_box_text
-                                        );
-                                        ui.add_space(SPACING);
-
-                                        let check_box_text = self.formatted_string(
-                                            "dynamic borders show tray on hover if disabled"
-                                        );
-                                        ui.checkbox(
-                                            &mut self.settings.dynamic_borders_show_tray_if_disabled_on_hover,
-                                            check_box_text
-                                        );
-                                        ui.add_space(SPACING);
-                                        /* //TODO enable if implemented 
-                                        let check_box_text = self.formatted_string(
-                                            "dynamic borders show widgets"
-                                        );
-                                        ui.checkbox(
-                                            &mut self.settings.dynamic_borders_show_widgets,
-                                            check_box_text
-                                        );
-                                        ui.add_space(SPACING);
-
-                                        let check_box_text = self.formatted_string(
-                                            "dynamic borders show widgets on hover if disabled"
-                                        );
-                                        ui.checkbox(
-                                            &mut self.settings.dynamic_borders_show_widgets_if_disabled_on_hover,
-                                            check_box_text
-                                        );
-                                        ui.add_space(SPACING);
-                                        */
-                                        ui.label(
-                                            self.formatted_string(
-                                                "Dynamic borders rounded corner size:"
-                                            )
-                                        );
-                                        ui.add(
-                                            egui::Slider
-                                                ::new(
-                                                    &mut self.settings.rounded_corners_size,
-                                                    0..=30
-                                                )
-                                                .step_by(1.0)
-                                        );
-                                        ui.add_space(SPACING);
+        });
+        /* the title regex errors are shown at their rule */
+        if let (Some(error), false) = (errors.first(), setting.kind == Kind::ForegroundRules) {
+            ui.label(
+                self
+                    .formatted_small_string(
+                        format!("{} (allowed: {})", error.reason, error.allowed).as_str()
+                    )
+                    .color(egui::Color32::RED)
+            );
+        }
+        if setting.restart_needed {
+            ui.label(self.formatted_small_string("Applied the next time hidden_tb starts."));
+        }
+        let edited_value = match setting.kind {
+            Kind::Toggle => None,
+            Kind::Number { step, .. } => {
+                let range = setting.slider_range().expect("numbers have a slider");
+                let mut number = value.as_i64().unwrap_or_default();
+                let changed = ui
+                    .add(egui::Slider::new(&mut number, range).step_by(step))
+                    .changed();
+                changed.then(|| Value::from(number))
+            }
+            Kind::Choice(choices) => {
+                let mut selected = value.as_str().unwrap_or_default().to_string();
+                let mut changed = false;
+                ui.horizontal(|ui| {
+                    for (choice, label) in choices {
+                        changed |= ui.radio_value(&mut selected, choice.to_string(), *label).changed();
+                    }
+                });
+                changed.then(|| Value::from(selected))
+            }
+            Kind::Easing => {
+                let mut easing: Easing = serde_json::from_value(value.clone()).unwrap_or_default();
+                easing_selection(ui, setting.key, &mut easing);
+                let edited = serde_json::to_value(easing).expect("easings serialize to json");
+                (edited != value).then_some(edited)
+            }
+            Kind::List => {
+                let entries: Vec<String> = serde_json::from_value(value.clone()).unwrap_or_default();
+                let mut text = entries.join("\n");
+                let changed = ui
+                    .add(egui::TextEdit::multiline(&mut text).desired_rows(3))
+                    .changed();
+                changed.then(|| Value::from(text.split('\n').collect::<Vec<_>>()))
+            }
+            Kind::ForegroundRules => {
+                let mut rules: Vec<ForegroundRule> = serde_json
+                    ::from_value(value.clone())
+                    .unwrap_or_default();
+                self.foreground_rules_editor(ui, &mut rules);
+                let edited = serde_json::to_value(rules).expect("rules serialize to json");
+                (edited != value).then_some(edited)
+            }
+        };
+        if let Some(new_value) = new_value.or(edited_value) {
+            self.settings
+                .set_value(setting.key, new_value)
+                .expect("the editor produces values of the type of the setting");
+        }
+    }
 
-                                        ui.label(
-                                            self.formatted_string("Dynamic borders margin top:")
-                                        );
-                                        ui.add(
-                                            egui::Slider
-                                                ::new(&mut self.settings.margin_top, -5..=20)
-                                                .step_by(1.0)
-                                        );
-                                        ui.add_space(SPACING);
+    /* the sections as tabs and the search box, a search looks through the settings of every section. */
+    fn section_tabs(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            for section in Section::ALL {
+                let text = self.formatted_small_string(section.name());
+                ui.selectable_value(&mut self.section, section, text);
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.label(self.formatted_small_string("Search:"));
+            ui.add(egui::TextEdit::singleline(&mut self.search).hint_text("name or description"));
+            if ui.add_enabled(!self.search.is_empty(), egui::Button::new("clear")).clicked() {
+                self.search.clear();
+            }
+        });
+    }
 
-                                        ui.label(
-                                            self.formatted_string("Dynamic borders margin bottom:")
-                                        );
-                                        ui.add(
-                                            egui::Slider
-                                                ::new(&mut self.settings.margin_bottom, -5..=20)
-                                                .step_by(1.0)
-                                        );
-                                        ui.add_space(SPACING);
+    fn foreground_rules_editor(&self, ui: &mut egui::Ui, rules: &mut Vec<ForegroundRule>) {
+        let mut removed = None;
+        for (index, rule) in rules.iter_mut().enumerate() {
+            ui.horizontal(|ui| {
+                ui.label("exe:");
+                ui.add(egui::TextEdit::singleline(&mut rule.exe_name).desired_width(100.0));
+                ui.label("class:");
+                ui.add(egui::TextEdit::singleline(&mut rule.window_class).desired_width(100.0));
+                ui.label("title regex:");
+                let regex_error = settings_validation::check_title_regex(index, &rule.title_regex).err();
+                let mut regex_edit = egui::TextEdit::singleline(&mut rule.title_regex).desired_width(100.0);
+                if regex_error.is_some() {
+                    regex_edit = regex_edit.text_color(egui::Color32::RED);
+                }
+                let response = ui.add(regex_edit);
+                if let Some(error) = regex_error {
+                    response.on_hover_text(error.reason);
+                }
+                egui::ComboBox
+                    ::from_id_source(("foreground_rule_action", index))
+                    .selected_text(rule_action_name(&rule.action))
+                    .show_ui(ui, |ui| {
+                        for action in [
+                            RuleAction::AlwaysShow,
+                            RuleAction::AlwaysHide,
+                            RuleAction::DisableDynamicBorders,
+                        ] {
+                            ui.selectable_value(&mut rule.action, action, rule_action_name(&action));
+                        }
+                    });
+                if ui.button("remove").clicked() {
+                    removed = Some(index);
+                }
+            });
+        }
+        if let Some(index) = removed {
+            rules.remove(index);
+        }
+        if ui.button(self.formatted_small_string("add rule")).clicked() {
+            rules.push(ForegroundRule::default());
+        }
+    }
 
-                                        ui.label(
-                                            self.formatted_string("Dynamic borders margin left:")
-                                        );
-                                        ui.add(
-                                            egui::Slider
-                                                ::new(&mut self.settings.margin_left, -5..=20)
-                                                .step_by(1.0)
-                                        );
-                                        ui.add_space(SPACING);
+    /* switching saves the file with the new active profile, unsaved changes of the current profile are dropped. */
+    fn profile_selection(&mut self, ui: &mut egui::Ui) {
+        let active = self.global_settings.get_active_profile().to_string();
+        let mut selected = active.clone();
+        let mut add_clicked = false;
+        ui.horizontal(|ui| {
+            ui.label(self.formatted_string("Profile:"));
+            egui::ComboBox
+                ::from_id_source("profile")
+                .selected_text(selected.clone())
+                .show_ui(ui, |ui| {
+                    for name in self.global_settings.get_profile_names() {
+                        ui.selectable_value(&mut selected, name.clone(), name);
+                    }
+                });
+            ui.add(egui::TextEdit::singleline(&mut self.new_profile_name).desired_width(100.0));
+            add_clicked = ui.button("new profile").clicked();
+        });
+        let result = if selected != active {
+            self.global_settings.switch_profile(&selected)
+        } else if add_clicked {
+            self.global_settings.add_profile(&self.new_profile_name)
+        } else {
+            return;
+        };
+        match result {
+            Ok(()) => {
+                self.settings = self.global_settings.clone();
+                self.new_profile_name.clear();
+                self.info_string = self.formatted_small_string(
+                    format!("Profile {} is active.", self.global_settings.get_active_profile()).as_str()
+                );
+            }
+            Err(errors) => {
+                let text = errors
+                    .iter()
+                    .map(|error| error.to_string())
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                self.info_string = self.formatted_small_string(text.as_str()).color(egui::Color32::RED);
+            }
+        }
+    }
 
-                                        ui.label(
-                                            self.formatted_string("Dynamic borders margin right:")
-                                        );
-                                        ui.add(
-                                            egui::Slider
-                                                ::new(&mut self.settings.margin_right, -5..=20)
-                                                .step_by(1.0)
-                                        );
+    /*
+    Sends the changed settings to the running hidden_tb whenever they differ from the last preview,
+    so slider changes are visible on the taskbar while dragging. Without changes the preview is reverted.
+    */
+    fn update_preview(&mut self, changed: &[&'static Setting]) {
+        let values: Map<String, Value> = changed
+            .iter()
+            .map(|setting| {
+                (setting.key.to_string(), self.settings.get_value(setting.key).expect("every setting has a value"))
+            })
+            .collect();
+        if values == self.previewed {
+            return;
+        }
+        let request = match values.is_empty() {
+            true => Request::Revert,
+            false => Request::Preview { values: values.clone() },
+        };
+        self.preview_requests.send(request).ok();
+        self.previewed = values;
+    }
 
-                                        ui.label(
-                                            self.formatted_string("Dynamic app borders margin left offset:")
-                                        );
-                                        ui.add(
-                                            egui::Slider
-                                                ::new(&mut self.settings.margin_offset_left, -1000..=1000)
-                                                .step_by(1.0)
-                                        );
-                                        ui.label(
-                                            self.formatted_string("Dynamic app borders margin right offset:")
-                                        );
-                                        ui.add(
-                                            egui::Slider
-                                                ::new(&mut self.settings.margin_offset_right, -1000..=1000)
-                                                .step_by(1.0)
-                                        );
+    /* saves the edited settings, empty lines of the lists are dropped. */
+    fn save(&mut self) {
+        let mut settings = self.settings.clone();
+        for setting in settings_registry::all().iter().filter(|setting| setting.kind == Kind::List) {
+            let entries: Vec<String> = serde_json
+                ::from_value(settings.get_value(setting.key).expect("every setting has a value"))
+                .unwrap_or_default();
+            let entries: Vec<String> = entries
+                .into_iter()
+                .map(|entry| entry.trim().to_string())
+                .filter(|entry| !entry.is_empty())
+                .collect();
+            settings
+                .set_value(setting.key, Value::from(entries))
+                .expect("a list of names is a valid list");
+        }
+        match settings.save() {
+            Ok(()) => {
+                self.global_settings = settings.clone();
+                self.settings = settings;
+                self.info_string = self.formatted_small_string(
+                    "Settings saved. A running hidden_tb applies the changes automatically."
+                );
+            }
+            Err(e) => {
+                self.info_string = self
+                    .formatted_small_string(format!("Settings not saved: {}", e).as_str())
+                    .color(egui::Color32::RED);
+            }
+        }
+    }
 
-                                        ui.add_space(SPACING);
-                                        ui.label(
-                                            self.formatted_small_string(
-                                                "This fixes the taskbar size on newer windows 11 builds. If your Taskbar looks wrong, disable this and try again."
-                                            )
-                                        );
-                                        let check_box_text = self.formatted_string("Windows 11 Bugfix");
-                                        ui.checkbox(&mut self.settings.windows_11_bugfix, check_box_text);
-                                        
-                                    }
-                                });
-                            });
-                            ui.separator();
-                            ui.label(self.formatted_string("Hidden_TB Refresh Time in MS:"));
-                            ui.add(
-                                egui::Slider
-                                    ::new(&mut self.settings.sleep_time_in_ms, 0..=200)
-                                    .step_by(5.0)
-                            );
-                            ui.add_space(SPACING);
+    fn formatted_string(&self, str: &str) -> egui::widget_text::RichText {
+        egui::RichText::new(str).font(self.font_id.clone())
+    }
 
-                            ui.label(self.formatted_string("Infrequent Work Refresh Skips:"));
-                            ui.label(
-                                self.formatted_small_string(
-                                    "Keep it to check at around one second (time is 'Infrequent Work Refresh Skips' * 'Hidden_TB Refresh Time in MS'). It handles the checks for lower priority changes like a new display attached."
-                                )
-                            );
-                            ui.label(
-                                self.formatted_small_string(
-                                    format!(
-                                        "Current infrequent check timeout in seconds: {} s",
-                                        (
-                                            (self.settings.infrequent_count *
-                                                (self.settings.sleep_time_in_ms as usize)) as f64
-                                        ) / 1000.0
-                                    ).as_str()
-                                )
-                            );
-                            ui.add(
-                                egui::Slider
-                                    ::new(&mut self.settings.infrequent_count, 1..=360)
-                                    .step_by(1.0)
-                            );
+    fn formatted_small_string(&self, str: &str) -> egui::widget_text::RichText {
+        egui::RichText::new(str).font(self.small_font_id.clone())
+    }
+}
 
-                            ui.separator();
+/*
+Sends the previews to the running hidden_tb from a background thread, a slow answer never blocks the ui.
+Previews that queued up while waiting are skipped, only the latest one is sent.
+*/
+fn start_preview_thread() -> Sender<Request> {
+    let (sender, receiver) = mpsc::channel::<Request>();
+    thread::spawn(move || {
+        let endpoint = Endpoint::for_user();
+        while let Ok(mut request) = receiver.recv() {
+            while let Ok(newer) = receiver.try_recv() {
+                request = newer;
+            }
+            match control::send(&endpoint, &request) {
+                Ok(Response { ok: true, .. }) => {}
+                Ok(Response { error, .. }) => eprintln!("Preview rejected: {}", error.unwrap_or_default()),
+                Err(e) => eprintln!("Could not preview the settings, is hidden_tb running? {}", e),
+            }
+        }
+    });
+    sender
+}
 
-                            ui.add_space(SPACING);
-                            /* Too buggy atm... */
-                            //let check_box_text = self.formatted_string("merge tray with applist");
-                            //ui.checkbox(&mut self.settings.merge_tray, check_box_text);
-                            //ui.add_space(SPACING);
-                            /* //TODO enable if implemented 
-                            let check_box_text = self.formatted_string("merge widget with applist");
-                            ui.checkbox(&mut self.settings.merge_widgets, check_box_text);
-                            ui.add_space(SPACING);*/
-                        });
-                    });
+fn tooltip(setting: &Setting) -> String {
+    match setting.restart_needed {
+        true => format!("{}\nApplied the next time hidden_tb starts.", setting.description),
+        false => setting.description.to_string(),
+    }
+}
 
+fn easing_selection(ui: &mut egui::Ui, id: &str, easing: &mut Easing) {
+    egui::ComboBox
+        ::from_id_source(id)
+        .selected_text(easing_name(easing))
+        .show_ui(ui, |ui| {
+            ui.selectable_value(easing, Easing::Linear, easing_name(&Easing::Linear));
+            ui.selectable_value(easing, Easing::EaseIn, easing_name(&Easing::EaseIn));
+            ui.selectable_value(easing, Easing::EaseOut, easing_name(&Easing::EaseOut));
+            ui.selectable_value(easing, Easing::EaseInOut, easing_name(&Easing::EaseInOut));
+            let is_bezier = matches!(easing, Easing::CubicBezier { .. });
+            if ui.selectable_label(is_bezier, "cubic bezier").clicked() && !is_bezier {
+                /* start with the control points of the css "ease" curve */
+                *easing = Easing::CubicBezier { x1: 0.25, y1: 0.1, x2: 0.25, y2: 1.0 };
+            }
+        });
+    if let Easing::CubicBezier { x1, y1, x2, y2 } = easing {
+        ui.add(egui::Slider::new(x1, 0.0..=1.0).text("x1"));
+        ui.add(egui::Slider::new(y1, -1.0..=2.0).text("y1"));
+        ui.add(egui::Slider::new(x2, 0.0..=1.0).text("x2"));
+        ui.add(egui::Slider::new(y2, -1.0..=2.0).text("y2"));
+    }
+}
 
-                    ui.separator();
+fn rule_action_name(action: &RuleAction) -> &'static str {
+    match action {
+        RuleAction::AlwaysShow => "always show",
+        RuleAction::AlwaysHide => "always hide",
+        RuleAction::DisableDynamicBorders => "disable dynamic borders",
+    }
+}
 
-                    ui.add_space(SPACING);
+fn easing_name(easing: &Easing) -> &'static str {
+    match easing {
+        Easing::Linear => "linear",
+        Easing::EaseIn => "ease in",
+        Easing::EaseOut => "ease out",
+        Easing::EaseInOut => "ease in out",
+        Easing::CubicBezier { .. } => "cubic bezier",
+    }
+}
 
-                    ui.add_space(SPACING);
+impl eframe::App for MyApp {
+    /* closing the window drops unsaved changes, the running hidden_tb goes back to the saved settings */
+    fn on_close_event(&mut self) -> bool {
+        if !self.previewed.is_empty() {
+            control::send(&Endpoint::for_user(), &Request::Revert).ok();
+        }
+        true
+    }
 
-                    if ui.button(self.formatted_string("Save Settings")).clicked() {
-                        self.call_settings_update();
-                        self.info_string = self.formatted_small_string(
-                            "Settings saved. Close the gui and start hidden_tb.exe again with the applied changes."
-                        );
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        egui::CentralPanel::default().show(ctx, |ui| {
+            let errors = self.settings.validate().err().unwrap_or_default();
+            let changed = self.settings.changed_settings(&self.global_settings);
+            if !errors.is_empty() {
+                self.info_string = self
+                    .formatted_small_string(
+                        format!(
+                            "{} invalid settings, fix the highlighted fields to save",
+                            errors.len()
+                        ).as_str()
+                    )
+                    .color(egui::Color32::RED);
+            } else if !changed.is_empty() {
+                let mut text = "Currently unsaved settings".to_string();
+                let restarts: Vec<_> = changed
+                    .iter()
+                    .filter(|setting| setting.restart_needed)
+                    .map(|setting| setting.label)
+                    .collect();
+                if !restarts.is_empty() {
+                    text += &format!(", applied after a restart: {}", restarts.join(", "));
+                }
+                self.info_string = self.formatted_small_string(&text);
+            }
+            /* invalid values are not previewed, the running hidden_tb keeps the last valid preview */
+            if errors.is_empty() {
+                self.update_preview(&changed);
+            }
+            self.profile_selection(ui);
+            ui.separator();
+            self.section_tabs(ui);
+            ui.separator();
+            let options_scroll_area = egui::ScrollArea
+                ::new([true, true])
+                .max_height(750.0)
+                .id_source("options_scroll_area");
+            options_scroll_area.show(ui, |ui| {
+                ui.vertical(|ui| {
+                    if self.search.trim().is_empty() {
+                        let settings = self.visible_settings(self.section);
+                        self.section_editor(ui, &settings);
+                        return;
+                    }
+                    for section in Section::ALL {
+                        let settings = self.visible_settings(section);
+                        if settings.is_empty() {
+                            continue;
+                        }
+                        ui.heading(self.formatted_string(section.name()));
+                        ui.add_space(SPACING);
+                        self.section_editor(ui, &settings);
+                        ui.separator();
                     }
-
-                    /*
-                    if
-                        ui
-                            .button(
-                                self.formatted_small_string("Save and Set to Restart on Close.")
-                            )
-                            .clicked()
-                    {
-                        self.call_settings_update();
-                        self.info_string = self.formatted_small_string(
-                            "Settings applied. Close this window to restart the taskbar."
-                        );
-                        signaling::get_signaling_struct().set_reset_called(true);
-                    } */
-
-                    ui.label(self.info_string.clone());
-                    ui.separator();
                 });
             });
+
+            ui.add_space(SPACING);
+
+            /* invalid settings are never saved, the info string lists them */
+            ui.horizontal(|ui| {
+                if ui.button(self.formatted_string("Save Settings")).clicked() && errors.is_empty() {
+                    self.save();
+                }
+                /* the next update reverts the preview of the running hidden_tb */
+                if ui.button(self.formatted_string("Cancel")).clicked() {
+                    self.settings = self.global_settings.clone();
+                    self.info_string = self.formatted_small_string("Changes reverted.");
+                }
+            });
+
+            ui.label(self.info_string.clone());
+            ui.label(
+                self.formatted_small_string(
+                    format!("Settings file: {}", settings_location::get()).as_str()
+                )
+            );
+            ui.separator();
         });
     }
 }
@@ -0,0 +1,95 @@
+use std::error::Error;
+use std::fmt;
+
+use regex::Regex;
+use serde_json::Value;
+
+use crate::settings_registry::{Kind, Setting};
+
+/* a settings value hidden_tb can not run with. */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SettingsError {
+    /* the key in settings.json, foreground rules are indexed like foreground_rules[0].title_regex. */
+    pub field: String,
+    pub value: String,
+    /* the allowed values, e.g. "1..=255". */
+    pub allowed: String,
+    pub reason: String,
+}
+
+impl fmt::Display for SettingsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} = {} is invalid, {} (allowed: {})",
+            self.field, self.value, self.reason, self.allowed
+        )
+    }
+}
+
+impl Error for SettingsError {}
+
+/* checks the {value} of {setting} against the limits of its kind. */
+pub fn check(setting: &Setting, value: &Value) -> Vec<SettingsError> {
+    match setting.kind {
+        Kind::Number {
+            unsigned,
+            min,
+            max,
+            reason,
+            ..
+        } => {
+            /* u64 settings go beyond i64::MAX, i128 holds the numbers of both types */
+            let number = match unsigned {
+                true => value.as_u64().map(i128::from),
+                false => value.as_i64().map(i128::from),
+            };
+            let reason = match number {
+                None if unsigned => "it is not a whole number of 0 or more",
+                None => "it is not a whole number",
+                Some(number)
+                    if min.is_some_and(|min| number < min.into())
+                        || max.is_some_and(|max| number > max.into()) =>
+                {
+                    match reason.is_empty() {
+                        true => "it is out of range",
+                        false => reason,
+                    }
+                }
+                Some(_) => return Vec::new(),
+            };
+            vec![SettingsError {
+                field: setting.key.to_string(),
+                value: value.to_string(),
+                allowed: setting.allowed(),
+                reason: reason.to_string(),
+            }]
+        }
+        Kind::ForegroundRules => value
+            .as_array()
+            .into_iter()
+            .flatten()
+            .enumerate()
+            .filter_map(|(index, rule)| {
+                let title_regex = rule.get("title_regex")?.as_str()?;
+                check_title_regex(index, title_regex).err()
+            })
+            .collect(),
+        Kind::Toggle | Kind::Choice(_) | Kind::Easing | Kind::List => Vec::new(),
+    }
+}
+
+/* the title regex of the foreground rule at {index} has to compile, an empty regex is not checked. */
+pub fn check_title_regex(index: usize, title_regex: &str) -> Result<(), SettingsError> {
+    if title_regex.is_empty() {
+        return Ok(());
+    }
+    Regex::new(title_regex)
+        .map(|_| ())
+        .map_err(|e| SettingsError {
+            field: format!("foreground_rules[{}].title_regex", index),
+            value: title_regex.to_string(),
+            allowed: "a regular expression".to_string(),
+            reason: e.to_string(),
+        })
+}
@@ -0,0 +1,85 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::tb_settings::TbSettings;
+
+/* modification time and size of the settings file, None while the file does not exist. */
+type Fingerprint = Option<(SystemTime, u64)>;
+
+fn fingerprint(path: &Path) -> Fingerprint {
+    let metadata = fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+/*
+Watches the settings file for changes. The main loop polls it in the infrequent routine,
+a changed file is parsed and validated before the running settings are replaced.
+*/
+#[derive(Debug)]
+pub struct SettingsWatcher {
+    path: PathBuf,
+    last_fingerprint: Fingerprint,
+}
+
+impl SettingsWatcher {
+    /* the current state of the file counts as seen, only later changes are reported. */
+    pub fn new(path: PathBuf) -> Self {
+        let last_fingerprint = fingerprint(&path);
+        SettingsWatcher {
+            path,
+            last_fingerprint,
+        }
+    }
+
+    pub fn get_path(&self) -> &Path {
+        &self.path
+    }
+
+    /*
+    Returns the new settings if the file changed since the last poll. A file that can not be parsed or has invalid
+    values is reported and skipped, the running settings stay active until the file is fixed.
+    */
+    pub fn poll(&mut self) -> Option<TbSettings> {
+        let current = fingerprint(&self.path);
+        if current == self.last_fingerprint {
+            return None;
+        }
+        self.last_fingerprint = current;
+        current?;
+
+        match self.load() {
+            Ok(settings) => Some(settings),
+            Err(e) => {
+                eprintln!("Ignoring the settings in {}: {}", self.path.display(), e);
+                None
+            }
+        }
+    }
+
+    /* reads the file even if it did not change, e.g. for the reload command. */
+    pub fn reload(&mut self) -> Result<TbSettings, String> {
+        self.last_fingerprint = fingerprint(&self.path);
+        self.load()
+    }
+
+    /* writes {settings} to the watched file, the written file counts as seen. */
+    pub fn save(&mut self, settings: &TbSettings) -> Result<(), String> {
+        settings.save_to(&self.path).map_err(|e| e.to_string())?;
+        self.last_fingerprint = fingerprint(&self.path);
+        Ok(())
+    }
+
+    fn load(&self) -> Result<TbSettings, String> {
+        let settings = TbSettings::load_from(&self.path)
+            .map_err(|e| format!("could not read {}: {}", self.path.display(), e))?;
+        settings.validate().map_err(|errors| {
+            errors
+                .iter()
+                .map(|error| error.to_string())
+                .collect::<Vec<_>>()
+                .join("; ")
+        })?;
+        Ok(settings)
+    }
+}
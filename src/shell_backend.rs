@@ -0,0 +1,286 @@
+use crate::geometry::{Dpi, Point, Rect};
+use crate::region::RegionSpec;
+use crate::tb_settings::TbSettings;
+
+pub const _AUTOHIDE: isize = 0x01;
+pub const _ALWAYS_ON_TOP: isize = 0x02;
+
+pub const PRIMARY_TASKBAR_CLASS: &str = "Shell_TrayWnd";
+pub const SECONDARY_TASKBAR_CLASS: &str = "Shell_SecondaryTrayWnd";
+pub const TASKBAR_CLASSES: [&str; 2] = [PRIMARY_TASKBAR_CLASS, SECONDARY_TASKBAR_CLASS];
+
+/* which taskbar window to look up. Secondary taskbars are identified by the display of their monitor. */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TaskbarLocation {
+    Primary,
+    Secondary(Rect),
+}
+
+/* opaque window handle. On windows this is the raw HWND value. */
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct WindowHandle(pub isize);
+
+#[derive(Default, Debug, Clone)]
+pub struct FormEntry {
+    pub hwnd: WindowHandle,
+    pub rect: Rect,
+}
+
+/* the window in the foreground and the executable it belongs to. */
+#[derive(Default, Debug, Clone)]
+pub struct ForegroundWindow {
+    pub hwnd: WindowHandle,
+    pub class_name: String,
+    pub title: String,
+    /* file name of the executable, e.g. "Code.exe". None if the process could not be queried. */
+    pub exe_name: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Monitor {
+    display: Rect,
+    is_primary: bool,
+}
+
+/* the dpi of a taskbar is queried from its window with ShellBackend::get_dpi. */
+impl Monitor {
+    pub fn new(display: Rect, is_primary: bool) -> Self {
+        Monitor {
+            display,
+            is_primary,
+        }
+    }
+    pub fn get_display(&self) -> Rect {
+        self.display
+    }
+    pub fn is_primary(&self) -> bool {
+        self.is_primary
+    }
+}
+
+/*
+Everything the taskbar logic needs from the shell. The win32 implementation lives in windows_calls,
+the simulated_shell implementation is an in-memory desktop to drive the taskbar state machine without windows.
+*/
+pub trait ShellBackend {
+    /* finds the first child window of {parent} with the class name {class_name}. None as parent searches top level windows. */
+    fn find_window(&self, parent: Option<WindowHandle>, class_name: &str) -> Option<FormEntry>;
+    /* like find_window, but returns every matching window. */
+    fn find_windows(&self, parent: Option<WindowHandle>, class_name: &str) -> Vec<FormEntry>;
+    /* dpi of the monitor the window is on. */
+    fn get_dpi(&self, hwnd: WindowHandle) -> Dpi;
+    fn get_cursor_pos(&self) -> Option<Point>;
+    fn get_start_menu_open(&self) -> bool;
+    /* the exe name of {previous} is reused while its window stays in the foreground, the process is only queried on a change. */
+    fn get_foreground_window(
+        &self,
+        previous: Option<&ForegroundWindow>,
+    ) -> Option<ForegroundWindow>;
+    /* returns true if the window already had the layered style. Otherwise it is set and false is returned. */
+    fn check_and_set_transparency_style(&self, hwnd: WindowHandle) -> bool;
+    fn set_window_alpha(&self, hwnd: WindowHandle, alpha: u8) -> bool;
+    fn set_window_topmost(&self, hwnd: WindowHandle);
+    fn set_app_bar_state(&self, hwnd: WindowHandle, option: isize);
+    fn move_window_on_tb(&self, hwnd: WindowHandle, x: i32, y: i32) -> bool;
+    /* moves the window to the screen position {x}, {y} without changing its size or z order. */
+    fn set_window_position(&self, hwnd: WindowHandle, x: i32, y: i32) -> bool;
+    /* sets the window region, the window takes ownership of the realised region. */
+    fn set_window_region(&self, hwnd: WindowHandle, region: &RegionSpec);
+    /* removes the region, alpha and the extended styles hidden_tb has set on the window. */
+    fn reset_window_style(&self, hwnd: WindowHandle);
+    fn get_monitors(&self) -> Vec<Monitor>;
+    /*
+    Visible and not minimized top level windows the user works in. Without the desktop background, the taskbars,
+    tool windows, windows that can not be activated and click through windows.
+    */
+    fn get_visible_windows(&self) -> Vec<FormEntry>;
+    fn get_work_area(&self) -> Rect;
+    /* returns true if the work area was changed to {rect}. */
+    fn set_work_area(&self, rect: &Rect) -> bool;
+    fn send_workspace_and_display_change_msg(&self);
+    fn restart_executable(&self, process_name: &str) -> bool;
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct WantedHwnds {
+    pub taskbar: bool,
+    pub tray: bool,
+    pub rebar: bool,
+    pub applist: bool,
+    pub apps: bool,
+}
+
+impl WantedHwnds {
+    pub fn new(settings: &TbSettings) -> Self {
+        //Currently only the taskbar is needed, so set all other values to false
+        //Later on detect through settings if more is needed
+        let mut wanted_hwnds = WantedHwnds {
+            taskbar: true,
+            tray: false,
+            rebar: false,
+            applist: false,
+            apps: false,
+        };
+        if settings.get_merge_tray()
+            || settings.get_merge_widgets()
+            || settings.get_enable_dynamic_borders()
+        {
+            wanted_hwnds.tray = true;
+            wanted_hwnds.rebar = true;
+            wanted_hwnds.applist = true;
+            //apps is currently not used, so we can keep it on false and not depend on it.
+            //wanted_hwnds.apps = true;
+        }
+        wanted_hwnds
+    }
+}
+
+#[derive(Default, Debug, Clone)]
+pub struct TaskbarData {
+    /*
+    taskbar is on top.
+
+    tray depends on taskbar.
+    rebar depends on taskbar.
+
+    applist depends on rebar.
+
+    apps depend on applist.
+    */
+    pub taskbar: Option<FormEntry>,
+
+    /* dpi of the monitor of the taskbar, converts the logical settings to the physical rects. */
+    pub dpi: Dpi,
+
+    pub tray: Option<FormEntry>,
+    pub rebar: Option<FormEntry>,
+
+    pub applist: Option<FormEntry>,
+
+    pub apps: Option<FormEntry>,
+
+    pub wanted_hwnds: WantedHwnds,
+
+    pub display_rect: Option<Rect>,
+}
+
+impl TaskbarData {
+    pub fn new(backend: &impl ShellBackend, wanted: &WantedHwnds) -> Self {
+        Self::find(backend, wanted, TaskbarLocation::Primary, Point::default())
+    }
+
+    /*
+    {moved_by} is how far the taskbar is currently moved from its docked position, e.g. by the slide hide mode.
+    The rects are reported at the docked position, a slid away secondary taskbar is still matched to its display.
+    */
+    pub fn find(
+        backend: &impl ShellBackend,
+        wanted: &WantedHwnds,
+        location: TaskbarLocation,
+        moved_by: Point,
+    ) -> Self {
+        let mut data = match location {
+            TaskbarLocation::Primary => Self::find_primary(backend, wanted),
+            TaskbarLocation::Secondary(display) => {
+                Self::find_secondary(backend, wanted, &display, moved_by)
+            }
+        };
+        data.offset(-moved_by.x, -moved_by.y);
+        data
+    }
+
+    fn find_primary(backend: &impl ShellBackend, wanted: &WantedHwnds) -> Self {
+        let mut data = TaskbarData::default();
+        if wanted.taskbar {
+            data.taskbar = backend.find_window(None, PRIMARY_TASKBAR_CLASS);
+            if let Some(taskbar) = &data.taskbar {
+                data.dpi = backend.get_dpi(taskbar.hwnd);
+
+                if wanted.tray {
+                    data.tray = backend.find_window(Some(taskbar.hwnd), "TrayNotifyWnd");
+                }
+                if wanted.rebar {
+                    data.rebar = backend.find_window(Some(taskbar.hwnd), "ReBarWindow32");
+                    if let Some(rebar) = &data.rebar {
+                        if wanted.applist {
+                            data.applist = backend.find_window(Some(rebar.hwnd), "MSTaskSwWClass");
+                            if let Some(applist) = &data.applist {
+                                if wanted.apps {
+                                    data.apps =
+                                        backend.find_window(Some(applist.hwnd), "MSTaskListWClass");
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        data.wanted_hwnds = wanted.clone();
+        data
+    }
+
+    /*
+    Secondary taskbars have no tray, the applist is the task list below the WorkerW window.
+    taskbar -> WorkerW (rebar) -> MSTaskListWClass (applist)
+    */
+    fn find_secondary(
+        backend: &impl ShellBackend,
+        wanted: &WantedHwnds,
+        display: &Rect,
+        moved_by: Point,
+    ) -> Self {
+        let mut data = TaskbarData::default();
+        if wanted.taskbar {
+            data.taskbar = backend
+                .find_windows(None, SECONDARY_TASKBAR_CLASS)
+                .into_iter()
+                .find(|taskbar| {
+                    let docked = taskbar.rect.offset(-moved_by.x, -moved_by.y);
+                    display.contains(&docked.center())
+                });
+            if let Some(taskbar) = &data.taskbar {
+                data.dpi = backend.get_dpi(taskbar.hwnd);
+                if wanted.rebar {
+                    data.rebar = backend.find_window(Some(taskbar.hwnd), "WorkerW");
+                    if let Some(rebar) = &data.rebar {
+                        if wanted.applist {
+                            data.applist =
+                                backend.find_window(Some(rebar.hwnd), "MSTaskListWClass");
+                        }
+                    }
+                }
+            }
+        }
+        data.wanted_hwnds = WantedHwnds {
+            tray: false,
+            apps: false,
+            ..wanted.clone()
+        };
+        data.display_rect = Some(*display);
+        data
+    }
+
+    /* moves every rect by {dx}, {dy}. Used to map the rects of a moved taskbar back to its docked position. */
+    pub fn offset(&mut self, dx: i32, dy: i32) {
+        for entry in [
+            &mut self.taskbar,
+            &mut self.tray,
+            &mut self.rebar,
+            &mut self.applist,
+            &mut self.apps,
+        ]
+        .into_iter()
+        .flatten()
+        {
+            entry.rect = entry.rect.offset(dx, dy);
+        }
+    }
+
+    pub fn contains_none(&self) -> bool {
+        (self.applist.is_none() && self.wanted_hwnds.applist)
+            || (self.apps.is_none() && self.wanted_hwnds.apps)
+            || (self.rebar.is_none() && self.wanted_hwnds.rebar)
+            || (self.tray.is_none() && self.wanted_hwnds.tray)
+            || (self.taskbar.is_none() && self.wanted_hwnds.taskbar)
+    }
+}
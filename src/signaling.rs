@@ -8,7 +8,7 @@ use once_cell::sync::OnceCell;
 #[derive(Default)]
 pub struct Signaling {
     exit_called: AtomicBool,
-    settings_called: AtomicBool,
+    reload_called: AtomicBool,
 }
 
 impl Signaling {
@@ -20,12 +20,14 @@ impl Signaling {
         self.exit_called.load(Ordering::SeqCst)
     }
 
-    pub fn set_settings_called(&self, val: bool) {
-        self.settings_called.store(val, Ordering::SeqCst);
+    /* asks the main loop to check the settings file on the next tick instead of the next infrequent routine. */
+    pub fn set_reload_called(&self, val: bool) {
+        self.reload_called.store(val, Ordering::SeqCst);
     }
 
-    pub fn get_settings_called(&self) -> bool {
-        self.settings_called.load(Ordering::SeqCst)
+    /* returns the request and resets it. */
+    pub fn take_reload_called(&self) -> bool {
+        self.reload_called.swap(false, Ordering::SeqCst)
     }
 }
 
@@ -0,0 +1,449 @@
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use crate::geometry::{Dpi, Edge, Physical, Point, Rect};
+use crate::region::RegionSpec;
+use crate::shell_backend::{
+    ForegroundWindow, FormEntry, Monitor, ShellBackend, WindowHandle, SECONDARY_TASKBAR_CLASS,
+    TASKBAR_CLASSES,
+};
+
+#[derive(Debug, Clone)]
+pub struct SimulatedWindow {
+    pub hwnd: WindowHandle,
+    pub parent: Option<WindowHandle>,
+    pub class_name: String,
+    pub rect: Rect,
+    pub layered: bool,
+    pub alpha: u8,
+    pub topmost: bool,
+    pub visible: bool,
+    pub title: String,
+    pub exe_name: Option<String>,
+    pub region: Option<RegionSpec>,
+    /* a tool window, a window that can not be activated or a click through window. */
+    pub overlay: bool,
+}
+
+/* the last region set on a window. */
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimulatedRegion {
+    pub hwnd: WindowHandle,
+    pub spec: RegionSpec,
+}
+
+/* the state of the simulated desktop. Tests change it directly to steer the taskbar. */
+#[derive(Debug)]
+pub struct SimulatedDesktop {
+    pub windows: Vec<SimulatedWindow>,
+    pub monitors: Vec<Monitor>,
+    pub work_area: Rect,
+    pub cursor_pos: Option<Point>,
+    pub start_menu_open: bool,
+    pub foreground: Option<WindowHandle>,
+    pub app_bar_state: Option<isize>,
+    pub region: Option<SimulatedRegion>,
+    pub region_updates: usize,
+    pub alpha_changes: Vec<u8>,
+    pub broadcasts: usize,
+    pub restarted: Vec<String>,
+    /* how often the executable of the foreground window was looked up. */
+    pub process_queries: usize,
+    /* the dpi of the monitors of these displays, every other monitor has the default dpi. */
+    pub monitor_dpis: Vec<(Rect, Dpi)>,
+    next_hwnd: isize,
+}
+
+impl Default for SimulatedDesktop {
+    fn default() -> Self {
+        SimulatedDesktop {
+            windows: Vec::new(),
+            monitors: Vec::new(),
+            work_area: Rect::default(),
+            cursor_pos: None,
+            start_menu_open: false,
+            foreground: None,
+            app_bar_state: None,
+            region: None,
+            region_updates: 0,
+            alpha_changes: Vec::new(),
+            broadcasts: 0,
+            restarted: Vec::new(),
+            process_queries: 0,
+            monitor_dpis: Vec::new(),
+            next_hwnd: 1,
+        }
+    }
+}
+
+impl SimulatedDesktop {
+    pub fn add_window(
+        &mut self,
+        parent: Option<WindowHandle>,
+        class_name: &str,
+        rect: Rect,
+    ) -> WindowHandle {
+        let hwnd = WindowHandle(self.next_hwnd);
+        self.next_hwnd += 1;
+        self.windows.push(SimulatedWindow {
+            hwnd,
+            parent,
+            class_name: class_name.to_string(),
+            rect,
+            layered: false,
+            alpha: 255,
+            topmost: false,
+            visible: true,
+            title: String::new(),
+            exe_name: None,
+            region: None,
+            overlay: false,
+        });
+        hwnd
+    }
+
+    pub fn remove_window(&mut self, hwnd: WindowHandle) {
+        self.windows
+            .retain(|window| window.hwnd != hwnd && window.parent != Some(hwnd));
+    }
+
+    pub fn window(&self, hwnd: WindowHandle) -> Option<&SimulatedWindow> {
+        self.windows.iter().find(|window| window.hwnd == hwnd)
+    }
+
+    pub fn window_mut(&mut self, hwnd: WindowHandle) -> Option<&mut SimulatedWindow> {
+        self.windows.iter_mut().find(|window| window.hwnd == hwnd)
+    }
+
+    pub fn window_by_class(&self, class_name: &str) -> Option<&SimulatedWindow> {
+        self.windows
+            .iter()
+            .find(|window| window.class_name == class_name)
+    }
+
+    /* changes the dpi of the monitor of {display}. */
+    pub fn set_monitor_dpi(&mut self, display: Rect, dpi: Dpi) {
+        self.monitor_dpis.retain(|(other, _)| *other != display);
+        self.monitor_dpis.push((display, dpi));
+    }
+
+    pub fn taskbar_alpha(&self) -> Option<u8> {
+        self.window_by_class("Shell_TrayWnd")
+            .map(|taskbar| taskbar.alpha)
+    }
+}
+
+/*
+In-memory shell backend. Clones share the same desktop, so a test keeps one handle
+while the taskbar owns the other.
+*/
+#[derive(Debug, Clone, Default)]
+pub struct SimulatedShell {
+    desktop: Arc<Mutex<SimulatedDesktop>>,
+}
+
+impl SimulatedShell {
+    pub fn new() -> Self {
+        SimulatedShell::default()
+    }
+
+    /*
+    A primary monitor of {display} with a bottom docked taskbar of {taskbar_height}.
+    The applist is centered like on windows 11 and the tray sits on the right side.
+    */
+    pub fn with_standard_taskbar(display: Rect, taskbar_height: i32) -> Self {
+        Self::with_taskbar_on(display, Edge::Bottom, taskbar_height)
+    }
+
+    /*
+    Like with_standard_taskbar, but docked to {edge} with a taskbar {thickness} wide.
+    A vertical taskbar has the applist centered and the tray at the bottom.
+    */
+    pub fn with_taskbar_on(display: Rect, edge: Edge, thickness: i32) -> Self {
+        /* the layout is built for a horizontal taskbar and transposed for a vertical one */
+        let (space, space_edge) = match edge {
+            Edge::Left => (display.transposed(), Edge::Top),
+            Edge::Right => (display.transposed(), Edge::Bottom),
+            _ => (display, edge),
+        };
+        let place = |rect: Rect| match edge.is_vertical() {
+            true => rect.transposed(),
+            false => rect,
+        };
+
+        let shell = SimulatedShell::new();
+        {
+            let mut desktop = shell.desktop();
+            desktop.monitors.push(Monitor::new(display, true));
+            desktop.work_area = display.grow_edge(edge, -Physical(thickness));
+            let taskbar_rect = space.strip(space_edge, Physical(thickness));
+            let taskbar = desktop.add_window(None, "Shell_TrayWnd", place(taskbar_rect));
+            desktop.add_window(
+                Some(taskbar),
+                "TrayNotifyWnd",
+                place(Rect::new(
+                    taskbar_rect.right - 200,
+                    taskbar_rect.top,
+                    taskbar_rect.right,
+                    taskbar_rect.bottom,
+                )),
+            );
+            let rebar = desktop.add_window(
+                Some(taskbar),
+                "ReBarWindow32",
+                place(Rect::new(
+                    taskbar_rect.left,
+                    taskbar_rect.top,
+                    taskbar_rect.right - 200,
+                    taskbar_rect.bottom,
+                )),
+            );
+            let center = taskbar_rect.left + taskbar_rect.width() / 2;
+            let applist_rect = place(Rect::new(
+                center - 200,
+                taskbar_rect.top,
+                center + 200,
+                taskbar_rect.bottom,
+            ));
+            let applist = desktop.add_window(Some(rebar), "MSTaskSwWClass", applist_rect);
+            desktop.add_window(Some(applist), "MSTaskListWClass", applist_rect);
+        }
+        shell
+    }
+
+    /*
+    Adds a monitor of {display} right of the others with a secondary taskbar of {taskbar_height}.
+    Returns the handle of the taskbar window.
+    */
+    pub fn add_secondary_taskbar(&self, display: Rect, taskbar_height: i32) -> WindowHandle {
+        let mut desktop = self.desktop();
+        desktop.monitors.push(Monitor::new(display, false));
+        let taskbar_rect = Rect::new(
+            display.left,
+            display.bottom - taskbar_height,
+            display.right,
+            display.bottom,
+        );
+        let taskbar = desktop.add_window(None, SECONDARY_TASKBAR_CLASS, taskbar_rect);
+        let worker = desktop.add_window(Some(taskbar), "WorkerW", taskbar_rect);
+        let center = taskbar_rect.left + taskbar_rect.width() / 2;
+        desktop.add_window(
+            Some(worker),
+            "MSTaskListWClass",
+            Rect::new(
+                center - 200,
+                taskbar_rect.top,
+                center + 200,
+                taskbar_rect.bottom,
+            ),
+        );
+        taskbar
+    }
+
+    pub fn desktop(&self) -> MutexGuard<'_, SimulatedDesktop> {
+        self.desktop.lock().expect("simulated desktop poisoned")
+    }
+
+    pub fn set_cursor_pos(&self, point: Option<Point>) {
+        self.desktop().cursor_pos = point;
+    }
+
+    pub fn set_start_menu_open(&self, open: bool) {
+        self.desktop().start_menu_open = open;
+    }
+}
+
+impl ShellBackend for SimulatedShell {
+    fn find_window(&self, parent: Option<WindowHandle>, class_name: &str) -> Option<FormEntry> {
+        self.desktop()
+            .windows
+            .iter()
+            .find(|window| window.parent == parent && window.class_name == class_name)
+            .map(|window| FormEntry {
+                hwnd: window.hwnd,
+                rect: window.rect,
+            })
+    }
+
+    fn find_windows(&self, parent: Option<WindowHandle>, class_name: &str) -> Vec<FormEntry> {
+        self.desktop()
+            .windows
+            .iter()
+            .filter(|window| window.parent == parent && window.class_name == class_name)
+            .map(|window| FormEntry {
+                hwnd: window.hwnd,
+                rect: window.rect,
+            })
+            .collect()
+    }
+
+    fn get_dpi(&self, hwnd: WindowHandle) -> Dpi {
+        let desktop = self.desktop();
+        let Some(window) = desktop.window(hwnd) else {
+            return Dpi::DEFAULT;
+        };
+        let center = window.rect.center();
+        desktop
+            .monitor_dpis
+            .iter()
+            .find(|(display, _)| display.contains(&center))
+            .map_or(Dpi::DEFAULT, |(_, dpi)| *dpi)
+    }
+
+    fn get_cursor_pos(&self) -> Option<Point> {
+        self.desktop().cursor_pos
+    }
+
+    fn get_start_menu_open(&self) -> bool {
+        self.desktop().start_menu_open
+    }
+
+    fn get_foreground_window(
+        &self,
+        previous: Option<&ForegroundWindow>,
+    ) -> Option<ForegroundWindow> {
+        let mut desktop = self.desktop();
+        let hwnd = desktop.foreground?;
+        let exe_name = match previous.filter(|previous| previous.hwnd == hwnd) {
+            Some(previous) => previous.exe_name.clone(),
+            None => {
+                desktop.process_queries += 1;
+                desktop.window(hwnd)?.exe_name.clone()
+            }
+        };
+        let window = desktop.window(hwnd)?;
+        Some(ForegroundWindow {
+            hwnd,
+            class_name: window.class_name.clone(),
+            title: window.title.clone(),
+            exe_name,
+        })
+    }
+
+    fn check_and_set_transparency_style(&self, hwnd: WindowHandle) -> bool {
+        let mut desktop = self.desktop();
+        match desktop.window_mut(hwnd) {
+            Some(window) if window.layered => true,
+            Some(window) => {
+                window.layered = true;
+                false
+            }
+            None => false,
+        }
+    }
+
+    fn set_window_alpha(&self, hwnd: WindowHandle, alpha: u8) -> bool {
+        let mut desktop = self.desktop();
+        let changed = match desktop.window_mut(hwnd) {
+            /* SetLayeredWindowAttributes fails on windows without the layered style. */
+            Some(window) if window.layered => {
+                window.alpha = alpha;
+                true
+            }
+            _ => false,
+        };
+        if changed {
+            desktop.alpha_changes.push(alpha);
+        }
+        changed
+    }
+
+    fn set_window_topmost(&self, hwnd: WindowHandle) {
+        if let Some(window) = self.desktop().window_mut(hwnd) {
+            window.topmost = true;
+        }
+    }
+
+    fn set_app_bar_state(&self, _hwnd: WindowHandle, option: isize) {
+        self.desktop().app_bar_state = Some(option);
+    }
+
+    fn move_window_on_tb(&self, hwnd: WindowHandle, x: i32, y: i32) -> bool {
+        let mut desktop = self.desktop();
+        let Some(window) = desktop.window_mut(hwnd) else {
+            return false;
+        };
+        let width = window.rect.width();
+        let height = window.rect.height();
+        window.rect = Rect::new(x, y, x + width, y + height);
+        true
+    }
+
+    fn set_window_position(&self, hwnd: WindowHandle, x: i32, y: i32) -> bool {
+        let mut desktop = self.desktop();
+        let Some(taskbar) = desktop.window(hwnd).map(|window| window.rect) else {
+            return false;
+        };
+        let (dx, dy) = (x - taskbar.left, y - taskbar.top);
+        /* children move with their parent */
+        let mut moved = vec![hwnd];
+        while let Some(parent) = moved.pop() {
+            for window in desktop.windows.iter_mut() {
+                if window.hwnd == parent {
+                    window.rect = window.rect.offset(dx, dy);
+                } else if window.parent == Some(parent) {
+                    moved.push(window.hwnd);
+                }
+            }
+        }
+        true
+    }
+
+    fn set_window_region(&self, hwnd: WindowHandle, region: &RegionSpec) {
+        let mut desktop = self.desktop();
+        if let Some(window) = desktop.window_mut(hwnd) {
+            window.region = Some(region.clone());
+        }
+        desktop.region = Some(SimulatedRegion {
+            hwnd,
+            spec: region.clone(),
+        });
+        desktop.region_updates += 1;
+    }
+
+    fn reset_window_style(&self, hwnd: WindowHandle) {
+        let mut desktop = self.desktop();
+        if let Some(window) = desktop.window_mut(hwnd) {
+            window.alpha = 255;
+            window.layered = false;
+            window.region = None;
+        }
+        if desktop.region.as_ref().map(|region| region.hwnd) == Some(hwnd) {
+            desktop.region = None;
+        }
+    }
+
+    fn get_monitors(&self) -> Vec<Monitor> {
+        self.desktop().monitors.clone()
+    }
+
+    fn get_visible_windows(&self) -> Vec<FormEntry> {
+        self.desktop()
+            .windows
+            .iter()
+            .filter(|window| window.parent.is_none() && window.visible && !window.overlay)
+            .filter(|window| !TASKBAR_CLASSES.contains(&window.class_name.as_str()))
+            .map(|window| FormEntry {
+                hwnd: window.hwnd,
+                rect: window.rect,
+            })
+            .collect()
+    }
+
+    fn get_work_area(&self) -> Rect {
+        self.desktop().work_area
+    }
+
+    fn set_work_area(&self, rect: &Rect) -> bool {
+        self.desktop().work_area = *rect;
+        true
+    }
+
+    fn send_workspace_and_display_change_msg(&self) {
+        self.desktop().broadcasts += 1;
+    }
+
+    fn restart_executable(&self, process_name: &str) -> bool {
+        self.desktop().restarted.push(process_name.to_string());
+        true
+    }
+}
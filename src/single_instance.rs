@@ -0,0 +1,86 @@
+use std::io;
+
+/*
+Only one hidden_tb may run the main loop per user, two loops fight over the alpha, the regions and the work area.
+On windows the guard is a named mutex, elsewhere a locked file stands in for it so the guard can be tested on any platform.
+Both are released by the system when the process ends, also after a crash.
+*/
+pub struct InstanceGuard {
+    _lock: lock::Lock,
+}
+
+/* the mutex name on windows, the lock file path elsewhere. */
+pub fn name_for_user() -> String {
+    let user = std::env::var(if cfg!(windows) { "USERNAME" } else { "USER" }).unwrap_or_default();
+    if cfg!(windows) {
+        format!(r"Local\hidden_tb-{}", user)
+    } else {
+        let path = std::env::temp_dir().join(format!("hidden_tb-{}.lock", user));
+        path.to_string_lossy().into_owned()
+    }
+}
+
+/* returns None while another process holds the guard {name}. The guard is held until it is dropped. */
+pub fn acquire(name: &str) -> io::Result<Option<InstanceGuard>> {
+    Ok(lock::Lock::try_acquire(name)?.map(|lock| InstanceGuard { _lock: lock }))
+}
+
+#[cfg(windows)]
+mod lock {
+    use std::io;
+
+    use windows::core::HSTRING;
+    use windows::Win32::Foundation::{CloseHandle, GetLastError, ERROR_ALREADY_EXISTS, HANDLE};
+    use windows::Win32::System::Threading::CreateMutexW;
+
+    pub struct Lock(HANDLE);
+
+    impl Lock {
+        pub fn try_acquire(name: &str) -> io::Result<Option<Lock>> {
+            /* Safety: the name is a valid null terminated string, the handle is closed on drop. */
+            unsafe {
+                let mutex = CreateMutexW(None, true, &HSTRING::from(name))?;
+                if GetLastError() == ERROR_ALREADY_EXISTS {
+                    CloseHandle(mutex).ok();
+                    return Ok(None);
+                }
+                Ok(Some(Lock(mutex)))
+            }
+        }
+    }
+
+    impl Drop for Lock {
+        fn drop(&mut self) {
+            /* Safety: the handle was opened by try_acquire and is closed once. */
+            unsafe {
+                CloseHandle(self.0).ok();
+            }
+        }
+    }
+}
+
+#[cfg(not(windows))]
+mod lock {
+    use std::fs::{File, OpenOptions, TryLockError};
+    use std::io;
+
+    /* the lock is released when the file is closed. */
+    pub struct Lock {
+        _file: File,
+    }
+
+    impl Lock {
+        pub fn try_acquire(name: &str) -> io::Result<Option<Lock>> {
+            let file = OpenOptions::new()
+                .create(true)
+                .truncate(false)
+                .write(true)
+                .open(name)?;
+            match file.try_lock() {
+                Ok(()) => Ok(Some(Lock { _file: file })),
+                Err(TryLockError::WouldBlock) => Ok(None),
+                Err(TryLockError::Error(e)) => Err(e),
+            }
+        }
+    }
+}
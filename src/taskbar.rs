@@ -1,48 +1,181 @@
-use crate::restart_process;
-use crate::tb_settings::{self, TbSettings};
-use crate::windows_calls::{self, TaskbarData, WantedHwnds, _ALWAYS_ON_TOP};
+use std::time::Duration;
+
+use crate::animation::{Clock, FadeAnimation, SystemClock};
+use crate::app_rules::{self, CompiledRule};
+use crate::geometry::{Dpi, Edge, Logical, Physical, Point, Rect};
+use crate::region;
+use crate::shell_backend::{
+    ForegroundWindow, ShellBackend, TaskbarData, TaskbarLocation, WantedHwnds, _ALWAYS_ON_TOP,
+};
+use crate::tb_settings::{self, AutohidePolicy, HideMode, RuleAction, TbSettings};
+
+/* pressure built up while the cursor is pinned to the hidden detection strip. */
+#[derive(Debug, Clone, Copy)]
+struct EdgePressure {
+    since: Duration,
+    /* distance of the cursor from the screen edge, moving further away releases the pressure. */
+    depth: i32,
+}
+
+/* visibility asked for by the show or hide command and the hover state at that time. */
+#[derive(Debug, Clone, Copy)]
+struct RequestedVisibility {
+    shown: bool,
+    hovering: bool,
+}
 
-/*  */
+/* the taskbar state machine. Every call into the shell goes through the backend. */
 #[derive(Debug)]
-pub struct Taskbar {
+pub struct Taskbar<B: ShellBackend> {
+    backend: B,
+    location: TaskbarLocation,
     settings: tb_settings::TbSettings,
-    taskbar_data: windows_calls::TaskbarData,
-    current_orig_taskbar_data: windows_calls::TaskbarData,
-    last_taskbar_data: windows_calls::TaskbarData,
+    taskbar_data: TaskbarData,
+    current_orig_taskbar_data: TaskbarData,
+    last_taskbar_data: TaskbarData,
     is_hidden: bool,
-    step_value: u8,
+    clock: Box<dyn Clock>,
+    fade: FadeAnimation,
     tray_shown_currently: bool,
     first_new_handles: bool,
-    display_rect: Option<windows::Win32::Foundation::RECT>,
+    display_rect: Option<Rect>,
+    /* distance the taskbar is currently moved off the screen edge by the slide hide mode. */
+    slide_offset: i32,
+    /* clock time since the cursor wants the taskbar shown or hidden, used for the reveal and hide delays. */
+    show_wanted_since: Option<Duration>,
+    hide_wanted_since: Option<Duration>,
+    edge_pressure: Option<EdgePressure>,
+    /* hover state of the last check, selects between the enter and the leave zone. */
+    hovering_tb: bool,
+    hovering_tray: bool,
+    foreground_rules: Vec<CompiledRule>,
+    /* a foreground rule has disabled the dynamic borders. */
+    dynamic_borders_suppressed: bool,
+    /* the pin command keeps the taskbar shown until it is unpinned. */
+    pinned: bool,
+    /* kept until the cursor enters or leaves the taskbar, then the autohide rules take over again. */
+    requested_visibility: Option<RequestedVisibility>,
+    /* intellihide: a window overlaps the taskbar, enumerating the windows is too slow for every tick. */
+    window_overlapping: bool,
 }
 
-impl Taskbar {
-    pub fn new() -> Self {
-        let settings = TbSettings::new();
-        let step_value = 255 / settings.get_animation_steps();
+impl<B: ShellBackend> Taskbar<B> {
+    pub fn new(settings: TbSettings, backend: B) -> Self {
+        Self::with_clock(settings, backend, Box::<SystemClock>::default())
+    }
+
+    /* the clock drives the fade animation, tests pass a ManualClock to control the timeline. */
+    pub fn with_clock(settings: TbSettings, backend: B, clock: Box<dyn Clock>) -> Self {
+        Self::for_location(settings, backend, clock, TaskbarLocation::Primary)
+    }
+
+    /* a taskbar for the taskbar window at {location}, secondary taskbars use the display of their monitor. */
+    pub fn for_location(
+        settings: TbSettings,
+        backend: B,
+        clock: Box<dyn Clock>,
+        location: TaskbarLocation,
+    ) -> Self {
+        let fade = FadeAnimation::new(
+            255,
+            settings.get_animation_steps(),
+            settings.get_animation_easing(),
+        );
+        let foreground_rules = app_rules::compile_rules(&settings.get_foreground_rules());
         let wanted_hwnds = WantedHwnds::new(&settings);
-        let tb_data = windows_calls::TaskbarData::new(&wanted_hwnds);
+        let tb_data = TaskbarData::find(&backend, &wanted_hwnds, location, Point::default());
+        let display_rect = match location {
+            TaskbarLocation::Primary => backend
+                .get_monitors()
+                .iter()
+                .find(|monitor| monitor.is_primary())
+                .map(|monitor| monitor.get_display()),
+            TaskbarLocation::Secondary(display) => Some(display),
+        };
         Taskbar {
+            backend,
+            location,
             last_taskbar_data: TaskbarData::default(),
             taskbar_data: tb_data.clone(),
             current_orig_taskbar_data: tb_data,
             settings,
-            step_value,
             is_hidden: false,
+            clock,
+            fade,
             tray_shown_currently: false,
             first_new_handles: true,
-            display_rect: None,
+            display_rect,
+            slide_offset: 0,
+            show_wanted_since: None,
+            hide_wanted_since: None,
+            edge_pressure: None,
+            hovering_tb: false,
+            hovering_tray: false,
+            foreground_rules,
+            dynamic_borders_suppressed: false,
+            pinned: false,
+            requested_visibility: None,
+            window_overlapping: false,
+        }
+    }
+
+    pub fn get_backend(&self) -> &B {
+        &self.backend
+    }
+
+    pub fn get_location(&self) -> TaskbarLocation {
+        self.location
+    }
+
+    /* the screen edge the taskbar is docked to, detected from its docked rect on the display. */
+    pub fn get_edge(&self) -> Edge {
+        match (&self.taskbar_data.taskbar, &self.display_rect) {
+            (Some(taskbar), Some(display_rect)) => Edge::of(&taskbar.rect, display_rect),
+            _ => Edge::default(),
+        }
+    }
+
+    /* dpi of the monitor of the taskbar. Every length from the settings is converted with it. */
+    pub fn get_dpi(&self) -> Dpi {
+        self.taskbar_data.dpi
+    }
+
+    /* the size of the taskbar across its edge, the height of a horizontal and the width of a vertical taskbar. */
+    fn get_thickness(&self, rect: &Rect) -> Physical {
+        match self.get_edge().is_vertical() {
+            true => Physical(rect.width()),
+            false => Physical(rect.height()),
         }
     }
 
+    pub fn is_hidden(&self) -> bool {
+        self.is_hidden
+    }
+
+    pub fn is_animating(&self) -> bool {
+        self.fade.is_running()
+    }
+
+    /* progress of the running fade between 0.0 and 1.0, 1.0 if no fade is running. */
+    pub fn get_animation_progress(&self) -> f64 {
+        self.fade.progress(self.clock.now())
+    }
+
     pub fn refresh_handles(&mut self) {
-        let taskbar_data = windows_calls::TaskbarData::new(&WantedHwnds::new(&self.settings));
+        let taskbar_data = self.fetch_new_handles();
         self.taskbar_data = taskbar_data;
         self.last_taskbar_data = TaskbarData::default();
     }
 
+    /* the rects are always reported at the docked position, even while the taskbar is slid away. */
     pub fn fetch_new_handles(&self) -> TaskbarData {
-        windows_calls::TaskbarData::new(&WantedHwnds::new(&self.settings))
+        let outward = self.get_edge().outward();
+        TaskbarData::find(
+            &self.backend,
+            &WantedHwnds::new(&self.settings),
+            self.location,
+            Point::new(outward.x * self.slide_offset, outward.y * self.slide_offset),
+        )
     }
 
     /* calls on_new_handles to update all routines that have to react on new handles. */
@@ -58,8 +191,8 @@ impl Taskbar {
 
     pub fn refresh_area_and_set_on_top(&self) {
         if let Some(taskbar) = &self.taskbar_data.taskbar {
-            windows_calls::set_handle_to_topmost(&taskbar.hwnd);
-            windows_calls::set_app_bar_state(&taskbar.hwnd, _ALWAYS_ON_TOP);
+            self.backend.set_window_topmost(taskbar.hwnd);
+            self.backend.set_app_bar_state(taskbar.hwnd, _ALWAYS_ON_TOP);
         }
     }
 
@@ -88,113 +221,282 @@ impl Taskbar {
         println!("None: {}", none);
     }
 
+    /*
+    Zone the cursor has to be in to hover over {rect}. While hidden only the detection strip at the docked edge counts.
+    Once hovering, the leave zone is larger than the enter zone by the leave margin, so the cursor resting on the
+    border of an island does not flicker between both states.
+    */
+    fn hover_zone(&self, rect: Rect, was_hovering: bool) -> Rect {
+        let edge = self.get_edge();
+        let dpi = self.get_dpi();
+        let bottom_offset = dpi.to_physical(self.settings.get_tb_rect_bottom_offset());
+        let zone = rect.grow_edge(edge, bottom_offset);
+        if self.settings.get_autohide() && self.is_hidden {
+            return zone.strip(
+                edge,
+                dpi.to_physical(self.settings.get_tb_rect_detection_size_in_pixel())
+                    + bottom_offset,
+            );
+        }
+        if was_hovering {
+            return zone.expand(dpi.to_physical(self.settings.get_hover_leave_margin_in_pixel()));
+        }
+        zone
+    }
+
     pub fn is_hovering_on_tray(&self) -> bool {
         if let Some(tray_entry) = &self.taskbar_data.tray {
-            if let Some(cursor_pos) = windows_calls::get_cursor_pos() {
-                let mut hidden_rect = tray_entry.rect;
-                hidden_rect.bottom += self.settings.get_tb_rect_bottom_offset();
-                if self.settings.get_autohide() && self.is_hidden {
-                    hidden_rect.top = hidden_rect.bottom
-                        - self.settings.get_tb_rect_detection_size_in_pixel()
-                        - self.settings.get_tb_rect_bottom_offset();
-                    return windows_calls::get_point_in_rect(&hidden_rect, &cursor_pos);
-                }
-                return windows_calls::get_point_in_rect(&hidden_rect, &cursor_pos);
+            if let Some(cursor_pos) = self.backend.get_cursor_pos() {
+                return self
+                    .hover_zone(tray_entry.rect, self.hovering_tray)
+                    .contains(&cursor_pos);
             }
         }
         false
     }
 
-    pub fn is_hovering_on_tb(&self) -> bool {
-        let wanted_handle = match self.settings.get_enable_dynamic_borders() {
+    /*
+    The visible part of the taskbar: the applist island with dynamic borders, otherwise the whole taskbar.
+    The island of a vertical taskbar is computed transposed, left and right then mean top and bottom.
+    */
+    fn island_rect(&self) -> Option<Rect> {
+        let wanted_handle = match self.has_dynamic_borders() {
             true => &self.taskbar_data.applist,
             false => &self.taskbar_data.taskbar,
         };
-        if let Some(wanted_entry) = &wanted_handle {
-            if let Some(cursor_pos) = windows_calls::get_cursor_pos() {
-                let mut hidden_rect = wanted_entry.rect;
-                if self.settings.get_enable_dynamic_borders() {
-                    let tb_rect = match self.taskbar_data.taskbar {
-                        Some(ref tb) => tb.rect,
-                        None => return false,
-                    };
-
-                    //offset the left rect to include windows and search button etc, which is not contained in the applist handle
-                    hidden_rect.left = tb_rect.right - wanted_entry.rect.right;
-                    // Offset left and right applist based on margins set in the settings
-                    hidden_rect.left -= self.settings.get_margin_offset_left();
-                    hidden_rect.right += self.settings.get_margin_offset_right();
-                    hidden_rect.bottom = tb_rect.bottom;
-                    hidden_rect.top = tb_rect.top;
-                }
-                hidden_rect.bottom += self.settings.get_tb_rect_bottom_offset();
-                if self.settings.get_autohide() && self.is_hidden {
-                    hidden_rect.top = hidden_rect.bottom
-                        - self.settings.get_tb_rect_detection_size_in_pixel()
-                        - self.settings.get_tb_rect_bottom_offset();
-                    return windows_calls::get_point_in_rect(&hidden_rect, &cursor_pos);
-                }
-                return windows_calls::get_point_in_rect(&hidden_rect, &cursor_pos);
+        let wanted_entry = wanted_handle.as_ref()?;
+        if !self.has_dynamic_borders() {
+            return Some(wanted_entry.rect);
+        }
+        let vertical = self.get_edge().is_vertical();
+        let transpose = |rect: Rect| match vertical {
+            true => rect.transposed(),
+            false => rect,
+        };
+        let tb_rect = transpose(self.taskbar_data.taskbar.as_ref()?.rect);
+        let applist_rect = transpose(wanted_entry.rect);
+        let mut island_rect = applist_rect;
+
+        //offset the left rect to include windows and search button etc, which is not contained in the applist handle
+        island_rect.left = tb_rect.left + tb_rect.right - applist_rect.right;
+        // Offset left and right applist based on margins set in the settings
+        let dpi = self.get_dpi();
+        island_rect.left -= dpi.to_physical(self.settings.get_margin_offset_left()).0;
+        island_rect.right += dpi.to_physical(self.settings.get_margin_offset_right()).0;
+        island_rect.bottom = tb_rect.bottom;
+        island_rect.top = tb_rect.top;
+        Some(transpose(island_rect))
+    }
+
+    pub fn is_hovering_on_tb(&self) -> bool {
+        if let Some(island_rect) = self.island_rect() {
+            if let Some(cursor_pos) = self.backend.get_cursor_pos() {
+                return self
+                    .hover_zone(island_rect, self.hovering_tb)
+                    .contains(&cursor_pos);
             }
         }
         false
     }
 
+    /* intellihide: true if a visible window on the monitor of the taskbar overlaps the taskbar or its island. */
+    pub fn is_window_overlapping(&self) -> bool {
+        let (Some(taskbar_entry), Some(island_rect)) =
+            (&self.taskbar_data.taskbar, self.island_rect())
+        else {
+            return false;
+        };
+        self.backend
+            .get_visible_windows()
+            .iter()
+            .filter(|window| window.hwnd != taskbar_entry.hwnd)
+            .filter(|window| {
+                self.display_rect
+                    .is_none_or(|display_rect| display_rect.intersects(&window.rect))
+            })
+            .any(|window| window.rect.intersects(&island_rect))
+    }
+
+    /* checks the windows again for intellihide, the infrequent routine calls it. */
+    pub fn update_window_overlap(&mut self) {
+        self.window_overlapping = self.is_window_overlapping();
+    }
+
+    /*
+    Push to reveal. The cursor can not move past the screen edge, so the pressure is the time it stays pinned to it.
+    Moving along the edge keeps the pressure, moving away from the edge starts over. Returns true once the time is reached.
+    */
+    fn is_pushing_against_edge(&mut self, is_hovering: bool) -> bool {
+        let cursor_pos = match self.backend.get_cursor_pos() {
+            Some(cursor_pos) if is_hovering => cursor_pos,
+            _ => {
+                self.edge_pressure = None;
+                return false;
+            }
+        };
+        let now = self.clock.now();
+        let depth = self.distance_from_edge(cursor_pos);
+        if self
+            .edge_pressure
+            .is_some_and(|pressure| depth > pressure.depth)
+        {
+            self.edge_pressure = None;
+        }
+        let pressure = self
+            .edge_pressure
+            .get_or_insert(EdgePressure { since: now, depth });
+        pressure.depth = depth;
+
+        let time = Duration::from_millis(self.settings.get_push_reveal_time_in_ms());
+        now.saturating_sub(pressure.since) >= time
+    }
+
+    /* how far {point} is from the screen edge the taskbar is docked to, 0 on the outermost pixels. */
+    fn distance_from_edge(&self, point: Point) -> i32 {
+        let Some(display) = self.display_rect else {
+            return 0;
+        };
+        match self.get_edge() {
+            Edge::Left => point.x - display.left,
+            Edge::Top => point.y - display.top,
+            Edge::Right => display.right - 1 - point.x,
+            Edge::Bottom => display.bottom - 1 - point.y,
+        }
+    }
+
     fn set_taskbar_alpha(&self, alpha: u8) -> bool {
         if let Some(taskbar_entry) = &self.taskbar_data.taskbar {
-            return windows_calls::set_window_alpha(&taskbar_entry.hwnd, alpha);
+            return self.backend.set_window_alpha(taskbar_entry.hwnd, alpha);
         }
 
         false
     }
 
+    /* moves the taskbar off the screen edge by the part of its thickness that {visibility} 0..=255 hides. */
+    fn set_taskbar_slide(&mut self, visibility: u8) -> bool {
+        if let Some((hwnd, docked)) = self
+            .taskbar_data
+            .taskbar
+            .as_ref()
+            .map(|tb| (tb.hwnd, tb.rect))
+        {
+            let offset = (self.get_thickness(&docked).0 as f64 * (255 - visibility) as f64 / 255.0)
+                .round() as i32;
+            let outward = self.get_edge().outward();
+            if self.backend.set_window_position(
+                hwnd,
+                docked.left + outward.x * offset,
+                docked.top + outward.y * offset,
+            ) {
+                self.slide_offset = offset;
+                return true;
+            }
+        }
+        false
+    }
+
+    /* applies the visibility of the running animation in the configured hide mode. */
+    fn set_taskbar_visibility(&mut self, visibility: u8) -> bool {
+        match self.settings.get_hide_mode() {
+            HideMode::Fade => self.set_taskbar_alpha(visibility),
+            HideMode::Slide => self.set_taskbar_slide(visibility),
+        }
+    }
+
     pub fn check_and_set_taskbar_transparency_state(&self) -> bool {
         if let Some(taskbar_entry) = &self.taskbar_data.taskbar {
-            return windows_calls::check_and_set_transparency_style(&taskbar_entry.hwnd);
+            return self
+                .backend
+                .check_and_set_transparency_style(taskbar_entry.hwnd);
         }
         false
     }
 
+    /* starts fading out. The taskbar counts as hidden once the fade has finished. */
     pub fn hide_taskbar(&mut self) {
-        let mut alpha: u8 = 255;
-        let mut changed = true;
-        for step in 0..self.settings.get_animation_steps() {
-            alpha = alpha.saturating_sub(self.step_value);
-            if step == self.settings.get_animation_steps() - 1 {
-                alpha = 0;
-            }
-            changed = changed && self.set_taskbar_alpha(alpha);
-            std::thread::sleep(std::time::Duration::from_millis(
-                self.settings.get_animation_time_in_ms(),
-            ));
-        }
-        if changed {
-            self.is_hidden = true;
+        if self.fade.get_target() == 0 {
+            return;
         }
+        let duration = Duration::from_millis(self.settings.get_hide_animation_duration_in_ms());
+        self.fade.start(0, duration, self.clock.now());
     }
 
+    /* starts fading in, reversing a running fade out from its current alpha. */
     pub fn show_taskbar(&mut self) {
-        let mut alpha: u8 = 0;
-        let mut changed = true;
+        if self.fade.get_target() == 255 {
+            return;
+        }
+        self.is_hidden = false;
+        let duration = Duration::from_millis(self.settings.get_show_animation_duration_in_ms());
+        self.fade.start(255, duration, self.clock.now());
+    }
+
+    pub fn is_pinned(&self) -> bool {
+        self.pinned
+    }
 
-        for step in 0..self.settings.get_animation_steps() {
-            alpha = alpha.saturating_add(self.step_value);
-            if step == self.settings.get_animation_steps() - 1 {
-                alpha = 255;
+    pub fn set_pinned(&mut self, pinned: bool) {
+        self.pinned = pinned;
+        self.requested_visibility = None;
+    }
+
+    /* shows or hides the taskbar with the next tick, until the cursor enters or leaves the taskbar. */
+    pub fn request_visibility(&mut self, shown: bool) {
+        self.requested_visibility = Some(RequestedVisibility {
+            shown,
+            hovering: self.hovering_tb || (self.has_dynamic_borders() && self.hovering_tray),
+        });
+    }
+
+    /* shows a pinned taskbar and follows a requested visibility. Returns false while the autohide rules apply. */
+    fn follow_commands(&mut self, hovering: bool) -> bool {
+        let shown = match self.requested_visibility {
+            _ if self.pinned => true,
+            Some(requested) if requested.hovering == hovering => requested.shown,
+            _ => {
+                self.requested_visibility = None;
+                return false;
             }
-            changed = changed && self.set_taskbar_alpha(alpha);
-            std::thread::sleep(std::time::Duration::from_millis(
-                self.settings.get_animation_time_in_ms(),
-            ));
+        };
+        self.show_wanted_since = None;
+        self.hide_wanted_since = None;
+        self.edge_pressure = None;
+        match shown {
+            true => self.show_taskbar(),
+            false => self.hide_taskbar(),
+        }
+        true
+    }
+
+    /* applies the alpha or slide position of the running animation for the elapsed time. Called on every tick of the main loop. */
+    pub fn advance_animation(&mut self) {
+        if !self.fade.is_running() {
+            return;
+        }
+        let last_alpha = self.fade.get_alpha();
+        let alpha = self.fade.tick(self.clock.now());
+        let changed = alpha == last_alpha || self.set_taskbar_visibility(alpha);
+        if self.fade.is_running() {
+            return;
         }
-        if changed {
-            self.is_hidden = false;
+        if !changed {
+            /* the final state could not be set, restart the animation on the next state check */
+            self.fade = FadeAnimation::new(
+                last_alpha,
+                self.settings.get_animation_steps(),
+                self.settings.get_animation_easing(),
+            );
+            return;
+        }
+        if alpha == 0 {
+            self.is_hidden = true;
+        } else {
             // Revert change, because the taskbar has problems showing in front of other widnows with geforce
             // experience overlay enabled with showing fps counter or something else with performance overlay...
             // after disabling it it runs.
             //Set taskbar to topmost again, because it is not set to topmost when it is hidden
             if let Some(taskbar_entry) = &self.taskbar_data.taskbar {
-                windows_calls::set_window_topmost(&taskbar_entry.hwnd);
+                self.backend.set_window_topmost(taskbar_entry.hwnd);
             }
         }
     }
@@ -203,7 +505,8 @@ impl Taskbar {
         if let Some(tray_entry) = &self.taskbar_data.tray {
             if let Some(apps_entry) = &self.taskbar_data.applist {
                 //Todo: maybe can call LockWindowUpdate so it doesnt update the window on pressing the up arrow button on the tray
-                windows_calls::move_window_on_tb(&tray_entry.hwnd, apps_entry.rect.right, 0);
+                self.backend
+                    .move_window_on_tb(tray_entry.hwnd, apps_entry.rect.right, 0);
             }
         }
     }
@@ -213,15 +516,50 @@ impl Taskbar {
     }
 
     pub fn call_dynamic_update(&mut self, hovering_over_tray: bool, hovering_over_widgets: bool) {
-        windows_calls::create_rounded_region(
+        if let Some(region) = region::create_rounded_region_for_taskbar(
             &self.settings,
             &self.taskbar_data,
+            self.get_edge(),
             hovering_over_tray,
             hovering_over_widgets,
-        );
+        ) {
+            if let Some(taskbar_entry) = &self.taskbar_data.taskbar {
+                self.backend.set_window_region(taskbar_entry.hwnd, &region);
+            }
+        }
+    }
+
+    /* dynamic borders are enabled and not disabled by a foreground rule. */
+    fn has_dynamic_borders(&self) -> bool {
+        self.settings.get_enable_dynamic_borders() && !self.dynamic_borders_suppressed
     }
 
-    pub fn set_display_area(&mut self, display_rect: windows::Win32::Foundation::RECT) {
+    /* shows the whole taskbar while suppressed and restores the dynamic region afterwards. */
+    fn set_dynamic_borders_suppressed(&mut self, suppressed: bool) {
+        if self.dynamic_borders_suppressed == suppressed {
+            return;
+        }
+        self.dynamic_borders_suppressed = suppressed;
+        if !self.settings.get_enable_dynamic_borders() {
+            return;
+        }
+        if suppressed {
+            if let Some(taskbar_entry) = &self.taskbar_data.taskbar {
+                let region = region::create_full_region(&taskbar_entry.rect);
+                self.backend.set_window_region(taskbar_entry.hwnd, &region);
+            }
+        } else {
+            self.tray_shown_currently = self.is_hovering_on_tray();
+            self.call_dynamic_update(self.tray_shown_currently, false);
+        }
+    }
+
+    /* the action of the first foreground rule matching the foreground window. */
+    fn foreground_rule_action(&self, foreground: Option<&ForegroundWindow>) -> Option<RuleAction> {
+        app_rules::find_action(&self.foreground_rules, foreground?)
+    }
+
+    pub fn set_display_area(&mut self, display_rect: Rect) {
         let last_display_rect = self.display_rect;
 
         self.display_rect = Some(display_rect);
@@ -261,14 +599,20 @@ impl Taskbar {
         self.taskbar_data.display_rect = self.display_rect;
 
         /* Bugfix Windows 11. The applist.right stores the middle now of the screen? Left is one Icon shorter aswell... */
+        /* only the horizontal primary taskbar uses the MSTaskSwWClass applist the bugfix is made for. */
         if self.settings.get_windows_11_bugfix()
-            && self.taskbar_data.applist.is_some()
-            && self.taskbar_data.taskbar.is_some()
+            && self.location == TaskbarLocation::Primary
+            && !self.get_edge().is_vertical()
         {
-            let applist = self.taskbar_data.applist.as_mut().unwrap();
-            let taskbar = self.taskbar_data.taskbar.as_ref().unwrap();
-            applist.rect.right = taskbar.rect.right - applist.rect.left + 44;
-            applist.rect.left = taskbar.rect.left + applist.rect.left - 44;
+            /* the width of one icon */
+            let icon = self.get_dpi().to_physical(Logical(44)).0;
+            if let (Some(applist), Some(taskbar)) = (
+                self.taskbar_data.applist.as_mut(),
+                self.taskbar_data.taskbar.as_ref(),
+            ) {
+                applist.rect.right = taskbar.rect.right - applist.rect.left + icon;
+                applist.rect.left = taskbar.rect.left + applist.rect.left - icon;
+            }
         }
 
         if self.settings.get_merge_tray() {
@@ -277,7 +621,7 @@ impl Taskbar {
         if self.settings.get_merge_widgets() {
             self.merge_widgets_with_applist();
         }
-        if self.settings.get_enable_dynamic_borders() {
+        if self.has_dynamic_borders() {
             self.call_dynamic_update(self.is_hovering_on_tray(), false);
         }
     }
@@ -293,20 +637,74 @@ impl Taskbar {
         }
     }
 
-    pub fn handle_taskbar_state(&mut self) {
-        let start_menu_open = windows_calls::get_start_menu_open();
+    /*
+    Replaces the settings while running without losing the hidden state. A taskbar that stops autohiding is shown,
+    a changed hide mode is undone in the old mode and applied in the new one. Handles and regions are updated on the next tick.
+    */
+    pub fn apply_settings(&mut self, settings: TbSettings) {
+        let old_settings = std::mem::replace(&mut self.settings, settings);
+        let autohide_disabled = old_settings.get_autohide() && !self.settings.get_autohide();
+        if autohide_disabled || old_settings.get_hide_mode() != self.settings.get_hide_mode() {
+            match old_settings.get_hide_mode() {
+                HideMode::Fade => self.set_taskbar_alpha(255),
+                HideMode::Slide => self.set_taskbar_slide(255),
+            };
+            if autohide_disabled {
+                self.fade = FadeAnimation::new(
+                    255,
+                    self.settings.get_animation_steps(),
+                    self.settings.get_animation_easing(),
+                );
+                self.is_hidden = false;
+            } else {
+                self.set_taskbar_visibility(self.fade.get_alpha());
+            }
+        }
+        self.fade.set_steps(self.settings.get_animation_steps());
+        self.fade.set_easing(self.settings.get_animation_easing());
+        self.foreground_rules = app_rules::compile_rules(&self.settings.get_foreground_rules());
+        self.show_wanted_since = None;
+        self.hide_wanted_since = None;
+        self.edge_pressure = None;
+
+        if autohide_disabled && self.location == TaskbarLocation::Primary {
+            if let Some(taskbar_entry) = &self.taskbar_data.taskbar {
+                self.reset_work_area(&taskbar_entry.rect);
+            }
+        }
+        if old_settings.get_enable_dynamic_borders() && !self.settings.get_enable_dynamic_borders()
+        {
+            if let Some(taskbar_entry) = &self.taskbar_data.taskbar {
+                let region = region::create_full_region(&taskbar_entry.rect);
+                self.backend.set_window_region(taskbar_entry.hwnd, &region);
+            }
+        }
+        if WantedHwnds::new(&old_settings) != WantedHwnds::new(&self.settings) {
+            self.refresh_handles();
+        }
+        /* the next new handles recompute the regions and merges with the new settings */
+        self.first_new_handles = true;
+    }
+
+    /* {foreground} is queried once per tick by Taskbars for every taskbar. */
+    pub fn handle_taskbar_state(&mut self, foreground: Option<&ForegroundWindow>) {
+        let start_menu_open = self.backend.get_start_menu_open();
+        let rule_action = self.foreground_rule_action(foreground);
+        self.set_dynamic_borders_suppressed(rule_action == Some(RuleAction::DisableDynamicBorders));
+        self.hovering_tray = self.is_hovering_on_tray();
+        self.hovering_tb = self.is_hovering_on_tb();
 
         /* for autohiding tray logic */
         if !self.settings.get_dynamic_borders_show_tray()
             && self
                 .settings
                 .get_dynamic_borders_show_tray_if_disabled_on_hover()
-            && self.settings.get_enable_dynamic_borders()
+            && self.has_dynamic_borders()
         {
             if start_menu_open {
                 self.tray_shown_currently = true;
                 self.call_dynamic_update(true, false);
-            } else if self.is_hovering_on_tray() {
+            } else if self.hovering_tray {
                 if !self.tray_shown_currently {
                     self.tray_shown_currently = true;
                     self.call_dynamic_update(true, false);
@@ -321,30 +719,139 @@ impl Taskbar {
             return;
         }
 
-        let is_hovering = self.is_hovering_on_tb();
-
-        if start_menu_open
-            || is_hovering
-            || (self.settings.get_enable_dynamic_borders() && self.is_hovering_on_tray())
-        {
-            if self.is_hidden {
+        let mut is_hovering =
+            self.hovering_tb || (self.has_dynamic_borders() && self.hovering_tray);
+        if self.follow_commands(is_hovering) {
+            self.advance_animation();
+            return;
+        }
+        if self.settings.get_push_to_reveal() && self.is_hidden {
+            is_hovering = self.is_pushing_against_edge(is_hovering);
+        } else {
+            self.edge_pressure = None;
+        }
+        let keep_visible = is_hovering
+            || (self.settings.get_autohide_policy() == AutohidePolicy::Intellihide
+                && !self.window_overlapping);
+        let now = self.clock.now();
+
+        if start_menu_open || rule_action == Some(RuleAction::AlwaysShow) {
+            self.show_wanted_since = None;
+            self.hide_wanted_since = None;
+            self.show_taskbar();
+        } else if rule_action == Some(RuleAction::AlwaysHide) {
+            self.show_wanted_since = None;
+            self.hide_wanted_since = None;
+            self.hide_taskbar();
+        } else if keep_visible {
+            /* hover intent: the cursor has to dwell in the hot zone for the reveal delay */
+            self.hide_wanted_since = None;
+            let since = *self.show_wanted_since.get_or_insert(now);
+            let delay = Duration::from_millis(self.settings.get_reveal_delay_in_ms());
+            if now.saturating_sub(since) >= delay {
                 self.show_taskbar();
             }
-        } else if !self.is_hidden {
-            self.hide_taskbar();
+        } else {
+            /* grace period after leaving before the taskbar starts to hide */
+            self.show_wanted_since = None;
+            let since = *self.hide_wanted_since.get_or_insert(now);
+            let delay = Duration::from_millis(self.settings.get_hide_delay_in_ms());
+            if now.saturating_sub(since) >= delay {
+                self.hide_taskbar();
+            }
         }
+        self.advance_animation();
     }
 
     pub fn clean_up(&mut self) {
+        if self.slide_offset != 0 {
+            /* move a slid away taskbar back to its docked position */
+            self.set_taskbar_slide(255);
+        }
         if let Some(taskbar_data) = &self.taskbar_data.taskbar {
-            windows_calls::reset_taskbar(&taskbar_data.hwnd, &taskbar_data.rect);
+            self.backend.reset_window_style(taskbar_data.hwnd);
+            // reset taskbar region
+            if self.location == TaskbarLocation::Primary {
+                self.reset_work_area(&taskbar_data.rect);
+            }
+        }
+    }
+
+    fn reset_work_area(&self, rect: &Rect) {
+        let mut work_area = Rect::default();
+        let mut found_primary_display = false;
+        for primary_monitor in self
+            .backend
+            .get_monitors()
+            .iter()
+            .filter(|m| m.is_primary())
+        {
+            found_primary_display = true;
+            work_area = primary_monitor
+                .get_display()
+                .grow_edge(self.get_edge(), -self.get_thickness(rect));
+        }
+        if !found_primary_display {
+            panic!("could not find primary display while calling reset on exit");
+        }
+
+        if self.backend.set_work_area(&work_area) {
+            self.backend.send_workspace_and_display_change_msg();
+            return;
+        }
+        /* no call worked, todo: log error */
+        eprint!("failed to reset workspace area");
+    }
+
+    /* the whole display except one pixel at the docked edge, so windows do not cover the hidden detection strip. */
+    fn work_area_for_autohide(&self, display_rect: &Rect) -> Rect {
+        let mut work_area = display_rect.grow_edge(self.get_edge(), Physical(-1));
+        work_area.top += self
+            .get_dpi()
+            .to_physical(self.settings.get_workspace_offset_top())
+            .0;
+        work_area
+    }
+
+    fn compare_rect_to_workspace_region_for_autohide(&self, current_rect: &Rect) -> bool {
+        let workarea_rect = self.backend.get_work_area();
+        let expected = self.work_area_for_autohide(current_rect);
+        workarea_rect.width() == expected.width() && workarea_rect.height() == expected.height()
+    }
+
+    fn set_window_region_for_autohide(&self, rect: &Rect) {
+        let work_area = self.work_area_for_autohide(rect);
+        if self.backend.set_work_area(&work_area) {
+            return;
+        }
+        /* no call worked, todo: log error */
+        eprint!("failed to reset workspace area");
+    }
+
+    /* this function checks if each monitor is configured correctly for the autohide feature. */
+    pub fn check_and_update_workspace_region_for_autohide(&mut self) {
+        let mut change_in_workspace = false;
+        let monitors = self.backend.get_monitors();
+        for primary_monitor in monitors.iter().filter(|m| m.is_primary()) {
+            let display_area = primary_monitor.get_display();
+            self.set_display_area(display_area);
+            if !self.compare_rect_to_workspace_region_for_autohide(&display_area) {
+                /* work area is not configured correctly. Setting to autohide. */
+                self.set_window_region_for_autohide(&display_area);
+                change_in_workspace = true;
+            }
+        }
+        if change_in_workspace {
+            self.backend.send_workspace_and_display_change_msg();
+            self.refresh_area_and_set_on_top();
+            self.send_restarts();
         }
     }
 
     pub fn send_restarts(&self) {
         for process in self.settings.get_restart_executables() {
             println!("Restarting {}", process);
-            if restart_process::restart_process(&process).is_err() {
+            if !self.backend.restart_executable(&process) {
                 println!("Failed to restart {}", process);
             }
         }
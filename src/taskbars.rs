@@ -0,0 +1,151 @@
+use crate::animation::{Clock, SystemClock};
+use crate::shell_backend::{ForegroundWindow, ShellBackend, TaskbarLocation};
+use crate::taskbar::Taskbar;
+use crate::tb_settings::TbSettings;
+
+/*
+Every taskbar hidden_tb manages: the primary taskbar and one taskbar per secondary monitor.
+Each taskbar has its own hover zone, animation and dynamic border region. The work area is only handled by the primary taskbar.
+*/
+#[derive(Debug)]
+pub struct Taskbars<B: ShellBackend + Clone> {
+    backend: B,
+    settings: TbSettings,
+    clock: Box<dyn Clock>,
+    primary: Taskbar<B>,
+    secondaries: Vec<Taskbar<B>>,
+    pinned: bool,
+    /* shared by every taskbar, so the foreground window is only queried once per tick. */
+    foreground: Option<ForegroundWindow>,
+}
+
+impl<B: ShellBackend + Clone> Taskbars<B> {
+    pub fn new(settings: TbSettings, backend: B) -> Self {
+        Self::with_clock(settings, backend, Box::<SystemClock>::default())
+    }
+
+    pub fn with_clock(settings: TbSettings, backend: B, clock: Box<dyn Clock>) -> Self {
+        let primary = Taskbar::with_clock(settings.clone(), backend.clone(), clock.clone_box());
+        Taskbars {
+            backend,
+            settings,
+            clock,
+            primary,
+            secondaries: Vec::new(),
+            pinned: false,
+            foreground: None,
+        }
+    }
+
+    pub fn primary(&self) -> &Taskbar<B> {
+        &self.primary
+    }
+
+    pub fn primary_mut(&mut self) -> &mut Taskbar<B> {
+        &mut self.primary
+    }
+
+    pub fn secondaries(&self) -> &[Taskbar<B>] {
+        &self.secondaries
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut Taskbar<B>> {
+        std::iter::once(&mut self.primary).chain(self.secondaries.iter_mut())
+    }
+
+    /*
+    Matches the secondary taskbars to the monitors from get_monitors. Taskbars of removed monitors are restored and dropped,
+    a new monitor gets a taskbar as soon as all of its handles are found. Returns true if the taskbars changed.
+    */
+    pub fn sync_monitors(&mut self) -> bool {
+        let displays: Vec<_> = self
+            .backend
+            .get_monitors()
+            .iter()
+            .filter(|monitor| !monitor.is_primary())
+            .map(|monitor| monitor.get_display())
+            .collect();
+
+        let removed: Vec<_> = self
+            .secondaries
+            .extract_if(.., |taskbar| match taskbar.get_location() {
+                TaskbarLocation::Secondary(display) => !displays.contains(&display),
+                TaskbarLocation::Primary => true,
+            })
+            .collect();
+        let mut changed = !removed.is_empty();
+        /* a monitor that only changed its geometry keeps its taskbar window, it is restored before a new taskbar takes it over */
+        for mut taskbar in removed {
+            taskbar.clean_up();
+        }
+
+        for display in displays {
+            let location = TaskbarLocation::Secondary(display);
+            if self
+                .secondaries
+                .iter()
+                .any(|taskbar| taskbar.get_location() == location)
+            {
+                continue;
+            }
+            let mut taskbar = Taskbar::for_location(
+                self.settings.clone(),
+                self.backend.clone(),
+                self.clock.clone_box(),
+                location,
+            );
+            if taskbar.contains_none() {
+                continue;
+            }
+            println!("found secondary taskbar on {:?}", display);
+            if self.settings.get_autohide() || self.settings.get_enable_dynamic_borders() {
+                taskbar.check_and_set_taskbar_transparency_state();
+            }
+            taskbar.automation_routine();
+            taskbar.set_pinned(self.pinned);
+            self.secondaries.push(taskbar);
+            changed = true;
+        }
+        changed
+    }
+
+    pub fn apply_settings(&mut self, settings: &TbSettings) {
+        self.settings = settings.clone();
+        for taskbar in self.iter_mut() {
+            taskbar.apply_settings(settings.clone());
+        }
+    }
+
+    /* queries the foreground window for this tick, it is only needed while there are foreground rules. */
+    pub fn update_foreground_window(&mut self) -> Option<&ForegroundWindow> {
+        self.foreground = match self.settings.get_foreground_rules().is_empty() {
+            true => None,
+            false => self.backend.get_foreground_window(self.foreground.as_ref()),
+        };
+        self.foreground.as_ref()
+    }
+
+    pub fn is_pinned(&self) -> bool {
+        self.pinned
+    }
+
+    /* pins every taskbar, taskbars of monitors connected later are pinned as well. */
+    pub fn set_pinned(&mut self, pinned: bool) {
+        self.pinned = pinned;
+        for taskbar in self.iter_mut() {
+            taskbar.set_pinned(pinned);
+        }
+    }
+
+    pub fn request_visibility(&mut self, shown: bool) {
+        for taskbar in self.iter_mut() {
+            taskbar.request_visibility(shown);
+        }
+    }
+
+    pub fn clean_up(&mut self) {
+        for taskbar in self.iter_mut() {
+            taskbar.clean_up();
+        }
+    }
+}
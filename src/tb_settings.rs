@@ -1,415 +1,855 @@
+use crate::animation::Easing;
+use crate::geometry::Logical;
+use crate::settings_location;
+use crate::settings_registry::{self, Kind, Section, Setting};
+use crate::settings_validation::{self, SettingsError};
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
 use std::error::Error;
-use std::fs::File;
-use std::path::PathBuf;
-
-const FILE_NAME: &str = "settings.json";
-
-/* global settings that are loaded once on start. For any changes, restart. */
-#[derive(Serialize, Deserialize, Default, Debug, Clone)]
-pub struct TbSettings {
-    autohide: bool,
-    workspace_offset_top: u32,
-    merge_tray: bool,
-    merge_widgets: bool,
-    sleep_time_in_ms: u64,
-    animation_time_in_ms: u64,
-    animation_steps: u8,
-    infrequent_count: usize,
-    tb_rect_bottom_offset: i32,
-    tb_rect_detection_size_in_pixel: i32,
-    enable_dynamic_borders: bool,
-    dynamic_borders_show_tray: bool,
-    dynamic_borders_show_tray_if_disabled_on_hover: bool,
-    dynamic_borders_show_widgets: bool,
-    dynamic_borders_show_widgets_if_disabled_on_hover: bool,
-    rounded_corners_size: i32,
-    margin_offset_left: i32,
-    margin_offset_right: i32,
-    margin_left: i32,
-    margin_right: i32,
-    margin_bottom: i32,
-    margin_top: i32,
-    windows_11_bugfix: bool,
-    restart_executables: Vec<String>,
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+
+/* name of the profile a file without profiles is. */
+pub const DEFAULT_PROFILE: &str = "default";
+/* keys of the file that are not part of a profile. */
+const FILE_KEYS: [&str; 3] = ["version", "active_profile", "profiles"];
+
+/* the settings layout this build writes. A file without a version is version 1, the layout before versioning. */
+pub const SETTINGS_VERSION: u32 = 2;
+
+type Migration = fn(&mut Map<String, Value>);
+
+/*
+MIGRATIONS[n] upgrades a file of version n + 1 to version n + 2.
+Profiles exist since version 2, later migrations have to upgrade every stored profile as well.
+*/
+const MIGRATIONS: [Migration; 1] = [migrate_v1_to_v2];
+
+/* version 1 had a single animation_time_in_ms, the sleep between two of the animation_steps. */
+fn migrate_v1_to_v2(settings: &mut Map<String, Value>) {
+    if let Some(step_time) = settings
+        .remove("animation_time_in_ms")
+        .and_then(|value| value.as_u64())
+    {
+        let steps = settings
+            .get("animation_steps")
+            .and_then(|value| value.as_u64())
+            .unwrap_or(8);
+        let duration = Value::from(step_time * steps);
+        settings
+            .entry("show_animation_duration_in_ms")
+            .or_insert(duration.clone());
+        settings
+            .entry("hide_animation_duration_in_ms")
+            .or_insert(duration);
+    }
 }
 
-impl TbSettings {
-    pub fn new() -> Self {
-        match Self::try_load() {
-            Some(settings) => {
-                println!("settings loaded from file");
-                settings
-            }
-            None => {
-                let defaults = Self::load_defaults();
-                defaults.save().ok();
-                defaults
-            }
-        }
-    }
+/* upgrades the parsed settings file to SETTINGS_VERSION. Returns true if the layout was changed. */
+fn migrate(value: &mut Value) -> Result<bool, Box<dyn Error>> {
+    let settings = value
+        .as_object_mut()
+        .ok_or("the settings are not a json object")?;
+    let version = match settings.get("version") {
+        None => 1,
+        Some(version) => version.as_u64().ok_or("version is not a number")?.max(1) as u32,
+    };
+    if version > SETTINGS_VERSION {
+        println!(
+            "settings version {} is newer than {}, unknown settings are ignored",
+            version, SETTINGS_VERSION
+        );
+        return Ok(false);
+    }
+    for migration in &MIGRATIONS[(version - 1) as usize..] {
+        migration(settings);
+    }
+    settings.insert("version".to_string(), Value::from(SETTINGS_VERSION));
+    Ok(version < SETTINGS_VERSION)
+}
 
-    fn load_defaults() -> TbSettings {
-        TbSettings {
-            autohide: true,
-            workspace_offset_top: 0,
-            merge_tray: false,
-            merge_widgets: false,
-            sleep_time_in_ms: 10,
-            animation_time_in_ms: 10,
-            animation_steps: 8,
-            infrequent_count: 60,
-            tb_rect_detection_size_in_pixel: 1,
-            tb_rect_bottom_offset: 1,
-            enable_dynamic_borders: true,
-            dynamic_borders_show_tray: false,
-            dynamic_borders_show_tray_if_disabled_on_hover: true,
-            dynamic_borders_show_widgets: false,
-            dynamic_borders_show_widgets_if_disabled_on_hover: false,
-            rounded_corners_size: 4,
-            margin_left: 0,
-            margin_right: 0,
-            margin_bottom: 2,
-            margin_top: 2,
-            margin_offset_left: 0,
-            margin_offset_right: 0,
-            windows_11_bugfix: true,
-            restart_executables: Vec::new(),
-        }
-    }
+/* when autohide hides the taskbar. */
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum AutohidePolicy {
+    /* hides whenever the cursor is not over the taskbar. */
+    #[default]
+    Always,
+    /* only hides while a window overlaps the taskbar, on an empty desktop it stays visible. */
+    Intellihide,
+}
 
-    pub fn get_windows_11_bugfix(&self) -> bool {
-        self.windows_11_bugfix
-    }
+/* how the taskbar disappears when autohide hides it. */
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum HideMode {
+    /* fades the alpha of the taskbar to 0. */
+    #[default]
+    Fade,
+    /* moves the taskbar off the screen by its own height. */
+    Slide,
+}
 
-    pub fn set_windows_11_bugfix(&mut self, value: bool) {
-        if self.windows_11_bugfix == value {
-            return;
-        }
-        self.windows_11_bugfix = value;
-        self.try_save();
-    }
+/* what a matching foreground application rule does with the taskbar. */
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum RuleAction {
+    #[default]
+    AlwaysShow,
+    AlwaysHide,
+    DisableDynamicBorders,
+}
 
-    pub fn get_workspace_offset_top(&self) -> u32 {
-        self.workspace_offset_top
-    }
+/*
+Matches the foreground window. Empty fields are not checked, every other field has to match.
+exe_name is compared case insensitive to the file name of the process, title_regex is a regular expression.
+*/
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+pub struct ForegroundRule {
+    #[serde(default)]
+    pub exe_name: String,
+    #[serde(default)]
+    pub window_class: String,
+    #[serde(default)]
+    pub title_regex: String,
+    pub action: RuleAction,
+}
 
-    pub fn set_workspace_offset_top(&mut self, value: u32) {
-        if self.workspace_offset_top == value {
-            return;
+/*
+Declares every setting once. An entry generates the field of TbSettings, its default and getter,
+and its entry in SETTINGS, the registry the validation and the settings ui are derived from.
+*/
+macro_rules! settings {
+    ($($key:ident: $type:ty = $default:expr, $getter:ident, $setting:expr;)*) => {
+        /*
+        global settings. Loaded on start, the main loop reloads them whenever the settings file changes.
+        Settings missing in the file take their default value, so files of older versions keep every value they have.
+        */
+        #[derive(Serialize, Deserialize, Debug, Clone)]
+        #[serde(default)]
+        pub struct TbSettings {
+            version: u32,
+            $($key: $type,)*
+            /*
+            The settings above are the values of the active profile, every other profile is kept in {profiles}.
+            A stored profile lists its settings like the top level of the file, missing ones take their default.
+            */
+            active_profile: String,
+            profiles: BTreeMap<String, Map<String, Value>>,
         }
-        self.workspace_offset_top = value;
-        self.try_save();
-    }
 
-    pub fn get_dynamic_borders_show_widgets_if_disabled_on_hover(&self) -> bool {
-        self.dynamic_borders_show_widgets_if_disabled_on_hover
-    }
-
-    pub fn set_dynamic_borders_show_widgets_if_disabled_on_hover(&mut self, value: bool) {
-        if self.dynamic_borders_show_widgets_if_disabled_on_hover == value {
-            return;
-        }
-        self.dynamic_borders_show_widgets_if_disabled_on_hover = value;
-        self.try_save();
-    }
+        /* every setting in the order of the settings file. */
+        pub const SETTINGS: &[Setting] = &[$(Setting {
+            key: stringify!($key),
+            ..$setting
+        }),*];
+
+        impl TbSettings {
+            pub fn load_defaults() -> TbSettings {
+                TbSettings {
+                    version: SETTINGS_VERSION,
+                    $($key: $default,)*
+                    active_profile: DEFAULT_PROFILE.to_string(),
+                    profiles: BTreeMap::new(),
+                }
+            }
 
-    pub fn get_dynamic_borders_show_tray_if_disabled_on_hover(&self) -> bool {
-        self.dynamic_borders_show_tray_if_disabled_on_hover
-    }
+            $(pub fn $getter(&self) -> $type {
+                Clone::clone(&self.$key)
+            })*
+
+            /* the value of the setting {key} as it is written to the file, None for unknown keys. */
+            pub fn get_value(&self, key: &str) -> Option<Value> {
+                match key {
+                    $(stringify!($key) => {
+                        Some(serde_json::to_value(&self.$key).expect("settings serialize to json"))
+                    })*
+                    _ => None,
+                }
+            }
 
-    pub fn set_dynamic_borders_show_tray_if_disabled_on_hover(&mut self, value: bool) {
-        if self.dynamic_borders_show_tray_if_disabled_on_hover == value {
-            return;
+            /* sets the setting {key} without saving. Values of the wrong type are refused, the range is checked by validate. */
+            pub fn set_value(&mut self, key: &str, value: Value) -> Result<(), SettingsError> {
+                let type_error = |e: serde_json::Error| SettingsError {
+                    field: key.to_string(),
+                    value: value.to_string(),
+                    allowed: settings_registry::find(key)
+                        .map(|setting| setting.allowed())
+                        .unwrap_or_default(),
+                    reason: e.to_string(),
+                };
+                match key {
+                    $(stringify!($key) => {
+                        self.$key = serde_json::from_value(value.clone()).map_err(type_error)?;
+                    })*
+                    _ => {
+                        return Err(SettingsError {
+                            field: key.to_string(),
+                            value: value.to_string(),
+                            allowed: SETTINGS
+                                .iter()
+                                .map(|setting| setting.key)
+                                .collect::<Vec<_>>()
+                                .join(", "),
+                            reason: "there is no setting with this name".to_string(),
+                        })
+                    }
+                }
+                Ok(())
+            }
         }
-        self.dynamic_borders_show_tray_if_disabled_on_hover = value;
-        self.try_save();
-    }
-
-    pub fn get_restart_executables(&self) -> Vec<String> {
-        self.restart_executables.clone()
-    }
+    };
+}
 
-    pub fn set_restart_executables(&mut self, value: Vec<String>) {
-        if self.restart_executables == value {
-            return;
-        }
-        self.restart_executables = value;
-        self.try_save();
-    }
+settings! {
+    autohide: bool = true, get_autohide, Setting {
+        label: "autohide",
+        description: "Hides the taskbar until the cursor reaches its screen edge.",
+        section: Section::Autohide,
+        ..Setting::NEW
+    };
+    autohide_policy: AutohidePolicy = AutohidePolicy::Always, get_autohide_policy, Setting {
+        label: "Hide When",
+        description: "Hides the taskbar always, or only while a window overlaps it.",
+        section: Section::Autohide,
+        kind: Kind::Choice(&[("always", "always"), ("intellihide", "a window overlaps the taskbar")]),
+        enabled_by: Some("autohide"),
+        ..Setting::NEW
+    };
+    hide_mode: HideMode = HideMode::Fade, get_hide_mode, Setting {
+        label: "Hide Mode",
+        description: "Fades the taskbar out or slides it off its screen edge.",
+        section: Section::Autohide,
+        kind: Kind::Choice(&[("fade", "fade"), ("slide", "slide")]),
+        enabled_by: Some("autohide"),
+        ..Setting::NEW
+    };
+    animation_steps: u8 = 8, get_animation_steps, Setting {
+        label: "Animation Steps on hover and fade away",
+        description: "Number of alpha steps of the fade.",
+        section: Section::Autohide,
+        kind: Kind::Number {
+            unsigned: true,
+            min: Some(1),
+            max: Some(255),
+            reason: "the fade needs at least one step",
+            slider: (1, 20),
+            step: 1.0,
+        },
+        enabled_by: Some("autohide"),
+        ..Setting::NEW
+    };
+    show_animation_duration_in_ms: u64 = 80, get_show_animation_duration_in_ms, Setting {
+        label: "Show Animation Duration in MS",
+        description: "Duration of the animation that shows the taskbar.",
+        section: Section::Autohide,
+        kind: Kind::Number { unsigned: true, min: None, max: None, reason: "", slider: (0, 1000), step: 10.0 },
+        enabled_by: Some("autohide"),
+        ..Setting::NEW
+    };
+    hide_animation_duration_in_ms: u64 = 80, get_hide_animation_duration_in_ms, Setting {
+        label: "Hide Animation Duration in MS",
+        description: "Duration of the animation that hides the taskbar.",
+        section: Section::Autohide,
+        kind: Kind::Number { unsigned: true, min: None, max: None, reason: "", slider: (0, 1000), step: 10.0 },
+        enabled_by: Some("autohide"),
+        ..Setting::NEW
+    };
+    reveal_delay_in_ms: u64 = 0, get_reveal_delay_in_ms, Setting {
+        label: "Reveal Delay in MS",
+        description: "The cursor has to stay at the edge this long before the taskbar shows.",
+        section: Section::Autohide,
+        kind: Kind::Number { unsigned: true, min: None, max: None, reason: "", slider: (0, 2000), step: 10.0 },
+        enabled_by: Some("autohide"),
+        ..Setting::NEW
+    };
+    hide_delay_in_ms: u64 = 0, get_hide_delay_in_ms, Setting {
+        label: "Hide Delay in MS",
+        description: "The taskbar stays visible this long after the cursor left it.",
+        section: Section::Autohide,
+        kind: Kind::Number { unsigned: true, min: None, max: None, reason: "", slider: (0, 2000), step: 10.0 },
+        enabled_by: Some("autohide"),
+        ..Setting::NEW
+    };
+    push_to_reveal: bool = false, get_push_to_reveal, Setting {
+        label: "push against the edge to reveal",
+        description: "Reveals only after the cursor stayed pinned to the screen edge for the push time.",
+        section: Section::Autohide,
+        enabled_by: Some("autohide"),
+        ..Setting::NEW
+    };
+    push_reveal_time_in_ms: u64 = 300, get_push_reveal_time_in_ms, Setting {
+        label: "Push Time in MS",
+        description: "The cursor has to stay pinned to the edge this long to reveal the taskbar. Moving along the edge keeps the time, moving away from it starts over.",
+        section: Section::Autohide,
+        kind: Kind::Number { unsigned: true, min: None, max: None, reason: "", slider: (0, 2000), step: 10.0 },
+        enabled_by: Some("push_to_reveal"),
+        ..Setting::NEW
+    };
+    animation_easing: Easing = Easing::Linear, get_animation_easing, Setting {
+        label: "Animation Easing",
+        description: "Timing curve of the show and hide animations.",
+        section: Section::Autohide,
+        kind: Kind::Easing,
+        enabled_by: Some("autohide"),
+        ..Setting::NEW
+    };
+    tb_rect_detection_size_in_pixel: Logical = Logical(1), get_tb_rect_detection_size_in_pixel, Setting {
+        label: "Detection Size of Hidden_TB",
+        description: "Thickness of the strip at the screen edge that reveals the hidden taskbar.",
+        section: Section::Autohide,
+        kind: Kind::Number {
+            unsigned: false,
+            min: Some(1),
+            max: None,
+            reason: "a hidden taskbar without a detection strip can not be revealed",
+            slider: (1, 100),
+            step: 1.0,
+        },
+        enabled_by: Some("autohide"),
+        ..Setting::NEW
+    };
+    hover_leave_margin_in_pixel: Logical = Logical(10), get_hover_leave_margin_in_pixel, Setting {
+        label: "Hover Leave Margin",
+        description: "The cursor has to move this many pixels away from the taskbar or tray before it counts as left.",
+        section: Section::Autohide,
+        kind: Kind::Number {
+            unsigned: false,
+            min: Some(0),
+            max: None,
+            reason: "the leave zone can not be smaller than the taskbar",
+            slider: (0, 100),
+            step: 1.0,
+        },
+        enabled_by: Some("autohide"),
+        ..Setting::NEW
+    };
+    tb_rect_bottom_offset: Logical = Logical(1), get_tb_rect_bottom_offset, Setting {
+        label: "Bottom Rect Offset",
+        description: "Leave this value at 1 or 0. If the tb isn't detected when your mouse hits the bottom of the screen, increase it by one and test again.",
+        section: Section::Autohide,
+        kind: Kind::Number {
+            unsigned: false,
+            min: Some(0),
+            max: None,
+            reason: "a negative offset moves the detection strip off the screen edge",
+            slider: (0, 5),
+            step: 1.0,
+        },
+        enabled_by: Some("autohide"),
+        ..Setting::NEW
+    };
+    workspace_offset_top: Logical = Logical(0), get_workspace_offset_top, Setting {
+        label: "Window maximizing Offset Top",
+        description: "Offset to change the maximize window behavior. Leave it on 0 to fill the screen on maximizing a window, and bigger then 0 to use a top bar like rainmeter, so it will always show even when maximizing a window.",
+        section: Section::Autohide,
+        kind: Kind::Number {
+            unsigned: false,
+            min: Some(0),
+            max: None,
+            reason: "a negative offset moves the maximized windows above the screen",
+            slider: (0, 200),
+            step: 1.0,
+        },
+        enabled_by: Some("autohide"),
+        ..Setting::NEW
+    };
+    enable_dynamic_borders: bool = true, get_enable_dynamic_borders, Setting {
+        label: "enable dynamic borders",
+        description: "Cuts the taskbar down to islands around the apps and the tray.",
+        section: Section::DynamicBorders,
+        ..Setting::NEW
+    };
+    dynamic_borders_show_tray: bool = false, get_dynamic_borders_show_tray, Setting {
+        label: "dynamic borders show tray",
+        description: "Shows the tray as an island of its own.",
+        section: Section::DynamicBorders,
+        enabled_by: Some("enable_dynamic_borders"),
+        ..Setting::NEW
+    };
+    dynamic_borders_show_tray_if_disabled_on_hover: bool = true,
+        get_dynamic_borders_show_tray_if_disabled_on_hover, Setting {
+        label: "dynamic borders show tray on hover if disabled",
+        description: "Shows the tray island while the cursor is over the taskbar, if the tray is not shown.",
+        section: Section::DynamicBorders,
+        enabled_by: Some("enable_dynamic_borders"),
+        ..Setting::NEW
+    };
+    dynamic_borders_show_widgets: bool = false, get_dynamic_borders_show_widgets, Setting {
+        label: "dynamic borders show widgets",
+        description: "Shows the widgets as an island of their own.",
+        section: Section::DynamicBorders,
+        enabled_by: Some("enable_dynamic_borders"),
+        /* TODO show once implemented */
+        hidden: true,
+        ..Setting::NEW
+    };
+    dynamic_borders_show_widgets_if_disabled_on_hover: bool = false,
+        get_dynamic_borders_show_widgets_if_disabled_on_hover, Setting {
+        label: "dynamic borders show widgets on hover if disabled",
+        description: "Shows the widgets island while the cursor is over the taskbar, if the widgets are not shown.",
+        section: Section::DynamicBorders,
+        enabled_by: Some("enable_dynamic_borders"),
+        /* TODO show once implemented */
+        hidden: true,
+        ..Setting::NEW
+    };
+    rounded_corners_size: Logical = Logical(4), get_rounded_corners_size, Setting {
+        label: "Dynamic borders rounded corner size",
+        description: "Radius of the island corners.",
+        section: Section::DynamicBorders,
+        kind: Kind::Number {
+            unsigned: false,
+            min: Some(0),
+            max: None,
+            reason: "corners can not have a negative size",
+            slider: (0, 30),
+            step: 1.0,
+        },
+        enabled_by: Some("enable_dynamic_borders"),
+        ..Setting::NEW
+    };
+    margin_top: Logical = Logical(2), get_margin_top, Setting {
+        label: "Dynamic borders margin top",
+        description: "Space between the islands and the screen side of the taskbar.",
+        section: Section::DynamicBorders,
+        kind: Kind::Number { unsigned: false, min: None, max: None, reason: "", slider: (-5, 20), step: 1.0 },
+        enabled_by: Some("enable_dynamic_borders"),
+        ..Setting::NEW
+    };
+    margin_bottom: Logical = Logical(2), get_margin_bottom, Setting {
+        label: "Dynamic borders margin bottom",
+        description: "Space between the islands and the screen edge.",
+        section: Section::DynamicBorders,
+        kind: Kind::Number { unsigned: false, min: None, max: None, reason: "", slider: (-5, 20), step: 1.0 },
+        enabled_by: Some("enable_dynamic_borders"),
+        ..Setting::NEW
+    };
+    margin_left: Logical = Logical(0), get_margin_left, Setting {
+        label: "Dynamic borders margin left",
+        description: "Shrinks the islands on their left side.",
+        section: Section::DynamicBorders,
+        kind: Kind::Number { unsigned: false, min: None, max: None, reason: "", slider: (-5, 20), step: 1.0 },
+        enabled_by: Some("enable_dynamic_borders"),
+        ..Setting::NEW
+    };
+    margin_right: Logical = Logical(0), get_margin_right, Setting {
+        label: "Dynamic borders margin right",
+        description: "Shrinks the islands on their right side.",
+        section: Section::DynamicBorders,
+        kind: Kind::Number { unsigned: false, min: None, max: None, reason: "", slider: (-5, 20), step: 1.0 },
+        enabled_by: Some("enable_dynamic_borders"),
+        ..Setting::NEW
+    };
+    margin_offset_left: Logical = Logical(0), get_margin_offset_left, Setting {
+        label: "Dynamic app borders margin left offset",
+        description: "Moves the left edge of the app island, positive values move it to the left.",
+        section: Section::DynamicBorders,
+        kind: Kind::Number { unsigned: false, min: None, max: None, reason: "", slider: (-1000, 1000), step: 1.0 },
+        enabled_by: Some("enable_dynamic_borders"),
+        ..Setting::NEW
+    };
+    margin_offset_right: Logical = Logical(0), get_margin_offset_right, Setting {
+        label: "Dynamic app borders margin right offset",
+        description: "Moves the right edge of the app island, positive values move it to the right.",
+        section: Section::DynamicBorders,
+        kind: Kind::Number { unsigned: false, min: None, max: None, reason: "", slider: (-1000, 1000), step: 1.0 },
+        enabled_by: Some("enable_dynamic_borders"),
+        ..Setting::NEW
+    };
+    windows_11_bugfix: bool = true, get_windows_11_bugfix, Setting {
+        label: "Windows 11 Bugfix",
+        description: "This fixes the taskbar size on newer windows 11 builds. If your Taskbar looks wrong, disable this and try again.",
+        section: Section::DynamicBorders,
+        enabled_by: Some("enable_dynamic_borders"),
+        ..Setting::NEW
+    };
+    sleep_time_in_ms: u64 = 10, get_sleep_time_in_ms, Setting {
+        label: "Hidden_TB Refresh Time in MS",
+        description: "Sleep between two checks of the taskbar. Lower values react faster and use more cpu.",
+        section: Section::Performance,
+        kind: Kind::Number { unsigned: true, min: None, max: None, reason: "", slider: (0, 200), step: 5.0 },
+        ..Setting::NEW
+    };
+    infrequent_count: usize = 60, get_infrequent_count, Setting {
+        label: "Infrequent Work Refresh Skips",
+        description: "Keep it to check at around one second (time is 'Infrequent Work Refresh Skips' * 'Hidden_TB Refresh Time in MS'). It handles the checks for lower priority changes like a new display attached.",
+        section: Section::Performance,
+        kind: Kind::Number {
+            unsigned: true,
+            min: Some(1),
+            max: None,
+            reason: "the infrequent checks run every infrequent_count ticks",
+            slider: (1, 360),
+            step: 1.0,
+        },
+        ..Setting::NEW
+    };
+    restart_executables: Vec<String> = Vec::new(), get_restart_executables, Setting {
+        label: "Restart Executables",
+        description: "Executables restarted when hidden_tb starts, one per line, e.g. apps that draw on the taskbar.",
+        section: Section::Restarts,
+        kind: Kind::List,
+        restart_needed: true,
+        ..Setting::NEW
+    };
+    foreground_rules: Vec<ForegroundRule> = Vec::new(), get_foreground_rules, Setting {
+        label: "Foreground Application Rules",
+        description: "Applied while a matching window is in the foreground. Empty fields are not checked, the first matching rule wins.",
+        section: Section::Advanced,
+        kind: Kind::ForegroundRules,
+        ..Setting::NEW
+    };
+    merge_tray: bool = false, get_merge_tray, Setting {
+        label: "merge tray with applist",
+        description: "Moves the tray next to the apps.",
+        section: Section::Advanced,
+        /* too buggy at the moment */
+        hidden: true,
+        ..Setting::NEW
+    };
+    merge_widgets: bool = false, get_merge_widgets, Setting {
+        label: "merge widget with applist",
+        description: "Moves the widgets next to the apps.",
+        section: Section::Advanced,
+        /* TODO show once implemented */
+        hidden: true,
+        ..Setting::NEW
+    };
+}
 
-    pub fn get_margin_top(&self) -> i32 {
-        self.margin_top
+impl Default for TbSettings {
+    fn default() -> Self {
+        Self::load_defaults()
     }
+}
 
-    pub fn set_margin_top(&mut self, value: i32) {
-        if self.margin_top == value {
-            return;
-        }
-        self.margin_top = value;
-        self.try_save();
+impl TbSettings {
+    pub fn new() -> Self {
+        Self::new_with_warning().0
     }
 
-    pub fn get_margin_bottom(&self) -> i32 {
-        self.margin_bottom
+    /* loads the settings file of this process. The warning tells the user why the defaults are used. */
+    pub fn new_with_warning() -> (Self, Option<String>) {
+        Self::load_or_backup(&Self::get_path())
     }
 
-    pub fn set_margin_bottom(&mut self, value: i32) {
-        if self.margin_bottom == value {
-            return;
+    /*
+    Loads the settings file at {path}, migrates an older layout and writes it back in the current one,
+    invalid values are replaced by their defaults.
+    A file that can not be parsed is kept as a backup next to it and replaced by the defaults,
+    the returned warning names the error and the backup.
+    */
+    pub fn load_or_backup(path: &Path) -> (Self, Option<String>) {
+        if !path.exists() {
+            let defaults = Self::load_defaults();
+            defaults.save_to(path).ok();
+            return (defaults, None);
         }
-        self.margin_bottom = value;
-        self.try_save();
-    }
-
-    pub fn get_margin_offset_left(&self) -> i32 {
-        self.margin_offset_left
-    }
-
-    pub fn set_margin_offset_left(&mut self, value: i32) {
-        if self.margin_offset_left == value {
-            return;
+        let error = match Self::read(path) {
+            Ok((settings, migrated)) => {
+                println!("settings loaded from file");
+                /* invalid values are reset first, a file with them can not be saved and would be migrated on every start */
+                let (settings, errors) = settings.reset_invalid();
+                let mut warnings = Vec::new();
+                if !errors.is_empty() {
+                    warnings.push(Self::invalid_warning(path, &errors));
+                }
+                if migrated {
+                    println!("settings migrated to version {}", SETTINGS_VERSION);
+                    if let Err(e) = settings.save_to(path) {
+                        warnings.push(format!(
+                            "the migrated settings could not be saved to {}: {}",
+                            path.display(),
+                            e
+                        ));
+                    }
+                }
+                if warnings.is_empty() {
+                    return (settings, None);
+                }
+                let warning = warnings.join("\n");
+                eprintln!("{}", warning);
+                return (settings, Some(warning));
+            }
+            Err(e) => e,
+        };
+
+        let defaults = Self::load_defaults();
+        let backup = Self::get_backup_path(path);
+        let warning = match fs::rename(path, &backup) {
+            Ok(()) => {
+                defaults.save_to(path).ok();
+                format!(
+                    "{} could not be read: {}. It was moved to {} and the default settings are used.",
+                    path.display(),
+                    error,
+                    backup.display()
+                )
+            }
+            Err(backup_error) => format!(
+                "{} could not be read: {}. The default settings are used, the file is left untouched because the backup failed: {}",
+                path.display(),
+                error,
+                backup_error
+            ),
+        };
+        eprintln!("{}", warning);
+        (defaults, Some(warning))
+    }
+
+    /*
+    Loads the settings file at {path} like load_or_backup, but never writes, moves or creates a file.
+    The settings ui uses it, the running hidden_tb is the one that migrates and backs up the file.
+    */
+    pub fn load_read_only(path: &Path) -> (Self, Option<String>) {
+        if !path.exists() {
+            return (Self::load_defaults(), None);
         }
-        self.margin_offset_left = value;
-        self.try_save();
-    }
-
-    pub fn get_margin_offset_right(&self) -> i32 {
-        self.margin_offset_right
-    }
-
-    pub fn set_margin_offset_right(&mut self, value: i32) {
-        if self.margin_offset_right == value {
-            return;
+        match Self::read(path) {
+            Ok((settings, _)) => {
+                let (settings, errors) = settings.reset_invalid();
+                let warning = (!errors.is_empty()).then(|| Self::invalid_warning(path, &errors));
+                (settings, warning)
+            }
+            Err(e) => (
+                Self::load_defaults(),
+                Some(format!(
+                    "{} could not be read: {}. The default settings are used.",
+                    path.display(),
+                    e
+                )),
+            ),
         }
-        self.margin_offset_right = value;
-        self.try_save();
-    }
-
-    pub fn get_margin_left(&self) -> i32 {
-        self.margin_left
     }
 
-    pub fn set_margin_left(&mut self, value: i32) {
-        if self.margin_left == value {
-            return;
-        }
-        self.margin_left = value;
-        self.try_save();
+    fn invalid_warning(path: &Path, errors: &[SettingsError]) -> String {
+        format!(
+            "{} has invalid settings, their defaults are used: {}",
+            path.display(),
+            errors
+                .iter()
+                .map(|error| error.to_string())
+                .collect::<Vec<_>>()
+                .join("; ")
+        )
     }
 
-    pub fn get_margin_right(&self) -> i32 {
-        self.margin_right
+    /* settings.json.bak next to the settings file. */
+    pub fn get_backup_path(path: &Path) -> PathBuf {
+        let mut backup = path.as_os_str().to_owned();
+        backup.push(".bak");
+        PathBuf::from(backup)
     }
 
-    pub fn set_margin_right(&mut self, value: i32) {
-        if self.margin_right == value {
-            return;
+    fn try_save(&self) {
+        if let Err(e) = self.save() {
+            eprintln!("settings not saved: {}", e);
         }
-        self.margin_right = value;
-        self.try_save();
     }
 
-    pub fn get_enable_dynamic_borders(&self) -> bool {
-        self.enable_dynamic_borders
+    /* writes the settings to the settings file of this process. */
+    pub fn save(&self) -> Result<(), Box<dyn Error>> {
+        self.save_to(&Self::get_path())
     }
 
-    pub fn set_enable_dynamic_borders(&mut self, value: bool) {
-        if self.enable_dynamic_borders == value {
-            return;
+    /* invalid settings are never written. */
+    pub fn save_to(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        if let Err(errors) = self.validate() {
+            return Err(errors
+                .iter()
+                .map(|error| error.to_string())
+                .collect::<Vec<_>>()
+                .join("; ")
+                .into());
         }
-        self.enable_dynamic_borders = value;
-        self.try_save();
-    }
-
-    pub fn get_dynamic_borders_show_tray(&self) -> bool {
-        self.dynamic_borders_show_tray
-    }
-
-    pub fn set_dynamic_borders_show_tray(&mut self, value: bool) {
-        if self.dynamic_borders_show_tray == value {
-            return;
+        /* the per-user config directory does not exist before the first save */
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)?;
         }
-        self.dynamic_borders_show_tray = value;
-        self.try_save();
+        let file = File::create(path)?;
+        serde_json::to_writer_pretty(file, &self)?;
+        println!("settings saved");
+        Ok(())
     }
 
-    pub fn get_dynamic_borders_show_widgets(&self) -> bool {
-        self.dynamic_borders_show_widgets
+    /* parses the settings file at {path} without touching it, used to reload the settings while running. */
+    pub fn load_from(path: &Path) -> Result<TbSettings, Box<dyn Error>> {
+        Ok(Self::read(path)?.0)
     }
 
-    pub fn set_dynamic_borders_show_widgets(&mut self, value: bool) {
-        if self.dynamic_borders_show_widgets == value {
-            return;
-        }
-        self.dynamic_borders_show_widgets = value;
-        self.try_save();
+    /* parses and migrates settings json of any version. */
+    pub fn from_json(json: &str) -> Result<TbSettings, Box<dyn Error>> {
+        Ok(Self::parse(json)?.0)
     }
 
-    pub fn get_rounded_corners_size(&self) -> i32 {
-        self.rounded_corners_size
+    fn read(path: &Path) -> Result<(TbSettings, bool), Box<dyn Error>> {
+        Self::parse(&fs::read_to_string(path)?)
     }
 
-    pub fn set_rounded_corners_size(&mut self, value: i32) {
-        if self.rounded_corners_size == value {
-            return;
-        }
-        self.rounded_corners_size = value;
-        self.try_save();
+    fn parse(json: &str) -> Result<(TbSettings, bool), Box<dyn Error>> {
+        let mut value: Value = serde_json::from_str(json)?;
+        let migrated = migrate(&mut value)?;
+        let settings: TbSettings = serde_json::from_value(value)?;
+        Ok((settings, migrated))
     }
 
-    pub fn get_animation_time_in_ms(&self) -> u64 {
-        self.animation_time_in_ms
+    pub fn get_version(&self) -> u32 {
+        self.version
     }
 
-    pub fn set_animation_time_in_ms(&mut self, value: u64) {
-        if self.animation_time_in_ms == value {
-            return;
+    /* checks every value hidden_tb can not run with, all errors are returned at once. */
+    pub fn validate(&self) -> Result<(), Vec<SettingsError>> {
+        let errors: Vec<SettingsError> = SETTINGS
+            .iter()
+            .flat_map(|setting| {
+                let value = self
+                    .get_value(setting.key)
+                    .expect("every setting has a value");
+                settings_validation::check(setting, &value)
+            })
+            .collect();
+        match errors.is_empty() {
+            true => Ok(()),
+            false => Err(errors),
         }
-        self.animation_time_in_ms = value;
-        self.try_save();
     }
 
-    pub fn get_tb_rect_detection_size_in_pixel(&self) -> i32 {
-        self.tb_rect_detection_size_in_pixel
-    }
-
-    pub fn get_merge_tray(&self) -> bool {
-        self.merge_tray
-    }
-
-    pub fn set_merge_tray(&mut self, value: bool) {
-        if self.merge_tray == value {
-            return;
+    /*
+    Replaces invalid values by their defaults and returns the errors. Foreground rules with an invalid
+    title regex are dropped, the returned errors name them.
+    */
+    fn reset_invalid(mut self) -> (Self, Vec<SettingsError>) {
+        let Err(errors) = self.validate() else {
+            return (self, Vec::new());
+        };
+        let defaults = Self::load_defaults();
+        for error in errors.iter() {
+            if let Some(default) = defaults.get_value(&error.field) {
+                self.set_value(&error.field, default)
+                    .expect("defaults have the type of their setting");
+            }
         }
-        self.merge_tray = value;
-        self.try_save();
+        /* the index only ends up in the error, which is already part of {errors} */
+        self.foreground_rules
+            .retain(|rule| settings_validation::check_title_regex(0, &rule.title_regex).is_ok());
+        (self, errors)
     }
 
-    pub fn get_merge_widgets(&self) -> bool {
-        self.merge_widgets
+    /* the settings with another value in {other}, e.g. the unsaved changes of the settings ui. */
+    pub fn changed_settings(&self, other: &TbSettings) -> Vec<&'static Setting> {
+        SETTINGS
+            .iter()
+            .filter(|setting| self.get_value(setting.key) != other.get_value(setting.key))
+            .collect()
     }
 
-    pub fn set_merge_widgets(&mut self, value: bool) {
-        if self.merge_widgets == value {
-            return;
-        }
-        self.merge_widgets = value;
-        self.try_save();
+    pub fn get_active_profile(&self) -> &str {
+        &self.active_profile
     }
 
-    pub fn set_tb_rect_detection_size_in_pixel(&mut self, value: i32) {
-        if self.tb_rect_detection_size_in_pixel == value {
-            return;
+    /* the active and every stored profile, sorted by name. */
+    pub fn get_profile_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.profiles.keys().cloned().collect();
+        if !self.profiles.contains_key(&self.active_profile) {
+            names.push(self.active_profile.clone());
         }
-        self.tb_rect_detection_size_in_pixel = value;
-        self.try_save();
-    }
-
-    pub fn get_tb_rect_bottom_offset(&self) -> i32 {
-        self.tb_rect_bottom_offset
-    }
-
-    pub fn set_tb_rect_bottom_offset(&mut self, value: i32) {
-        if self.tb_rect_bottom_offset == value {
-            return;
+        names.sort();
+        names
+    }
+
+    /* the values of the active profile, without the keys of the file. */
+    fn profile_values(&self) -> Map<String, Value> {
+        let Value::Object(mut values) =
+            serde_json::to_value(self).expect("settings serialize to json")
+        else {
+            unreachable!("settings serialize to a json object");
+        };
+        for key in FILE_KEYS {
+            values.remove(key);
         }
-        self.tb_rect_bottom_offset = value;
-        self.try_save();
-    }
-
-    pub fn get_animation_steps(&self) -> u8 {
-        self.animation_steps
+        values
     }
 
-    pub fn set_animation_steps(&mut self, value: u8) {
-        if self.animation_steps == value {
-            return;
+    /*
+    The settings with {name} as the active profile. The values of the current profile are stored under its name,
+    the values of {name} replace them. Invalid values in the stored profile are returned as errors.
+    */
+    pub fn with_profile(&self, name: &str) -> Result<TbSettings, Vec<SettingsError>> {
+        if name == self.active_profile {
+            return Ok(self.clone());
         }
-        self.animation_steps = value;
-        self.try_save();
-    }
-
-    pub fn get_infrequent_count(&self) -> usize {
-        self.infrequent_count
+        let Some(values) = self.profiles.get(name) else {
+            return Err(vec![SettingsError {
+                field: "active_profile".to_string(),
+                value: name.to_string(),
+                allowed: self.get_profile_names().join(", "),
+                reason: "there is no profile with this name".to_string(),
+            }]);
+        };
+        let mut profiles = self.profiles.clone();
+        profiles.remove(name);
+        profiles.insert(self.active_profile.clone(), self.profile_values());
+
+        let mut value = Value::Object(values.clone());
+        value["version"] = Value::from(SETTINGS_VERSION);
+        value["active_profile"] = Value::from(name);
+        let mut settings: TbSettings = serde_json::from_value(value).map_err(|e| {
+            vec![SettingsError {
+                field: format!("profiles.{}", name),
+                value: "stored profile".to_string(),
+                allowed: "the settings of this version".to_string(),
+                reason: e.to_string(),
+            }]
+        })?;
+        settings.validate()?;
+        settings.profiles = profiles;
+        Ok(settings)
     }
 
-    pub fn set_infrequent_count(&mut self, value: usize) {
-        if self.infrequent_count == value {
-            return;
+    /* the settings with a new profile {name} as the active one, it starts with the values of the current profile. */
+    pub fn with_new_profile(&self, name: &str) -> Result<TbSettings, Vec<SettingsError>> {
+        let name = name.trim();
+        if name.is_empty()
+            || self
+                .get_profile_names()
+                .iter()
+                .any(|existing| existing == name)
+        {
+            return Err(vec![SettingsError {
+                field: "active_profile".to_string(),
+                value: name.to_string(),
+                allowed: "a name that is not used by another profile".to_string(),
+                reason: "profiles need a unique name".to_string(),
+            }]);
         }
-        self.infrequent_count = value;
-        self.try_save();
-    }
-
-    pub fn get_autohide(&self) -> bool {
-        self.autohide
+        let mut settings = self.clone();
+        settings
+            .profiles
+            .insert(self.active_profile.clone(), self.profile_values());
+        settings.active_profile = name.to_string();
+        Ok(settings)
     }
 
-    pub fn set_autohide(&mut self, value: bool) {
-        if self.autohide == value {
-            return;
-        }
-        self.autohide = value;
+    /* adds the profile {name} as the active one and saves the file. */
+    pub fn add_profile(&mut self, name: &str) -> Result<(), Vec<SettingsError>> {
+        let settings = self.with_new_profile(name)?;
+        *self = settings;
         self.try_save();
+        Ok(())
     }
 
-    pub fn get_sleep_time_in_ms(&self) -> u64 {
-        self.sleep_time_in_ms
-    }
-
-    pub fn set_sleep_time_in_ms(&mut self, value: u64) {
-        if self.sleep_time_in_ms == value {
-            return;
-        }
-        self.sleep_time_in_ms = value;
+    /* makes {name} the active profile and saves the file, a running hidden_tb applies it on reload. */
+    pub fn switch_profile(&mut self, name: &str) -> Result<(), Vec<SettingsError>> {
+        let settings = self.with_profile(name)?;
+        *self = settings;
         self.try_save();
-    }
-
-    fn try_save(&self) {
-        self.save().ok();
-    }
-
-    fn try_load() -> Option<TbSettings> {
-        if TbSettings::check_if_file_exists() {
-            match Self::load() {
-                Ok(val) => {
-                    return Some(val);
-                }
-                Err(_) => Self::delete_file(),
-            }
-        }
-        None
-    }
-
-    fn delete_file() {
-        std::fs::remove_file(FILE_NAME).ok();
-    }
-
-    fn save(&self) -> Result<(), Box<dyn Error>> {
-        let path = Self::get_path();
-        let file = File::create(path)?;
-        serde_json::to_writer_pretty(file, &self)?;
-        println!("settings saved");
         Ok(())
     }
 
-    fn load() -> Result<TbSettings, Box<dyn Error>> {
-        let path = Self::get_path();
-        let file = File::open(path)?;
-        let settings: TbSettings = serde_json::from_reader(file)?;
-        Ok(settings)
-    }
-
-    fn check_if_file_exists() -> bool {
-        let path = Self::get_path();
-        path.exists()
-    }
-
-    fn get_path() -> PathBuf {
-        let mut rsrc_dir = std::env::current_exe().expect("Can't find path to executable");
-        rsrc_dir.pop();
-        rsrc_dir.push(FILE_NAME);
-        rsrc_dir
+    /* the settings file of this process, see SettingsLocation::resolve for the lookup order. */
+    pub fn get_path() -> PathBuf {
+        settings_location::get().path.clone()
     }
 }
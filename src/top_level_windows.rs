@@ -0,0 +1,80 @@
+use std::ffi::c_void;
+use std::mem;
+
+use windows::Win32::Foundation::{BOOL, HWND, LPARAM, RECT};
+use windows::Win32::Graphics::Dwm::{DwmGetWindowAttribute, DWMWA_CLOAKED};
+use windows::Win32::UI::WindowsAndMessaging::{
+    EnumWindows, GetClassNameW, GetWindowLongA, GetWindowRect, IsIconic, IsWindowVisible,
+    GWL_EXSTYLE, WINDOW_EX_STYLE, WS_EX_NOACTIVATE, WS_EX_TOOLWINDOW, WS_EX_TRANSPARENT,
+};
+
+use crate::geometry::Rect;
+use crate::shell_backend::{FormEntry, TASKBAR_CLASSES};
+use crate::windows_calls::from_hwnd;
+
+/* the desktop background covers every monitor and is never a window that overlaps the taskbar. */
+const IGNORED_CLASSES: [&str; 2] = ["Progman", "WorkerW"];
+/* tool windows, overlays and click through windows float above the apps, e.g. notifications and recording borders. */
+const IGNORED_EX_STYLES: WINDOW_EX_STYLE =
+    WINDOW_EX_STYLE(WS_EX_TOOLWINDOW.0 | WS_EX_NOACTIVATE.0 | WS_EX_TRANSPARENT.0);
+
+pub fn get_visible_windows() -> Vec<FormEntry> {
+    let windows = Vec::<FormEntry>::new();
+    let windows_ptr = Box::into_raw(Box::new(windows));
+
+    unsafe {
+        let data = LPARAM(windows_ptr as isize);
+        /* an error only stops the enumeration early, the windows found until then are still valid. */
+        EnumWindows(Some(window_callback), data).ok();
+
+        let boxed_windows = Box::from_raw(windows_ptr);
+        *boxed_windows
+    }
+}
+
+unsafe extern "system" fn window_callback(hwnd: HWND, userdata: LPARAM) -> BOOL {
+    let windows: &mut Vec<FormEntry> = &mut *(userdata.0 as *mut Vec<FormEntry>);
+
+    if !IsWindowVisible(hwnd).as_bool() || IsIconic(hwnd).as_bool() || is_cloaked(hwnd) {
+        return true.into();
+    }
+    let class_name = get_class_name(hwnd);
+    if IGNORED_CLASSES.contains(&class_name.as_str())
+        || TASKBAR_CLASSES.contains(&class_name.as_str())
+    {
+        return true.into();
+    }
+    if GetWindowLongA(hwnd, GWL_EXSTYLE) as u32 & IGNORED_EX_STYLES.0 != 0 {
+        return true.into();
+    }
+    let mut rect = RECT::default();
+    if GetWindowRect(hwnd, &mut rect).is_ok() {
+        let rect = Rect::new(rect.left, rect.top, rect.right, rect.bottom);
+        if rect.width() > 0 && rect.height() > 0 {
+            windows.push(FormEntry {
+                hwnd: from_hwnd(hwnd),
+                rect,
+            });
+        }
+    }
+    true.into()
+}
+
+/* cloaked windows are visible for the window manager but not drawn, e.g. uwp apps on other virtual desktops. */
+unsafe fn is_cloaked(hwnd: HWND) -> bool {
+    let mut cloaked: u32 = 0;
+    DwmGetWindowAttribute(
+        hwnd,
+        DWMWA_CLOAKED,
+        &mut cloaked as *mut u32 as *mut c_void,
+        mem::size_of::<u32>() as u32,
+    )
+    .is_ok()
+        && cloaked != 0
+}
+
+unsafe fn get_class_name(hwnd: HWND) -> String {
+    let mut buffer = [0u16; 256];
+    let length = GetClassNameW(hwnd, &mut buffer);
+    String::from_utf16_lossy(&buffer[..length.max(0) as usize])
+}
@@ -1,7 +1,14 @@
 use native_windows_gui as nwg;
 use nwg::NativeUi;
+use std::cell::RefCell;
+use std::os::windows::io::AsRawHandle;
+use std::thread::JoinHandle;
+use windows::Win32::Foundation::{HANDLE, LPARAM, WPARAM};
+use windows::Win32::System::Threading::GetThreadId;
+use windows::Win32::UI::WindowsAndMessaging::{PostThreadMessageW, WM_QUIT};
 
 use crate::signaling;
+use crate::tb_settings::TbSettings;
 
 #[derive(Default)]
 pub struct SystemTray {
@@ -11,17 +18,97 @@ pub struct SystemTray {
     tray_menu: nwg::Menu,
     tray_exit_item: nwg::MenuItem,
     tray_open_settings_item: nwg::MenuItem,
+    tray_profiles_menu: nwg::Menu,
+    /* one item per profile, rebuilt every time the menu opens to show profiles added in the settings */
+    tray_profile_items: RefCell<Vec<(String, nwg::MenuItem)>>,
 }
 
 impl SystemTray {
     fn show_menu(&self) {
+        self.rebuild_profile_items();
         let (x, y) = nwg::GlobalCursor::position();
         self.tray_menu.popup(x, y);
     }
 
+    fn rebuild_profile_items(&self) {
+        let settings = match TbSettings::load_from(&TbSettings::get_path()) {
+            Ok(settings) => settings,
+            Err(e) => {
+                eprintln!("Could not read the profiles: {}", e);
+                return;
+            }
+        };
+        let mut items = self.tray_profile_items.borrow_mut();
+        /* dropping an item removes it from the menu */
+        items.clear();
+        for name in settings.get_profile_names() {
+            let mut item = nwg::MenuItem::default();
+            let built = nwg::MenuItem::builder()
+                .text(&name)
+                .check(name == settings.get_active_profile())
+                .parent(&self.tray_profiles_menu)
+                .build(&mut item);
+            match built {
+                Ok(()) => items.push((name, item)),
+                Err(e) => eprintln!("Could not add the profile {} to the menu: {}", name, e),
+            }
+        }
+    }
+
+    /* the profile of the selected menu item, None for the other items. */
+    fn selected_profile(&self, handle: nwg::ControlHandle) -> Option<String> {
+        self.tray_profile_items
+            .borrow()
+            .iter()
+            .find(|(_, item)| item.handle == handle)
+            .map(|(name, _)| name.clone())
+    }
+
+    /* saves {name} as the active profile, the main loop reloads the file on its next tick. */
+    fn switch_profile(&self, name: &str) {
+        let switched = TbSettings::load_from(&TbSettings::get_path())
+            .map_err(|e| e.to_string())
+            .and_then(|mut settings| {
+                settings.switch_profile(name).map_err(|errors| {
+                    errors
+                        .iter()
+                        .map(|error| error.to_string())
+                        .collect::<Vec<_>>()
+                        .join("; ")
+                })
+            });
+        match switched {
+            Ok(()) => signaling::get_signaling_struct().set_reload_called(true),
+            Err(e) => {
+                self.show_warning(&format!("Could not switch to the profile {}: {}", name, e))
+            }
+        }
+    }
+
+    /* starts the settings ui as a second process, the taskbar keeps running and picks up the saved settings. */
     fn open_settings_ui(&self) {
-        signaling::get_signaling_struct().set_settings_called(true);
-        self.exit();
+        /* the settings ui edits the file this process watches */
+        let started = std::env::current_exe().and_then(|exe| {
+            std::process::Command::new(exe)
+                .arg("settings")
+                .arg("--config")
+                .arg(TbSettings::get_path())
+                .spawn()
+        });
+        if let Err(e) = started {
+            eprintln!("Could not open the settings: {}", e);
+        }
+    }
+
+    /* a balloon notification, the text is cut to the 255 characters the shell shows. */
+    fn show_warning(&self, text: &str) {
+        let text: String = text.chars().take(255).collect();
+        self.tray.show(
+            &text,
+            Some("hidden_tb"),
+            Some(nwg::TrayNotificationFlags::WARNING_ICON),
+            None,
+        );
     }
 
     fn exit(&self) {
@@ -68,8 +155,13 @@ mod system_tray_ui {
                 .parent(&data.window)
                 .build(&mut data.tray_menu)?;
 
+            nwg::Menu::builder()
+                .text("Profile")
+                .parent(&data.tray_menu)
+                .build(&mut data.tray_profiles_menu)?;
+
             nwg::MenuItem::builder()
-                .text("Open Settings and Close TB")
+                .text("Open Settings")
                 .parent(&data.tray_menu)
                 .build(&mut data.tray_open_settings_item)?;
 
@@ -101,6 +193,9 @@ mod system_tray_ui {
                             if handle == evt_ui.tray_open_settings_item {
                                 SystemTray::open_settings_ui(&evt_ui);
                             }
+                            if let Some(name) = evt_ui.selected_profile(handle) {
+                                SystemTray::switch_profile(&evt_ui, &name);
+                            }
                         }
                         _ => {}
                     }
@@ -137,9 +232,22 @@ mod system_tray_ui {
     }
 }
 
+/* {warning} is shown as a notification once the icon is in the tray, e.g. the settings file could not be read. */
 #[inline]
-pub fn start_tray_icon() {
+pub fn start_tray_icon(warning: Option<String>) {
     nwg::init().expect("Failed to init Native Windows GUI");
-    let _ui = SystemTray::build_ui(Default::default()).expect("Failed to build UI");
+    let ui = SystemTray::build_ui(Default::default()).expect("Failed to build UI");
+    if let Some(warning) = warning {
+        ui.show_warning(&warning);
+    }
     nwg::dispatch_thread_events();
 }
+
+/* ends the message loop of the tray thread, e.g. after the quit command. A tray that was exited from its menu is ignored. */
+pub fn stop_tray_icon(tray_thread: &JoinHandle<()>) {
+    /* Safety: the handle stays valid until the thread is joined. */
+    unsafe {
+        let thread_id = GetThreadId(HANDLE(tray_thread.as_raw_handle()));
+        PostThreadMessageW(thread_id, WM_QUIT, WPARAM(0), LPARAM(0)).ok();
+    }
+}
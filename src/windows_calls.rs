@@ -1,177 +1,202 @@
 use std::ffi::c_void;
-use windows::core::PWSTR;
-use windows::Win32::Foundation::{BOOL, LRESULT, MAX_PATH, POINT};
-use windows::Win32::Graphics::Gdi::{CombineRgn, CreateRoundRectRgn, SetWindowRgn};
-use windows::Win32::System::Threading::{
-    GetCurrentProcessId, OpenProcess, QueryFullProcessImageNameW, PROCESS_NAME_FORMAT,
-    PROCESS_QUERY_INFORMATION, PROCESS_QUERY_LIMITED_INFORMATION, PROCESS_VM_READ,
-};
-use windows::Win32::UI::Shell::{
-    IAppVisibility, SHAppBarMessage, ABM_GETSTATE, ABM_SETSTATE, ABS_AUTOHIDE, APPBARDATA,
+use windows::Win32::Foundation::POINT;
+use windows::Win32::Graphics::Gdi::{
+    CombineRgn, CreateRoundRectRgn, DeleteObject, SetWindowRgn, HRGN, RGN_COMBINE_MODE,
 };
+use windows::Win32::UI::Shell::{IAppVisibility, APPBARDATA};
 use windows::Win32::{Foundation, UI::WindowsAndMessaging::*};
 use Foundation::HWND;
 use Foundation::RECT;
 
-use crate::taskbar::Taskbar;
-use crate::tb_settings::TbSettings;
-use crate::{monitors, restart_process};
-
-pub const _AUTOHIDE: isize = 0x01;
-pub const _ALWAYS_ON_TOP: isize = 0x02;
+use crate::geometry::{Dpi, Point, Rect};
+use crate::region::{RegionSpec, RoundedRect};
+use crate::shell_backend::{ForegroundWindow, FormEntry, Monitor, ShellBackend, WindowHandle};
+use crate::{monitors, restart_process, top_level_windows};
 
-#[derive(Default, Debug, Clone)]
-pub struct FormEntry {
-    pub hwnd: HWND,
-    pub rect: RECT,
-}
+/* win32 implementation of the shell backend. */
+#[derive(Default, Debug, Clone, Copy)]
+pub struct WindowsShell;
 
-#[derive(Default, Debug, Clone)]
-pub struct WantedHwnds {
-    pub taskbar: bool,
-    pub tray: bool,
-    pub rebar: bool,
-    pub applist: bool,
-    pub apps: bool,
-}
+impl ShellBackend for WindowsShell {
+    fn find_window(&self, parent: Option<WindowHandle>, class_name: &str) -> Option<FormEntry> {
+        let parent = match parent {
+            Some(parent) => to_hwnd(parent),
+            None => HWND_TOP,
+        };
+        /* Safety: Each class name used by hidden_tb does not contain a \0 character. */
+        unsafe { find_form_entry(parent, class_name) }
+    }
 
-impl WantedHwnds {
-    pub fn new(settings: &TbSettings) -> Self {
-        //Currently only the taskbar is needed, so set all other values to false
-        //Later on detect through settings if more is needed
-        let mut wanted_hwnds = WantedHwnds {
-            taskbar: true,
-            tray: false,
-            rebar: false,
-            applist: false,
-            apps: false,
+    fn find_windows(&self, parent: Option<WindowHandle>, class_name: &str) -> Vec<FormEntry> {
+        let parent = match parent {
+            Some(parent) => to_hwnd(parent),
+            None => HWND_TOP,
         };
-        if settings.get_merge_tray()
-            || settings.get_merge_widgets()
-            || settings.get_enable_dynamic_borders()
-        {
-            wanted_hwnds.tray = true;
-            wanted_hwnds.rebar = true;
-            wanted_hwnds.applist = true;
-            //apps is currently not used, so we can keep it on false and not depend on it.
-            //wanted_hwnds.apps = true;
-        }
-        wanted_hwnds
+        /* Safety: Each class name used by hidden_tb does not contain a \0 character. */
+        unsafe { find_form_entries(parent, class_name) }
     }
-}
 
-#[derive(Default, Debug, Clone)]
-pub struct TaskbarData {
-    /*
-    taskbar is on top.
+    fn get_dpi(&self, hwnd: WindowHandle) -> Dpi {
+        /* Safety: an invalid handle returns 0 and does not fail. */
+        unsafe { get_dpi_from_hwnd(&to_hwnd(hwnd)) }
+    }
 
-    tray depends on taskbar.
-    rebar depends on taskbar.
+    fn get_cursor_pos(&self) -> Option<Point> {
+        get_cursor_pos().map(|point| Point::new(point.x, point.y))
+    }
 
-    applist depends on rebar.
+    fn get_start_menu_open(&self) -> bool {
+        get_start_menu_open()
+    }
 
-    apps depend on applist.
-    */
-    pub taskbar: Option<FormEntry>,
+    fn get_foreground_window(
+        &self,
+        previous: Option<&ForegroundWindow>,
+    ) -> Option<ForegroundWindow> {
+        get_foreground_window(previous)
+    }
 
-    pub resolution: f64,
+    fn check_and_set_transparency_style(&self, hwnd: WindowHandle) -> bool {
+        check_and_set_transparency_style(&to_hwnd(hwnd))
+    }
 
-    pub tray: Option<FormEntry>,
-    pub rebar: Option<FormEntry>,
+    fn set_window_alpha(&self, hwnd: WindowHandle, alpha: u8) -> bool {
+        set_window_alpha(&to_hwnd(hwnd), alpha)
+    }
 
-    pub applist: Option<FormEntry>,
+    fn set_window_topmost(&self, hwnd: WindowHandle) {
+        set_window_topmost(&to_hwnd(hwnd));
+    }
 
-    pub apps: Option<FormEntry>,
+    fn set_app_bar_state(&self, hwnd: WindowHandle, option: isize) {
+        set_app_bar_state(&to_hwnd(hwnd), option);
+    }
 
-    pub wanted_hwnds: WantedHwnds,
+    fn move_window_on_tb(&self, hwnd: WindowHandle, x: i32, y: i32) -> bool {
+        move_window_on_tb(&to_hwnd(hwnd), x, y)
+    }
 
-    pub display_rect: Option<RECT>,
-}
+    fn set_window_position(&self, hwnd: WindowHandle, x: i32, y: i32) -> bool {
+        set_window_position(&to_hwnd(hwnd), x, y)
+    }
 
-impl FormEntry {
-    /* Safety: Don't call new with a str {name} that contains a \0 terminating character. */
-    unsafe fn new(dependent_hwnd: HWND, name: &str) -> Option<FormEntry> {
-        let Ok(hwnd) = windows::Win32::UI::WindowsAndMessaging::FindWindowExA(
-            dependent_hwnd,
-            HWND_TOP,
-            string_to_pcstr(name),
-            windows::core::PCSTR::null(),
-        ) else {
-            return None;
-        };
-        let mut rect = windows::Win32::Foundation::RECT::default();
-        let erg = windows::Win32::UI::WindowsAndMessaging::GetWindowRect(hwnd, &mut rect);
+    fn set_window_region(&self, hwnd: WindowHandle, region: &RegionSpec) {
+        set_window_region(&to_hwnd(hwnd), region);
+    }
 
-        if erg.is_ok() {
-            return Some(FormEntry { hwnd, rect });
-        }
-        /* todo: log error */
-        None
+    fn reset_window_style(&self, hwnd: WindowHandle) {
+        reset_window_style(&to_hwnd(hwnd));
     }
-}
 
-unsafe fn get_dpi_from_hwnd(hwnd: &HWND) -> f64 {
-    let dpi = windows::Win32::UI::HiDpi::GetDpiForWindow(*hwnd);
-    dpi.into()
-}
+    fn get_monitors(&self) -> Vec<Monitor> {
+        monitors::get_monitors()
+    }
+
+    fn get_visible_windows(&self) -> Vec<FormEntry> {
+        top_level_windows::get_visible_windows()
+    }
 
-impl TaskbarData {
-    pub fn new(wanted: &WantedHwnds) -> Self {
-        let mut data = TaskbarData::default();
-        /* Safety: Each string does not contain a \0 character, so the string_to_pcstr will not create an unsafe string representation */
+    fn get_work_area(&self) -> Rect {
+        from_rect(&get_rect_of_work_area())
+    }
+
+    fn set_work_area(&self, rect: &Rect) -> bool {
         unsafe {
-            data.taskbar = None;
-            if wanted.taskbar {
-                data.taskbar = FormEntry::new(HWND_TOP, "Shell_TrayWnd");
-                if let Some(taskbar) = &data.taskbar {
-                    data.resolution = get_dpi_from_hwnd(&taskbar.hwnd) / 96.0;
-
-                    data.tray = None;
-                    if wanted.tray {
-                        data.tray = FormEntry::new(taskbar.hwnd, "TrayNotifyWnd");
-                    }
-                    data.rebar = None;
-                    if wanted.rebar {
-                        data.rebar = FormEntry::new(taskbar.hwnd, "ReBarWindow32");
-                        if let Some(rebar) = &data.rebar {
-                            data.applist = None;
-                            if wanted.applist {
-                                data.applist = FormEntry::new(rebar.hwnd, "MSTaskSwWClass");
-                                if let Some(applist) = &data.applist {
-                                    data.apps = None;
-                                    if wanted.apps {
-                                        data.apps =
-                                            FormEntry::new(applist.hwnd, "MSTaskListWClass");
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
-            }
+            call_and_check_set_window_region(
+                &to_rect(rect),
+                &[
+                    SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS(0),
+                    SPIF_SENDWININICHANGE | SPIF_UPDATEINIFILE | SPIF_SENDCHANGE,
+                    SPIF_SENDCHANGE,
+                    SPIF_UPDATEINIFILE,
+                ],
+            )
         }
-        data.wanted_hwnds = wanted.clone();
-        data
     }
 
-    pub fn contains_none(&self) -> bool {
-        (self.applist.is_none() && self.wanted_hwnds.applist)
-            || (self.apps.is_none() && self.wanted_hwnds.apps)
-            || (self.rebar.is_none() && self.wanted_hwnds.rebar)
-            || (self.tray.is_none() && self.wanted_hwnds.tray)
-            || (self.taskbar.is_none() && self.wanted_hwnds.taskbar)
+    fn send_workspace_and_display_change_msg(&self) {
+        send_workspace_and_display_change_msg();
+    }
+
+    fn restart_executable(&self, process_name: &str) -> bool {
+        restart_process::restart_process(process_name).is_ok()
+    }
+}
+
+#[inline]
+pub fn to_hwnd(handle: WindowHandle) -> HWND {
+    HWND(handle.0 as _)
+}
+
+#[inline]
+pub fn from_hwnd(hwnd: HWND) -> WindowHandle {
+    WindowHandle(hwnd.0 as isize)
+}
+
+#[inline]
+pub fn to_rect(rect: &Rect) -> RECT {
+    RECT {
+        left: rect.left,
+        top: rect.top,
+        right: rect.right,
+        bottom: rect.bottom,
     }
 }
 
-/* if the input str contains \0, this function will be unsafe */
 #[inline]
-pub(crate) unsafe fn string_to_pcstr(input: &str) -> windows::core::PCSTR {
-    windows::core::PCSTR::from_raw(format!("{input}\0").as_bytes().as_ptr())
+pub fn from_rect(rect: &RECT) -> Rect {
+    Rect::new(rect.left, rect.top, rect.right, rect.bottom)
+}
+
+/* Safety: Don't call with a str {name} that contains a \0 terminating character. */
+unsafe fn find_form_entry(dependent_hwnd: HWND, name: &str) -> Option<FormEntry> {
+    let class_name = format!("{name}\0");
+    let Ok(hwnd) = windows::Win32::UI::WindowsAndMessaging::FindWindowExA(
+        dependent_hwnd,
+        HWND_TOP,
+        windows::core::PCSTR::from_raw(class_name.as_ptr()),
+        windows::core::PCSTR::null(),
+    ) else {
+        return None;
+    };
+    let mut rect = windows::Win32::Foundation::RECT::default();
+    let erg = windows::Win32::UI::WindowsAndMessaging::GetWindowRect(hwnd, &mut rect);
+
+    if erg.is_ok() {
+        return Some(FormEntry {
+            hwnd: from_hwnd(hwnd),
+            rect: from_rect(&rect),
+        });
+    }
+    /* todo: log error */
+    None
+}
+
+/* finds all windows of the class {name}, each search continues after the last found window. */
+unsafe fn find_form_entries(dependent_hwnd: HWND, name: &str) -> Vec<FormEntry> {
+    let class_name = format!("{name}\0");
+    let mut entries = Vec::new();
+    let mut last_hwnd = HWND_TOP;
+    while let Ok(hwnd) = windows::Win32::UI::WindowsAndMessaging::FindWindowExA(
+        dependent_hwnd,
+        last_hwnd,
+        windows::core::PCSTR::from_raw(class_name.as_ptr()),
+        windows::core::PCSTR::null(),
+    ) {
+        let mut rect = windows::Win32::Foundation::RECT::default();
+        if windows::Win32::UI::WindowsAndMessaging::GetWindowRect(hwnd, &mut rect).is_ok() {
+            entries.push(FormEntry {
+                hwnd: from_hwnd(hwnd),
+                rect: from_rect(&rect),
+            });
+        }
+        last_hwnd = hwnd;
+    }
+    entries
 }
 
-pub fn get_point_in_rect(rect: &RECT, point: &POINT) -> bool {
-    /* safety: both have to be checked to be valid as its done in taskbar::is_hovering_on_tb */
-    unsafe { windows::Win32::Graphics::Gdi::PtInRect(rect, *point).as_bool() }
+unsafe fn get_dpi_from_hwnd(hwnd: &HWND) -> Dpi {
+    Dpi::new(windows::Win32::UI::HiDpi::GetDpiForWindow(*hwnd))
 }
 
 pub fn set_window_topmost(hwnd: &HWND) {
@@ -191,74 +216,38 @@ pub fn set_window_topmost(hwnd: &HWND) {
     }
 }
 
-pub fn create_rounded_region(
-    settings: &TbSettings,
-    tb_data: &TaskbarData,
-    hovering_over_tray: bool,
-    _hovering_over_widgets: bool,
-) {
-    if let Some(taskbar_entry) = &tb_data.taskbar {
-        if let Some(tray_entry) = &tb_data.tray {
-            if let Some(applist_entry) = &tb_data.applist {
-                unsafe {
-                    let resolution = tb_data.resolution;
-
-                    let taskbar_dynamic_region = CreateRoundRectRgn(
-                        (((applist_entry.rect.left) as f64
-                            - settings.get_margin_offset_left() as f64
-                            + settings.get_margin_left() as f64)
-                            * resolution) as i32,
-                        (resolution as i32) + settings.get_margin_top(),
-                        (((applist_entry.rect.right + 2) as f64
-                            + settings.get_margin_offset_right() as f64
-                            - settings.get_margin_right() as f64)
-                            * resolution) as i32,
-                        ((taskbar_entry.rect.bottom as f64 + 1.0
-                            - settings.get_margin_bottom() as f64
-                            - taskbar_entry.rect.top as f64)
-                            * resolution) as i32,
-                        settings.get_rounded_corners_size(),
-                        settings.get_rounded_corners_size(),
-                    );
-
-                    let mut show_tray = false;
-                    if settings.get_dynamic_borders_show_tray() {
-                        show_tray = true;
-                    } else if settings.get_dynamic_borders_show_tray_if_disabled_on_hover() {
-                        show_tray = hovering_over_tray;
-                    }
-                    if show_tray {
-                        let tray_region = CreateRoundRectRgn(
-                            ((tray_entry.rect.left as f64 + settings.get_margin_left() as f64
-                                - 3.0)
-                                * resolution) as i32,
-                            (resolution as i32) + settings.get_margin_top(),
-                            ((tray_entry.rect.right as f64 - settings.get_margin_right() as f64)
-                                * resolution) as i32,
-                            ((taskbar_entry.rect.bottom as f64 + 1.0
-                                - settings.get_margin_bottom() as f64
-                                - taskbar_entry.rect.top as f64)
-                                * resolution) as i32,
-                            settings.get_rounded_corners_size(),
-                            settings.get_rounded_corners_size(),
-                        );
-                        CombineRgn(
-                            taskbar_dynamic_region,
-                            taskbar_dynamic_region,
-                            tray_region,
-                            windows::Win32::Graphics::Gdi::RGN_COMBINE_MODE(2),
-                        );
-                    }
-
-                    //println!("calling setWindowRgn");
-                    SetWindowRgn(taskbar_entry.hwnd, taskbar_dynamic_region, true);
-                }
-            }
+unsafe fn create_round_rect_region(rounded_rect: &RoundedRect) -> HRGN {
+    CreateRoundRectRgn(
+        rounded_rect.rect.left,
+        rounded_rect.rect.top,
+        rounded_rect.rect.right,
+        rounded_rect.rect.bottom,
+        rounded_rect.corner_width.0,
+        rounded_rect.corner_height.0,
+    )
+}
+
+pub fn set_window_region(hwnd: &HWND, region: &RegionSpec) {
+    unsafe {
+        let window_region = create_round_rect_region(&region.base);
+        for (mode, rounded_rect) in &region.combines {
+            let combined_region = create_round_rect_region(rounded_rect);
+            CombineRgn(
+                window_region,
+                window_region,
+                combined_region,
+                RGN_COMBINE_MODE(*mode as i32),
+            );
+            /* only the combined result is handed to the window, the source region has to be freed. */
+            DeleteObject(combined_region);
         }
+
+        //println!("calling setWindowRgn");
+        SetWindowRgn(*hwnd, window_region, true);
     }
 }
 
-pub fn reset_taskbar(hwnd: &HWND, rect: &RECT) {
+pub fn reset_window_style(hwnd: &HWND) {
     unsafe {
         windows::Win32::Graphics::Gdi::SetWindowRgn(
             *hwnd,
@@ -284,8 +273,6 @@ pub fn reset_taskbar(hwnd: &HWND, rect: &RECT) {
                 GetWindowLongA(*hwnd, GWL_EXSTYLE) ^ (WS_EX_TRANSPARENT.0 as i32),
             );
         }
-        // reset taskbar region
-        reset_window_region(rect);
     }
 }
 
@@ -307,18 +294,8 @@ fn get_rect_of_work_area() -> RECT {
     workarea_rect
 }
 
-fn compare_rect_to_workspace_region_for_autohide(current_rect: &RECT, top_offset: u32) -> bool {
-    let workarea_rect = get_rect_of_work_area();
-    workarea_rect.bottom - workarea_rect.top
-        == current_rect.bottom - 1 - current_rect.top - (top_offset as i32)
-}
-
 pub fn check_and_set_transparency_style(hwnd: &HWND) -> bool {
     unsafe {
-        /* as i32 */
-        static __GWL_EXSTYLE: i32 = -20;
-        static __WS_EX_TRANSPARENT: i32 = 0x00000020;
-
         /* check if the style is set to enable transparency first */
         let current_style =
             windows::Win32::UI::WindowsAndMessaging::GetWindowLongA(*hwnd, GWL_EXSTYLE);
@@ -350,14 +327,6 @@ pub fn set_window_alpha(hwnd: &HWND, value: u8) -> bool {
     true
 }
 
-pub fn set_handle_to_topmost(hwnd: &HWND) {
-    unsafe {
-        if let Err(e) = SetWindowPos(*hwnd, HWND_TOPMOST, 0, 0, 0, 0, SWP_NOMOVE | SWP_NOSIZE) {
-            eprintln!("Error setting window to topmost: {:?}", e);
-        }
-    }
-}
-
 pub fn set_app_bar_state(hwnd: &HWND, option: isize) {
     unsafe {
         let mut msg = APPBARDATA {
@@ -373,27 +342,6 @@ pub fn set_app_bar_state(hwnd: &HWND, option: isize) {
     }
 }
 
-/* this function checks if each monitor is configured correctly for the autohide feature. */
-pub fn check_and_update_workspace_region_for_autohide(taskbar: &mut Taskbar, top_offset: u32) {
-    let mut change_in_workspace = false;
-    let monitors = monitors::get_monitors();
-    for primary_monitor in monitors.iter().filter(|m| m.is_primary()) {
-        let display_area = primary_monitor.get_display();
-        taskbar.set_display_area(display_area);
-        if !compare_rect_to_workspace_region_for_autohide(&display_area, top_offset) {
-            /* work area is not configured correctly. Setting to autohide. */
-            //println!("calling set window region");
-            set_window_region_for_autohide(&display_area, top_offset);
-            change_in_workspace = true;
-        }
-    }
-    if change_in_workspace {
-        send_workspace_and_display_change_msg();
-        taskbar.refresh_area_and_set_on_top();
-        taskbar.send_restarts();
-    }
-}
-
 const HWND_BROADCAST: HWND = HWND((-1isize) as _);
 
 fn send_workspace_and_display_change_msg() {
@@ -418,28 +366,6 @@ fn send_workspace_and_display_change_msg() {
     }
 }
 
-fn set_window_region_for_autohide(rect: &RECT, top_offset: u32) {
-    let mut mut_rect = *rect;
-    mut_rect.bottom -= 1;
-    mut_rect.top += top_offset as i32;
-    unsafe {
-        if call_and_check_set_window_region(
-            &mut_rect,
-            &[
-                SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS(0),
-                SPIF_SENDWININICHANGE | SPIF_UPDATEINIFILE | SPIF_SENDCHANGE,
-                SPIF_SENDCHANGE,
-                SPIF_UPDATEINIFILE,
-            ],
-        ) {
-            return;
-        }
-        /* no call worked, todo: log error */
-        eprint!("failed to reset workspace area");
-    }
-    /* */
-}
-
 unsafe fn call_and_check_set_window_region(
     rect: &RECT,
     call_options: &[SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS],
@@ -463,41 +389,44 @@ unsafe fn call_and_check_set_window_region(
     resetted
 }
 
-fn reset_window_region(rect: &RECT) {
-    let mut mut_rect = RECT::default();
-    let mut found_primary_display = false;
-    for primary_monitor in monitors::get_monitors().iter().filter(|m| m.is_primary()) {
-        found_primary_display = true;
-        mut_rect = primary_monitor.get_display();
-        let tb_height = rect.bottom - rect.top;
-        mut_rect.bottom -= tb_height;
-    }
-    if !found_primary_display {
-        panic!("could not find primary display while calling reset on exit");
+/*
+hidden_tb is a windows subsystem program, the commands of the command line print to the console they were started from.
+Returns false if there is no such console, e.g. when started from the explorer.
+*/
+pub fn attach_parent_console() -> bool {
+    /* Safety: fails without side effects if the parent has no console. */
+    unsafe {
+        windows::Win32::System::Console::AttachConsole(
+            windows::Win32::System::Console::ATTACH_PARENT_PROCESS,
+        )
+        .is_ok()
     }
+}
 
+/* for messages of a launch without console. */
+pub fn show_message_box(text: &str) {
+    /* Safety: both strings are valid null terminated strings. */
     unsafe {
-        if call_and_check_set_window_region(
-            &mut_rect,
-            &[
-                SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS(0),
-                SPIF_SENDWININICHANGE | SPIF_UPDATEINIFILE | SPIF_SENDCHANGE,
-                SPIF_SENDCHANGE,
-                SPIF_UPDATEINIFILE,
-            ],
-        ) {
-            send_workspace_and_display_change_msg();
-            return;
-        }
-        /* no call worked, todo: log error */
-        eprint!("failed to reset workspace area");
+        MessageBoxW(
+            HWND::default(),
+            &windows::core::HSTRING::from(text),
+            &windows::core::HSTRING::from("hidden_tb"),
+            MB_OK | MB_ICONINFORMATION,
+        );
     }
-    /* */
 }
 
-#[allow(dead_code)]
 pub fn initialize_windows_calls() {
     unsafe {
+        /*
+        Per monitor dpi awareness turns off the dpi virtualization, every rect and the cursor position are physical pixels
+        of their monitor and the settings are scaled with the dpi of the taskbar they are applied to.
+        */
+        if let Err(e) = windows::Win32::UI::HiDpi::SetProcessDpiAwarenessContext(
+            windows::Win32::UI::HiDpi::DPI_AWARENESS_CONTEXT_PER_MONITOR_AWARE_V2,
+        ) {
+            eprintln!("Error setting the dpi awareness: {:?}", e);
+        }
         /* Initialize system com to retrieve taskbar state in get start menu open function. Safety: None as parameter. */
         if windows::Win32::System::Com::CoInitialize(None).is_err() { /* todo: log error */ }
     }
@@ -517,6 +446,21 @@ pub fn move_window_on_tb(hwnd: &HWND, x: i32, y: i32) -> bool {
     }
 }
 
+/* synchronous, so the rects read afterwards already contain the new position. */
+pub fn set_window_position(hwnd: &HWND, x: i32, y: i32) -> bool {
+    unsafe {
+        move_window(
+            hwnd,
+            HWND_TOP,
+            x,
+            y,
+            0,
+            0,
+            SWP_NOSIZE | SWP_NOACTIVATE | SWP_NOZORDER,
+        )
+    }
+}
+
 unsafe fn move_window(
     hwnd: &HWND,
     position: HWND,
@@ -529,6 +473,36 @@ unsafe fn move_window(
     SetWindowPos(*hwnd, position, x, y, width, height, flag).is_ok()
 }
 
+pub fn get_foreground_window(previous: Option<&ForegroundWindow>) -> Option<ForegroundWindow> {
+    unsafe {
+        let hwnd = GetForegroundWindow();
+        if hwnd.0.is_null() {
+            return None;
+        }
+        let mut class_name = [0u16; 256];
+        let class_name_length = GetClassNameW(hwnd, &mut class_name).max(0) as usize;
+        let mut title = [0u16; 512];
+        let title_length = GetWindowTextW(hwnd, &mut title).max(0) as usize;
+        let exe_name = match previous.filter(|previous| previous.hwnd == from_hwnd(hwnd)) {
+            Some(previous) => previous.exe_name.clone(),
+            None => {
+                let mut process_id = 0;
+                GetWindowThreadProcessId(hwnd, Some(&mut process_id as *mut u32));
+                restart_process::get_process_path(process_id).and_then(|path| {
+                    path.file_name()
+                        .map(|name| name.to_string_lossy().into_owned())
+                })
+            }
+        };
+        Some(ForegroundWindow {
+            hwnd: from_hwnd(hwnd),
+            class_name: String::from_utf16_lossy(&class_name[..class_name_length]),
+            title: String::from_utf16_lossy(&title[..title_length]),
+            exe_name,
+        })
+    }
+}
+
 pub fn get_start_menu_open() -> bool {
     let val = windows::core::GUID::from("7E5FE3D9-985F-4908-91F9-EE19F9FD1514");
     unsafe {
@@ -0,0 +1,102 @@
+use std::time::Duration;
+
+use hidden_tb::animation::{Clock, Easing, FadeAnimation, ManualClock};
+
+const FULL: Duration = Duration::from_millis(80);
+
+#[test]
+fn fade_advances_by_elapsed_time_in_steps() {
+    let clock = ManualClock::new();
+    let mut fade = FadeAnimation::new(255, 8, Easing::Linear);
+    fade.start(0, FULL, clock.now());
+    assert_eq!(fade.tick(clock.now()), 255);
+
+    clock.advance(Duration::from_millis(5));
+    assert_eq!(fade.tick(clock.now()), 255);
+
+    clock.advance(Duration::from_millis(5));
+    assert_eq!(fade.tick(clock.now()), 223);
+    assert!((fade.progress(clock.now()) - 0.125).abs() < 1e-9);
+
+    clock.advance(Duration::from_millis(30));
+    assert_eq!(fade.tick(clock.now()), 128);
+}
+
+#[test]
+fn last_step_lands_on_the_target() {
+    let clock = ManualClock::new();
+    let mut fade = FadeAnimation::new(0, 7, Easing::Linear);
+    fade.start(255, FULL, clock.now());
+    clock.advance(Duration::from_secs(1));
+    assert_eq!(fade.tick(clock.now()), 255);
+    assert!(!fade.is_running());
+    assert_eq!(fade.progress(clock.now()), 1.0);
+}
+
+#[test]
+fn reversing_continues_from_the_current_alpha() {
+    let clock = ManualClock::new();
+    let mut fade = FadeAnimation::new(255, 8, Easing::Linear);
+    fade.start(0, FULL, clock.now());
+    clock.advance(Duration::from_millis(20));
+    assert_eq!(fade.tick(clock.now()), 191);
+
+    fade.start(255, FULL, clock.now());
+    assert_eq!(fade.get_alpha(), 191);
+    assert_eq!(fade.tick(clock.now()), 191);
+
+    /* only the remaining quarter of the distance has to be animated, which takes a quarter of the time */
+    clock.advance(Duration::from_millis(21));
+    assert_eq!(fade.tick(clock.now()), 255);
+    assert!(!fade.is_running());
+}
+
+#[test]
+fn starting_towards_the_current_alpha_does_not_animate() {
+    let mut fade = FadeAnimation::new(255, 8, Easing::Linear);
+    fade.start(255, FULL, Duration::ZERO);
+    assert!(!fade.is_running());
+    assert_eq!(fade.tick(Duration::from_millis(10)), 255);
+}
+
+#[test]
+fn named_easings_keep_their_end_points() {
+    for easing in [
+        Easing::Linear,
+        Easing::EaseIn,
+        Easing::EaseOut,
+        Easing::EaseInOut,
+        Easing::CubicBezier {
+            x1: 0.2,
+            y1: 1.4,
+            x2: 0.6,
+            y2: 1.0,
+        },
+    ] {
+        assert_eq!(easing.apply(0.0), 0.0);
+        assert_eq!(easing.apply(1.0), 1.0);
+    }
+}
+
+#[test]
+fn ease_curves_bend_the_timeline() {
+    assert!(Easing::EaseIn.apply(0.25) < 0.25);
+    assert!(Easing::EaseOut.apply(0.25) > 0.25);
+    assert!((Easing::EaseInOut.apply(0.5) - 0.5).abs() < 1e-6);
+    let linear_bezier = Easing::CubicBezier {
+        x1: 0.25,
+        y1: 0.25,
+        x2: 0.75,
+        y2: 0.75,
+    };
+    assert!((linear_bezier.apply(0.3) - 0.3).abs() < 1e-6);
+}
+
+#[test]
+fn ease_out_fade_drops_faster_at_the_start() {
+    let clock = ManualClock::new();
+    let mut fade = FadeAnimation::new(255, 8, Easing::EaseOut);
+    fade.start(0, FULL, clock.now());
+    clock.advance(Duration::from_millis(20));
+    assert!(fade.tick(clock.now()) < 191);
+}
@@ -0,0 +1,108 @@
+use hidden_tb::app_rules::{compile_rules, find_action};
+use hidden_tb::shell_backend::ForegroundWindow;
+use hidden_tb::tb_settings::{ForegroundRule, RuleAction};
+
+fn window(exe_name: &str, class_name: &str, title: &str) -> ForegroundWindow {
+    ForegroundWindow {
+        exe_name: Some(exe_name.to_string()),
+        class_name: class_name.to_string(),
+        title: title.to_string(),
+        ..Default::default()
+    }
+}
+
+fn rule(
+    exe_name: &str,
+    window_class: &str,
+    title_regex: &str,
+    action: RuleAction,
+) -> ForegroundRule {
+    ForegroundRule {
+        exe_name: exe_name.to_string(),
+        window_class: window_class.to_string(),
+        title_regex: title_regex.to_string(),
+        action,
+    }
+}
+
+#[test]
+fn exe_name_matches_case_insensitive() {
+    let rules = compile_rules(&[rule("code.exe", "", "", RuleAction::AlwaysShow)]);
+    let action = find_action(&rules, &window("Code.exe", "Chrome_WidgetWin_1", "main.rs"));
+    assert_eq!(action, Some(RuleAction::AlwaysShow));
+    assert_eq!(
+        find_action(&rules, &window("notepad.exe", "Notepad", "")),
+        None
+    );
+}
+
+#[test]
+fn every_set_field_has_to_match() {
+    let rules = compile_rules(&[rule(
+        "POWERPNT.EXE",
+        "screenClass",
+        "",
+        RuleAction::AlwaysHide,
+    )]);
+    assert_eq!(
+        find_action(&rules, &window("POWERPNT.EXE", "screenClass", "slides")),
+        Some(RuleAction::AlwaysHide)
+    );
+    assert_eq!(
+        find_action(&rules, &window("POWERPNT.EXE", "PPTFrameClass", "slides")),
+        None
+    );
+}
+
+#[test]
+fn title_is_matched_as_regex() {
+    let rules = compile_rules(&[rule(
+        "",
+        "",
+        r"^Meeting \| .*Teams$",
+        RuleAction::AlwaysShow,
+    )]);
+    assert_eq!(
+        find_action(
+            &rules,
+            &window("ms-teams.exe", "", "Meeting | Microsoft Teams")
+        ),
+        Some(RuleAction::AlwaysShow)
+    );
+    assert_eq!(
+        find_action(
+            &rules,
+            &window("ms-teams.exe", "", "Chat | Microsoft Teams")
+        ),
+        None
+    );
+}
+
+#[test]
+fn first_matching_rule_wins() {
+    let rules = compile_rules(&[
+        rule("Code.exe", "", "debug", RuleAction::AlwaysShow),
+        rule("Code.exe", "", "", RuleAction::DisableDynamicBorders),
+    ]);
+    assert_eq!(
+        find_action(&rules, &window("Code.exe", "", "main.rs [debug]")),
+        Some(RuleAction::AlwaysShow)
+    );
+    assert_eq!(
+        find_action(&rules, &window("Code.exe", "", "main.rs")),
+        Some(RuleAction::DisableDynamicBorders)
+    );
+}
+
+#[test]
+fn empty_and_invalid_rules_never_match() {
+    let rules = compile_rules(&[
+        rule("", "", "", RuleAction::AlwaysHide),
+        rule("", "", "(unclosed", RuleAction::AlwaysHide),
+    ]);
+    assert_eq!(rules.len(), 1);
+    assert_eq!(
+        find_action(&rules, &window("Code.exe", "", "(unclosed")),
+        None
+    );
+}
@@ -0,0 +1,179 @@
+use std::fs;
+use std::path::PathBuf;
+
+use hidden_tb::cli::{self, Command, ConfigCommand, RemoteCommand};
+use hidden_tb::control::{Endpoint, Request};
+use hidden_tb::geometry::{Logical, Rect};
+use hidden_tb::shell_backend::ShellBackend;
+use hidden_tb::simulated_shell::SimulatedShell;
+use hidden_tb::tb_settings::TbSettings;
+
+fn args(line: &str) -> Vec<String> {
+    line.split_whitespace().map(str::to_string).collect()
+}
+
+/* a settings file in the temp dir, unique per test and process. */
+fn settings_file(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!(
+        "hidden_tb_cli_{}_{}.json",
+        name,
+        std::process::id()
+    ));
+    fs::remove_file(&path).ok();
+    path
+}
+
+/* nothing listens on it, like a hidden_tb that is not running. */
+fn no_instance() -> Endpoint {
+    Endpoint::new(
+        std::env::temp_dir()
+            .join(format!("hidden_tb_cli_none_{}.sock", std::process::id()))
+            .to_string_lossy(),
+    )
+}
+
+#[test]
+fn parses_the_subcommands() {
+    assert_eq!(cli::parse(&args("")), Ok(Command::Run));
+    assert_eq!(cli::parse(&args("--config x.json run")), Ok(Command::Run));
+    assert_eq!(
+        cli::parse(&args("--settings --config x.json")),
+        Ok(Command::Settings)
+    );
+    assert_eq!(
+        cli::parse(&args("toggle")),
+        Ok(Command::Remote(RemoteCommand::Toggle))
+    );
+    assert_eq!(
+        cli::parse(&args("profile focus")),
+        Ok(Command::Remote(RemoteCommand::Send(Request::Profile {
+            name: "focus".to_string()
+        })))
+    );
+    assert_eq!(
+        cli::parse(&args("config set --config=x.json margin_top 4")),
+        Ok(Command::Config(ConfigCommand::Set(
+            "margin_top".to_string(),
+            "4".to_string()
+        )))
+    );
+    for line in ["dance", "show now", "config", "config get"] {
+        assert_eq!(
+            cli::parse(&args(line)).unwrap_err().exit_code,
+            cli::EXIT_USAGE
+        );
+    }
+}
+
+#[test]
+fn config_commands_edit_the_settings_file() {
+    let path = settings_file("config");
+    let config = |command| cli::config(&command, &path, &no_instance());
+    assert_eq!(
+        config(ConfigCommand::Get("margin_top".to_string())),
+        Ok("2".to_string())
+    );
+
+    config(ConfigCommand::Set(
+        "margin_top".to_string(),
+        "4".to_string(),
+    ))
+    .unwrap();
+    config(ConfigCommand::Set(
+        "hide_mode".to_string(),
+        "slide".to_string(),
+    ))
+    .unwrap();
+    let settings = TbSettings::load_from(&path).unwrap();
+    assert_eq!(settings.get_margin_top(), Logical(4));
+    assert_eq!(
+        config(ConfigCommand::Get("hide_mode".to_string())),
+        Ok("slide".to_string())
+    );
+    assert!(config(ConfigCommand::List)
+        .unwrap()
+        .contains("margin_top = 4"));
+
+    let invalid = config(ConfigCommand::Set(
+        "animation_steps".to_string(),
+        "0".to_string(),
+    ));
+    assert_eq!(invalid.unwrap_err().exit_code, cli::EXIT_FAILURE);
+    let unknown = config(ConfigCommand::Get("margin".to_string()));
+    assert_eq!(unknown.unwrap_err().exit_code, cli::EXIT_FAILURE);
+    assert_eq!(config(ConfigCommand::Path), Ok(path.display().to_string()));
+    fs::remove_file(&path).ok();
+}
+
+#[test]
+fn validate_fails_for_invalid_settings_files() {
+    let path = settings_file("validate");
+    let validate = || cli::config(&ConfigCommand::Validate, &path, &no_instance());
+    assert!(validate().is_ok());
+
+    let mut value = serde_json::to_value(TbSettings::load_defaults()).unwrap();
+    value["animation_steps"] = serde_json::json!(0);
+    fs::write(&path, value.to_string()).unwrap();
+    let error = validate().unwrap_err();
+    assert_eq!(error.exit_code, cli::EXIT_FAILURE);
+    assert!(error.message.contains("animation_steps"));
+
+    fs::write(&path, "{").unwrap();
+    assert_eq!(validate().unwrap_err().exit_code, cli::EXIT_FAILURE);
+    fs::remove_file(&path).ok();
+}
+
+#[test]
+fn remote_commands_need_a_running_hidden_tb() {
+    let error = cli::remote(&RemoteCommand::Toggle, &no_instance()).unwrap_err();
+    assert_eq!(error.exit_code, cli::EXIT_NOT_RUNNING);
+}
+
+#[test]
+fn restore_resets_what_a_crashed_hidden_tb_left_behind() {
+    let display = Rect::new(0, 0, 1920, 1080);
+    let shell = SimulatedShell::with_standard_taskbar(display, 48);
+    let taskbar = shell
+        .desktop()
+        .window_by_class("Shell_TrayWnd")
+        .unwrap()
+        .hwnd;
+    shell.check_and_set_transparency_style(taskbar);
+    shell.set_window_alpha(taskbar, 0);
+    shell.set_work_area(&Rect::new(0, 0, 1920, 1079));
+
+    assert_eq!(
+        cli::restore(&no_instance(), shell.clone()),
+        Ok("restored 1 taskbars".to_string())
+    );
+    let desktop = shell.desktop();
+    assert_eq!(desktop.taskbar_alpha(), Some(255));
+    assert_eq!(desktop.work_area, Rect::new(0, 0, 1920, 1032));
+}
+
+#[test]
+fn restore_moves_slid_away_taskbars_back() {
+    let display = Rect::new(0, 0, 1920, 1080);
+    let second_display = Rect::new(1920, 0, 3840, 1080);
+    let shell = SimulatedShell::with_standard_taskbar(display, 48);
+    let secondary = shell.add_secondary_taskbar(second_display, 48);
+    let taskbar = shell
+        .desktop()
+        .window_by_class("Shell_TrayWnd")
+        .unwrap()
+        .hwnd;
+    /* a crashed hidden_tb in slide mode left both taskbars below the screen */
+    shell.set_window_position(taskbar, 0, 1080);
+    shell.set_window_position(secondary, 1920, 1080);
+    shell.set_work_area(&Rect::new(0, 0, 1920, 1079));
+
+    assert_eq!(
+        cli::restore(&no_instance(), shell.clone()),
+        Ok("restored 2 taskbars".to_string())
+    );
+    let desktop = shell.desktop();
+    let rect = |hwnd| desktop.window(hwnd).unwrap().rect;
+    assert_eq!(rect(taskbar), Rect::new(0, 1032, 1920, 1080));
+    assert_eq!(rect(secondary), Rect::new(1920, 1032, 3840, 1080));
+    assert_eq!(desktop.work_area, Rect::new(0, 0, 1920, 1032));
+}
@@ -0,0 +1,110 @@
+#![cfg(unix)]
+
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::thread;
+use std::time::Duration;
+
+use hidden_tb::control::{self, ControlServer, Endpoint, ErrorCode, Request, Response};
+use serde_json::json;
+
+/* a socket in the temp dir, unique per test and process. */
+fn endpoint(name: &str) -> Endpoint {
+    let path = std::env::temp_dir().join(format!("hidden_tb_{}_{}.sock", name, std::process::id()));
+    Endpoint::new(path.to_string_lossy())
+}
+
+/* answers requests like the main loop until {count} requests were handled, returns them. */
+fn answer(server: &ControlServer, count: usize, response: Response) -> Vec<Request> {
+    let mut requests = Vec::new();
+    while requests.len() < count {
+        match server.try_next() {
+            Some(pending) => {
+                requests.push(pending.request.clone());
+                pending.respond(response.clone());
+            }
+            None => thread::sleep(Duration::from_millis(5)),
+        }
+    }
+    requests
+}
+
+#[test]
+fn requests_reach_the_main_loop_and_the_answer_goes_back() {
+    let endpoint = endpoint("round_trip");
+    let server = ControlServer::start(&endpoint).unwrap();
+    let request = Request::Preview {
+        values: json!({ "margin_top": 6 }).as_object().unwrap().clone(),
+    };
+
+    let client = {
+        let endpoint = endpoint.clone();
+        let request = request.clone();
+        thread::spawn(move || control::send(&endpoint, &request).unwrap())
+    };
+    assert_eq!(
+        answer(&server, 1, Response::error(ErrorCode::InvalidValue, "nope")),
+        vec![request]
+    );
+    assert_eq!(
+        client.join().unwrap(),
+        Response::error(ErrorCode::InvalidValue, "nope")
+    );
+    std::fs::remove_file(&endpoint.name).ok();
+}
+
+#[test]
+fn one_json_object_per_line() {
+    let endpoint = endpoint("lines");
+    let server = ControlServer::start(&endpoint).unwrap();
+    let client = {
+        let endpoint = endpoint.clone();
+        thread::spawn(move || {
+            let mut stream = UnixStream::connect(&endpoint.name).unwrap();
+            stream
+                .write_all(b"{\"command\":\"revert\"}\nnonsense\n{\"command\":\"dance\"}\n")
+                .unwrap();
+            let mut lines = BufReader::new(stream).lines();
+            let first = lines.next().unwrap().unwrap();
+            let second = lines.next().unwrap().unwrap();
+            let third = lines.next().unwrap().unwrap();
+            (first, second, third)
+        })
+    };
+    assert_eq!(answer(&server, 1, Response::ok()), vec![Request::Revert]);
+
+    let (first, second, third) = client.join().unwrap();
+    assert_eq!(first, r#"{"ok":true}"#);
+    let second: Response = serde_json::from_str(&second).unwrap();
+    assert!(!second.ok);
+    assert_eq!(second.code, Some(ErrorCode::InvalidRequest));
+    let third: Response = serde_json::from_str(&third).unwrap();
+    assert_eq!(third.code, Some(ErrorCode::UnknownCommand));
+    std::fs::remove_file(&endpoint.name).ok();
+}
+
+#[test]
+fn a_socket_in_use_is_not_taken_over() {
+    let endpoint = endpoint("in_use");
+    let _other = UnixListener::bind(&endpoint.name).unwrap();
+    assert!(ControlServer::start(&endpoint).is_err());
+    assert!(UnixStream::connect(&endpoint.name).is_ok());
+    std::fs::remove_file(&endpoint.name).ok();
+}
+
+#[test]
+fn a_stale_socket_is_replaced_but_other_files_are_kept() {
+    let stale = endpoint("stale");
+    drop(UnixListener::bind(&stale.name).unwrap());
+    assert!(ControlServer::start(&stale).is_ok());
+    std::fs::remove_file(&stale.name).ok();
+
+    let regular_file = endpoint("regular_file");
+    std::fs::write(&regular_file.name, "keep me").unwrap();
+    assert!(ControlServer::start(&regular_file).is_err());
+    assert_eq!(
+        std::fs::read_to_string(&regular_file.name).unwrap(),
+        "keep me"
+    );
+    std::fs::remove_file(&regular_file.name).ok();
+}
@@ -0,0 +1,96 @@
+use hidden_tb::geometry::{Dpi, Edge, Logical, Physical, Point, Rect};
+
+const DISPLAY: Rect = Rect {
+    left: 0,
+    top: 0,
+    right: 1920,
+    bottom: 1080,
+};
+
+#[test]
+fn detects_the_edge_of_a_bottom_taskbar() {
+    let taskbar = Rect::new(0, 1032, 1920, 1080);
+    assert_eq!(Edge::of(&taskbar, &DISPLAY), Edge::Bottom);
+    assert!(!Edge::Bottom.is_vertical());
+    assert_eq!(Edge::Bottom.outward(), Point::new(0, 1));
+    assert_eq!(DISPLAY.strip(Edge::Bottom, Physical(48)), taskbar);
+    assert_eq!(
+        DISPLAY.grow_edge(Edge::Bottom, -Physical(48)),
+        Rect::new(0, 0, 1920, 1032)
+    );
+}
+
+#[test]
+fn detects_the_edge_of_a_top_taskbar() {
+    let taskbar = Rect::new(0, 0, 1920, 48);
+    assert_eq!(Edge::of(&taskbar, &DISPLAY), Edge::Top);
+    assert!(!Edge::Top.is_vertical());
+    assert_eq!(Edge::Top.outward(), Point::new(0, -1));
+    assert_eq!(DISPLAY.strip(Edge::Top, Physical(48)), taskbar);
+    assert_eq!(
+        DISPLAY.grow_edge(Edge::Top, -Physical(48)),
+        Rect::new(0, 48, 1920, 1080)
+    );
+}
+
+#[test]
+fn detects_the_edge_of_a_left_taskbar() {
+    let taskbar = Rect::new(0, 0, 62, 1080);
+    assert_eq!(Edge::of(&taskbar, &DISPLAY), Edge::Left);
+    assert!(Edge::Left.is_vertical());
+    assert_eq!(Edge::Left.outward(), Point::new(-1, 0));
+    assert_eq!(DISPLAY.strip(Edge::Left, Physical(62)), taskbar);
+    assert_eq!(
+        DISPLAY.grow_edge(Edge::Left, -Physical(62)),
+        Rect::new(62, 0, 1920, 1080)
+    );
+}
+
+#[test]
+fn detects_the_edge_of_a_right_taskbar() {
+    let taskbar = Rect::new(1858, 0, 1920, 1080);
+    assert_eq!(Edge::of(&taskbar, &DISPLAY), Edge::Right);
+    assert!(Edge::Right.is_vertical());
+    assert_eq!(Edge::Right.outward(), Point::new(1, 0));
+    assert_eq!(DISPLAY.strip(Edge::Right, Physical(62)), taskbar);
+    assert_eq!(
+        DISPLAY.grow_edge(Edge::Right, -Physical(62)),
+        Rect::new(0, 0, 1858, 1080)
+    );
+}
+
+#[test]
+fn detects_edges_on_a_display_that_does_not_start_at_zero() {
+    let display = Rect::new(1920, -200, 3840, 880);
+    assert_eq!(
+        Edge::of(&Rect::new(1920, -200, 3840, -152), &display),
+        Edge::Top
+    );
+    assert_eq!(
+        Edge::of(&Rect::new(3778, -200, 3840, 880), &display),
+        Edge::Right
+    );
+}
+
+#[test]
+fn transposing_swaps_the_axes() {
+    let rect = Rect::new(10, 20, 30, 60);
+    assert_eq!(rect.transposed(), Rect::new(20, 10, 60, 30));
+    assert_eq!(rect.transposed().transposed(), rect);
+}
+
+#[test]
+fn dpi_converts_between_logical_and_physical_pixels() {
+    let dpi = Dpi(168);
+    assert_eq!(dpi.to_physical(Logical(12)), Physical(21));
+    assert_eq!(dpi.to_logical(Physical(21)), Logical(12));
+    assert_eq!(dpi.to_physical(Logical(4)), Physical(7));
+    assert_eq!(Dpi::DEFAULT.to_physical(Logical(13)), Physical(13));
+    assert_eq!(Dpi(144).to_physical(-Logical(1)), Physical(-2));
+    assert_eq!(
+        dpi.to_physical(Logical(2) + Logical(2) - Logical(1)),
+        Physical(5)
+    );
+    /* the shell reports 0 for windows it can not query */
+    assert_eq!(Dpi::new(0), Dpi::DEFAULT);
+}
@@ -0,0 +1,225 @@
+use hidden_tb::geometry::{Dpi, Physical, Rect};
+use hidden_tb::region::{create_rounded_region, CombineMode};
+use hidden_tb::tb_settings::TbSettings;
+use serde_json::json;
+
+const TASKBAR: Rect = Rect {
+    left: 0,
+    top: 1032,
+    right: 1920,
+    bottom: 1080,
+};
+const TRAY: Rect = Rect {
+    left: 1720,
+    top: 1032,
+    right: 1920,
+    bottom: 1080,
+};
+const APPLIST: Rect = Rect {
+    left: 716,
+    top: 1032,
+    right: 1204,
+    bottom: 1080,
+};
+
+/* builds settings from the defaults without touching the settings file. */
+fn settings_with(changes: serde_json::Value) -> TbSettings {
+    let mut value = serde_json::to_value(TbSettings::load_defaults()).unwrap();
+    for (key, change) in changes.as_object().unwrap() {
+        value[key] = change.clone();
+    }
+    serde_json::from_value(value).unwrap()
+}
+
+#[test]
+fn applist_island_uses_margins() {
+    let settings = settings_with(json!({
+        "margin_left": 3,
+        "margin_right": 5,
+        "margin_top": 2,
+        "margin_bottom": 4,
+        "dynamic_borders_show_tray_if_disabled_on_hover": false,
+    }));
+    let region = create_rounded_region(
+        &settings,
+        &TASKBAR,
+        Some(&TRAY),
+        &APPLIST,
+        Dpi::DEFAULT,
+        false,
+        false,
+    );
+    assert_eq!(region.base.rect, Rect::new(719, 3, 1201, 45));
+    assert!(region.combines.is_empty());
+}
+
+#[test]
+fn margin_offsets_widen_the_applist_island() {
+    let settings = settings_with(json!({
+        "margin_offset_left": 40,
+        "margin_offset_right": 60,
+    }));
+    let region = create_rounded_region(
+        &settings,
+        &TASKBAR,
+        Some(&TRAY),
+        &APPLIST,
+        Dpi::DEFAULT,
+        false,
+        false,
+    );
+    assert_eq!(region.base.rect.left, APPLIST.left - 40);
+    assert_eq!(region.base.rect.right, APPLIST.right + 2 + 60);
+}
+
+#[test]
+fn rounded_corners_apply_to_every_island() {
+    let settings = settings_with(json!({
+        "rounded_corners_size": 12,
+        "dynamic_borders_show_tray": true,
+    }));
+    let region = create_rounded_region(
+        &settings,
+        &TASKBAR,
+        Some(&TRAY),
+        &APPLIST,
+        Dpi::DEFAULT,
+        false,
+        false,
+    );
+    assert_eq!(region.base.corner_width, Physical(12));
+    assert_eq!(region.base.corner_height, Physical(12));
+    assert_eq!(region.combines[0].1.corner_width, Physical(12));
+}
+
+#[test]
+fn tray_is_only_shown_on_hover_if_disabled() {
+    let settings = settings_with(json!({
+        "dynamic_borders_show_tray": false,
+        "dynamic_borders_show_tray_if_disabled_on_hover": true,
+    }));
+    let hidden = create_rounded_region(
+        &settings,
+        &TASKBAR,
+        Some(&TRAY),
+        &APPLIST,
+        Dpi::DEFAULT,
+        false,
+        false,
+    );
+    assert!(hidden.combines.is_empty());
+
+    let shown = create_rounded_region(
+        &settings,
+        &TASKBAR,
+        Some(&TRAY),
+        &APPLIST,
+        Dpi::DEFAULT,
+        true,
+        false,
+    );
+    assert_eq!(shown.combines.len(), 1);
+    let (mode, tray_island) = shown.combines[0];
+    assert_eq!(mode, CombineMode::Or);
+    assert_eq!(tray_island.rect, Rect::new(1717, 3, 1920, 47));
+}
+
+#[test]
+fn tray_hover_is_ignored_without_hover_option() {
+    let settings = settings_with(json!({
+        "dynamic_borders_show_tray": false,
+        "dynamic_borders_show_tray_if_disabled_on_hover": false,
+    }));
+    let region = create_rounded_region(
+        &settings,
+        &TASKBAR,
+        Some(&TRAY),
+        &APPLIST,
+        Dpi::DEFAULT,
+        true,
+        false,
+    );
+    assert!(region.combines.is_empty());
+}
+
+#[test]
+fn margins_are_scaled_with_the_dpi() {
+    let settings = settings_with(json!({ "margin_top": 4 }));
+    /* the rects are physical pixels of a 150% display */
+    let region = create_rounded_region(
+        &settings,
+        &scaled(&TASKBAR, 1.5),
+        Some(&scaled(&TRAY, 1.5)),
+        &scaled(&APPLIST, 1.5),
+        Dpi(144),
+        false,
+        false,
+    );
+    assert_eq!(region.base.rect.top, 8);
+    assert_eq!(region.base.rect.bottom, 70);
+}
+
+fn scaled(rect: &Rect, scale: f64) -> Rect {
+    let scale = |value: i32| (value as f64 * scale).round() as i32;
+    Rect::new(
+        scale(rect.left),
+        scale(rect.top),
+        scale(rect.right),
+        scale(rect.bottom),
+    )
+}
+
+fn assert_close(actual: &Rect, expected: &Rect) {
+    for (actual, expected) in [
+        (actual.left, expected.left),
+        (actual.top, expected.top),
+        (actual.right, expected.right),
+        (actual.bottom, expected.bottom),
+    ] {
+        assert!(
+            (actual - expected).abs() <= 1,
+            "{:?} is not {:?}",
+            actual,
+            expected
+        );
+    }
+}
+
+#[test]
+fn islands_look_the_same_on_every_scale() {
+    let settings = settings_with(json!({
+        "margin_left": 4,
+        "margin_right": 8,
+        "margin_top": 4,
+        "margin_bottom": 4,
+        "margin_offset_left": 40,
+        "margin_offset_right": 20,
+        "rounded_corners_size": 12,
+        "dynamic_borders_show_tray": true,
+    }));
+    let normal = create_rounded_region(
+        &settings,
+        &TASKBAR,
+        Some(&TRAY),
+        &APPLIST,
+        Dpi::DEFAULT,
+        false,
+        false,
+    );
+    let large = create_rounded_region(
+        &settings,
+        &scaled(&TASKBAR, 1.75),
+        Some(&scaled(&TRAY, 1.75)),
+        &scaled(&APPLIST, 1.75),
+        Dpi(168),
+        false,
+        false,
+    );
+    assert_close(&large.base.rect, &scaled(&normal.base.rect, 1.75));
+    assert_close(
+        &large.combines[0].1.rect,
+        &scaled(&normal.combines[0].1.rect, 1.75),
+    );
+    assert_eq!(large.base.corner_width, Physical(21));
+    assert_eq!(large.combines[0].1.corner_height, Physical(21));
+}
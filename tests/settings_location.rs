@@ -0,0 +1,97 @@
+use std::fs;
+use std::path::PathBuf;
+
+use hidden_tb::settings_location::{config_argument, LocationSource, SettingsLocation};
+
+/* an empty directory in the temp dir, unique per test and process. */
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!(
+        "hidden_tb_location_{}_{}",
+        name,
+        std::process::id()
+    ));
+    fs::remove_dir_all(&dir).ok();
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn config_argument_wins_over_everything() {
+    let location = SettingsLocation::resolve(
+        Some(PathBuf::from("custom.json")),
+        Some(PathBuf::from("env.json")),
+        Some(PathBuf::from("appdata")),
+        Some(PathBuf::from("exe")),
+    );
+    assert_eq!(location.path, PathBuf::from("custom.json"));
+    assert_eq!(location.source, LocationSource::Argument);
+}
+
+#[test]
+fn environment_wins_over_the_default_locations() {
+    let location = SettingsLocation::resolve(
+        None,
+        Some(PathBuf::from("env.json")),
+        Some(PathBuf::from("appdata")),
+        Some(PathBuf::from("exe")),
+    );
+    assert_eq!(location.source, LocationSource::Environment);
+    /* an empty variable counts as unset */
+    let location = SettingsLocation::resolve(
+        None,
+        Some(PathBuf::new()),
+        Some(PathBuf::from("appdata")),
+        None,
+    );
+    assert_eq!(location.source, LocationSource::UserConfigDir);
+}
+
+#[test]
+fn new_installs_use_the_user_config_dir() {
+    let user_dir = temp_dir("new_user");
+    let exe_dir = temp_dir("new_exe");
+    let location =
+        SettingsLocation::resolve(None, None, Some(user_dir.clone()), Some(exe_dir.clone()));
+    assert_eq!(
+        location.path,
+        user_dir.join("hidden_tb").join("settings.json")
+    );
+    assert_eq!(location.source, LocationSource::UserConfigDir);
+    fs::remove_dir_all(&user_dir).ok();
+    fs::remove_dir_all(&exe_dir).ok();
+}
+
+#[test]
+fn an_existing_portable_file_is_kept() {
+    let user_dir = temp_dir("portable_user");
+    let exe_dir = temp_dir("portable_exe");
+    fs::write(exe_dir.join("settings.json"), "{}").unwrap();
+    let location =
+        SettingsLocation::resolve(None, None, Some(user_dir.clone()), Some(exe_dir.clone()));
+    assert_eq!(location.path, exe_dir.join("settings.json"));
+    assert_eq!(location.source, LocationSource::Portable);
+
+    /* once the user config dir has a file, it wins */
+    fs::create_dir_all(user_dir.join("hidden_tb")).unwrap();
+    fs::write(user_dir.join("hidden_tb").join("settings.json"), "{}").unwrap();
+    let location =
+        SettingsLocation::resolve(None, None, Some(user_dir.clone()), Some(exe_dir.clone()));
+    assert_eq!(location.source, LocationSource::UserConfigDir);
+    fs::remove_dir_all(&user_dir).ok();
+    fs::remove_dir_all(&exe_dir).ok();
+}
+
+#[test]
+fn parses_both_forms_of_the_config_argument() {
+    let args = |args: &[&str]| args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>();
+    assert_eq!(
+        config_argument(&args(&["hidden_tb.exe", "--config", "a.json"])),
+        Some(PathBuf::from("a.json"))
+    );
+    assert_eq!(
+        config_argument(&args(&["hidden_tb.exe", "--settings", "--config=b.json"])),
+        Some(PathBuf::from("b.json"))
+    );
+    assert_eq!(config_argument(&args(&["hidden_tb.exe", "--config"])), None);
+    assert_eq!(config_argument(&args(&["hidden_tb.exe"])), None);
+}
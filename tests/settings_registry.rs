@@ -0,0 +1,144 @@
+use hidden_tb::geometry::Logical;
+use hidden_tb::settings_registry::{self, Kind, Section};
+use hidden_tb::tb_settings::TbSettings;
+use serde_json::{json, Value};
+
+#[test]
+fn every_setting_of_the_file_is_in_the_registry() {
+    let value = serde_json::to_value(TbSettings::load_defaults()).unwrap();
+    let mut keys: Vec<&str> = value
+        .as_object()
+        .unwrap()
+        .keys()
+        .map(String::as_str)
+        .filter(|key| !["version", "active_profile", "profiles"].contains(key))
+        .collect();
+    let mut registered: Vec<&str> = settings_registry::all()
+        .iter()
+        .map(|setting| setting.key)
+        .collect();
+    keys.sort();
+    registered.sort();
+    assert_eq!(keys, registered);
+}
+
+#[test]
+fn entries_are_complete_and_consistent() {
+    for setting in settings_registry::all() {
+        assert!(!setting.label.is_empty(), "{} has no label", setting.key);
+        assert!(
+            !setting.description.is_empty(),
+            "{} has no description",
+            setting.key
+        );
+        if let Some(toggle) = setting.enabled_by {
+            let toggle = settings_registry::find(toggle).unwrap();
+            assert_eq!(toggle.kind, Kind::Toggle, "{}", setting.key);
+        }
+        if let Kind::Number {
+            min, max, slider, ..
+        } = setting.kind
+        {
+            assert!(slider.0 < slider.1, "{}", setting.key);
+            /* the slider of the settings ui starts and ends at allowed values */
+            assert!(min.is_none_or(|min| slider.0 >= min), "{}", setting.key);
+            assert!(max.is_none_or(|max| slider.1 <= max), "{}", setting.key);
+            if let (Some(min), Some(max)) = (min, max) {
+                assert!(min <= max, "{}", setting.key);
+            }
+        }
+    }
+    assert!(Section::ALL
+        .iter()
+        .all(|section| settings_registry::in_section(*section).next().is_some()));
+}
+
+#[test]
+fn sliders_only_offer_allowed_values() {
+    let animation_steps = settings_registry::find("animation_steps").unwrap();
+    assert_eq!(animation_steps.slider_range(), Some(1..=20));
+    for setting in settings_registry::all() {
+        if let Kind::Number { min, max, .. } = setting.kind {
+            let range = setting.slider_range().unwrap();
+            assert!(
+                min.is_none_or(|min| *range.start() >= min),
+                "{}",
+                setting.key
+            );
+            assert!(max.is_none_or(|max| *range.end() <= max), "{}", setting.key);
+        } else {
+            assert_eq!(setting.slider_range(), None);
+        }
+    }
+}
+
+#[test]
+fn numbers_are_unsigned_exactly_if_their_type_is() {
+    let mut settings = TbSettings::load_defaults();
+    for setting in settings_registry::all() {
+        if let Kind::Number { unsigned, .. } = setting.kind {
+            let negative = settings.set_value(setting.key, json!(-1));
+            assert_eq!(negative.is_err(), unsigned, "{}", setting.key);
+        }
+    }
+}
+
+#[test]
+fn values_are_set_by_key_with_their_type() {
+    let mut settings = TbSettings::load_defaults();
+    settings.set_value("margin_top", json!(7)).unwrap();
+    settings.set_value("hide_mode", json!("slide")).unwrap();
+    assert_eq!(settings.get_margin_top(), Logical(7));
+    assert_eq!(settings.get_value("hide_mode"), Some(json!("slide")));
+
+    let error = settings.set_value("hide_mode", json!("drop")).unwrap_err();
+    assert_eq!(error.field, "hide_mode");
+    assert_eq!(error.allowed, "fade, slide");
+    let error = settings
+        .set_value("animation_steps", json!(-1))
+        .unwrap_err();
+    assert_eq!(error.allowed, "1..=255");
+    assert!(settings.set_value("no_such_setting", Value::Null).is_err());
+    assert_eq!(settings.get_value("no_such_setting"), None);
+}
+
+#[test]
+fn changed_settings_include_every_setting() {
+    let saved = TbSettings::load_defaults();
+    let mut edited = saved.clone();
+    assert!(edited.changed_settings(&saved).is_empty());
+
+    edited
+        .set_value("restart_executables", json!(["explorer.exe"]))
+        .unwrap();
+    edited.set_value("autohide", json!(false)).unwrap();
+    let changed: Vec<_> = edited
+        .changed_settings(&saved)
+        .iter()
+        .map(|setting| setting.key)
+        .collect();
+    assert_eq!(changed, vec!["autohide", "restart_executables"]);
+    assert!(
+        settings_registry::find("restart_executables")
+            .unwrap()
+            .restart_needed
+    );
+}
+
+#[test]
+fn search_matches_key_label_and_description() {
+    let setting = settings_registry::find("tb_rect_detection_size_in_pixel").unwrap();
+    assert!(setting.matches(""));
+    assert!(setting.matches("  DETECTION "));
+    assert!(setting.matches("rect_detection"));
+    assert!(setting.matches("strip at the screen edge"));
+    assert!(!setting.matches("corner"));
+
+    let found: Vec<_> = settings_registry::all()
+        .iter()
+        .filter(|setting| setting.matches("margin"))
+        .map(|setting| setting.section)
+        .collect();
+    assert!(found.contains(&Section::Autohide));
+    assert!(found.contains(&Section::DynamicBorders));
+}
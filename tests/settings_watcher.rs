@@ -0,0 +1,83 @@
+use std::fs;
+use std::path::PathBuf;
+
+use hidden_tb::geometry::Logical;
+use hidden_tb::settings_watcher::SettingsWatcher;
+use hidden_tb::tb_settings::TbSettings;
+use serde_json::json;
+
+/* a settings file in the temp dir, unique per test and process. */
+fn settings_file(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("hidden_tb_{}_{}.json", name, std::process::id()));
+    fs::remove_file(&path).ok();
+    path
+}
+
+fn write_settings(path: &PathBuf, changes: serde_json::Value) {
+    let mut value = serde_json::to_value(TbSettings::load_defaults()).unwrap();
+    for (key, change) in changes.as_object().unwrap() {
+        value[key] = change.clone();
+    }
+    fs::write(path, serde_json::to_string_pretty(&value).unwrap()).unwrap();
+}
+
+#[test]
+fn reports_only_changes_after_creation() {
+    let path = settings_file("changes");
+    write_settings(&path, json!({}));
+    let mut watcher = SettingsWatcher::new(path.clone());
+    assert!(watcher.poll().is_none());
+
+    write_settings(&path, json!({ "animation_steps": 16 }));
+    let settings = watcher.poll().unwrap();
+    assert_eq!(settings.get_animation_steps(), 16);
+    assert!(watcher.poll().is_none());
+    fs::remove_file(&path).ok();
+}
+
+#[test]
+fn picks_up_a_file_created_later() {
+    let path = settings_file("created");
+    let mut watcher = SettingsWatcher::new(path.clone());
+    assert!(watcher.poll().is_none());
+
+    write_settings(&path, json!({ "autohide": false }));
+    assert!(!watcher.poll().unwrap().get_autohide());
+    fs::remove_file(&path).ok();
+}
+
+#[test]
+fn skips_unparsable_and_invalid_files_without_touching_them() {
+    let path = settings_file("invalid");
+    write_settings(&path, json!({}));
+    let mut watcher = SettingsWatcher::new(path.clone());
+
+    fs::write(&path, "{ \"autohide\": ").unwrap();
+    assert!(watcher.poll().is_none());
+    assert!(path.exists());
+
+    write_settings(&path, json!({ "infrequent_count": 0 }));
+    assert!(watcher.poll().is_none());
+
+    /* fixing the file applies it */
+    write_settings(&path, json!({ "infrequent_count": 30 }));
+    assert_eq!(watcher.poll().unwrap().get_infrequent_count(), 30);
+    fs::remove_file(&path).ok();
+}
+
+#[test]
+fn saving_counts_as_seen_and_reload_reads_an_unchanged_file() {
+    let path = settings_file("save");
+    write_settings(&path, json!({}));
+    let mut watcher = SettingsWatcher::new(path.clone());
+
+    let mut settings = TbSettings::load_defaults();
+    settings.set_value("margin_top", json!(5)).unwrap();
+    watcher.save(&settings).unwrap();
+    assert!(watcher.poll().is_none());
+    assert_eq!(watcher.reload().unwrap().get_margin_top(), Logical(5));
+
+    write_settings(&path, json!({ "animation_steps": 0 }));
+    assert!(watcher.reload().unwrap_err().contains("animation_steps"));
+    fs::remove_file(&path).ok();
+}
@@ -0,0 +1,100 @@
+use hidden_tb::geometry::{Edge, Rect};
+use hidden_tb::shell_backend::{ShellBackend, TaskbarData, WantedHwnds};
+use hidden_tb::simulated_shell::SimulatedShell;
+use hidden_tb::tb_settings::TbSettings;
+
+const DISPLAY: Rect = Rect {
+    left: 0,
+    top: 0,
+    right: 1920,
+    bottom: 1080,
+};
+
+#[test]
+fn standard_taskbar_has_the_windows_hidden_tb_looks_for() {
+    let shell = SimulatedShell::with_standard_taskbar(DISPLAY, 48);
+    let taskbar = shell.find_window(None, "Shell_TrayWnd").unwrap();
+    assert_eq!(taskbar.rect, Rect::new(0, 1032, 1920, 1080));
+    let rebar = shell
+        .find_window(Some(taskbar.hwnd), "ReBarWindow32")
+        .unwrap();
+    let applist = shell
+        .find_window(Some(rebar.hwnd), "MSTaskSwWClass")
+        .unwrap();
+    assert_eq!(applist.rect, Rect::new(760, 1032, 1160, 1080));
+    assert!(shell
+        .find_window(Some(taskbar.hwnd), "TrayNotifyWnd")
+        .is_some());
+    assert_eq!(shell.get_work_area(), Rect::new(0, 0, 1920, 1032));
+
+    let data = TaskbarData::new(&shell, &WantedHwnds::new(&TbSettings::load_defaults()));
+    assert!(!data.contains_none());
+}
+
+#[test]
+fn alpha_needs_the_layered_style_until_the_style_is_reset() {
+    let shell = SimulatedShell::with_standard_taskbar(DISPLAY, 48);
+    let taskbar = shell.find_window(None, "Shell_TrayWnd").unwrap().hwnd;
+    assert!(!shell.set_window_alpha(taskbar, 0));
+
+    /* the first call sets the style, the next ones find it set */
+    assert!(!shell.check_and_set_transparency_style(taskbar));
+    assert!(shell.check_and_set_transparency_style(taskbar));
+    assert!(shell.set_window_alpha(taskbar, 0));
+    assert_eq!(shell.desktop().taskbar_alpha(), Some(0));
+
+    shell.reset_window_style(taskbar);
+    let desktop = shell.desktop();
+    let window = desktop.window(taskbar).unwrap();
+    assert_eq!(window.alpha, 255);
+    assert!(!window.layered);
+    assert_eq!(desktop.alpha_changes, vec![0]);
+}
+
+#[test]
+fn moving_a_taskbar_moves_its_children() {
+    let shell = SimulatedShell::with_taskbar_on(DISPLAY, Edge::Left, 48);
+    let taskbar = shell.find_window(None, "Shell_TrayWnd").unwrap();
+    assert_eq!(taskbar.rect, Rect::new(0, 0, 48, 1080));
+    let tray = shell
+        .find_window(Some(taskbar.hwnd), "TrayNotifyWnd")
+        .unwrap();
+
+    assert!(shell.set_window_position(taskbar.hwnd, -48, 0));
+    assert_eq!(
+        shell.find_window(None, "Shell_TrayWnd").unwrap().rect,
+        Rect::new(-48, 0, 0, 1080)
+    );
+    assert_eq!(
+        shell.desktop().window(tray.hwnd).unwrap().rect,
+        tray.rect.offset(-48, 0)
+    );
+}
+
+#[test]
+fn work_area_changes_are_kept() {
+    let shell = SimulatedShell::with_standard_taskbar(DISPLAY, 48);
+    assert!(shell.set_work_area(&Rect::new(0, 0, 1920, 1079)));
+    assert_eq!(shell.get_work_area(), Rect::new(0, 0, 1920, 1079));
+    shell.send_workspace_and_display_change_msg();
+    assert_eq!(shell.desktop().broadcasts, 1);
+}
+
+#[test]
+fn visible_windows_leave_out_overlays_and_taskbars() {
+    let shell = SimulatedShell::with_standard_taskbar(DISPLAY, 48);
+    let (app, overlay) = {
+        let mut desktop = shell.desktop();
+        let app = desktop.add_window(None, "Notepad", Rect::new(100, 100, 900, 700));
+        let overlay = desktop.add_window(None, "Toast", Rect::new(1500, 900, 1900, 1000));
+        desktop.window_mut(overlay).unwrap().overlay = true;
+        (app, overlay)
+    };
+    let visible: Vec<_> = shell
+        .get_visible_windows()
+        .iter()
+        .map(|window| window.hwnd)
+        .collect();
+    assert_eq!(visible, vec![app]);
+    assert!(!visible.contains(&overlay));
+}
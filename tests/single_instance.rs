@@ -0,0 +1,30 @@
+use hidden_tb::single_instance;
+
+/* a guard name in the temp dir, unique per test and process. */
+fn guard_name(name: &str) -> String {
+    let path = std::env::temp_dir().join(format!("hidden_tb_{}_{}.lock", name, std::process::id()));
+    path.to_string_lossy().into_owned()
+}
+
+#[test]
+fn only_one_guard_is_held_at_a_time() {
+    let name = guard_name("guard");
+    let guard = single_instance::acquire(&name).unwrap();
+    assert!(guard.is_some());
+    assert!(single_instance::acquire(&name).unwrap().is_none());
+
+    /* the guard is released when the running hidden_tb exits */
+    drop(guard);
+    assert!(single_instance::acquire(&name).unwrap().is_some());
+    std::fs::remove_file(&name).ok();
+}
+
+#[test]
+fn guards_of_other_names_are_independent() {
+    let first = guard_name("first");
+    let second = guard_name("second");
+    let _guard = single_instance::acquire(&first).unwrap().unwrap();
+    assert!(single_instance::acquire(&second).unwrap().is_some());
+    std::fs::remove_file(&first).ok();
+    std::fs::remove_file(&second).ok();
+}
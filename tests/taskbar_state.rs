@@ -0,0 +1,1142 @@
+use std::time::Duration;
+
+use hidden_tb::animation::ManualClock;
+use hidden_tb::control::{ErrorCode, Request, Response};
+use hidden_tb::geometry::{Dpi, Edge, Logical, Physical, Point, Rect};
+use hidden_tb::scheduler::Scheduler;
+use hidden_tb::shell_backend::{Monitor, TaskbarLocation, WindowHandle};
+use hidden_tb::signaling::Signaling;
+use hidden_tb::simulated_shell::SimulatedShell;
+use hidden_tb::taskbar::Taskbar;
+use hidden_tb::taskbars::Taskbars;
+use hidden_tb::tb_settings::TbSettings;
+use serde_json::json;
+
+const DISPLAY: Rect = Rect {
+    left: 0,
+    top: 0,
+    right: 1920,
+    bottom: 1080,
+};
+const SECOND_DISPLAY: Rect = Rect {
+    left: 1920,
+    top: 0,
+    right: 3840,
+    bottom: 1080,
+};
+
+/* the default fade takes 8 steps of 10 ms */
+const FADE: Duration = Duration::from_millis(80);
+
+struct Harness {
+    shell: SimulatedShell,
+    clock: ManualClock,
+    taskbars: Taskbars<SimulatedShell>,
+    scheduler: Scheduler,
+    settings: TbSettings,
+    signaling: Signaling,
+}
+
+impl Harness {
+    fn taskbar(&self) -> &Taskbar<SimulatedShell> {
+        self.taskbars.primary()
+    }
+
+    fn tick(&mut self) {
+        self.scheduler.tick(&self.settings, &mut self.taskbars);
+    }
+
+    /* ticks once to start a fade and once more after it has finished. */
+    fn tick_through_fade(&mut self) {
+        self.tick();
+        self.clock.advance(FADE);
+        self.tick();
+    }
+}
+
+/* builds settings from the defaults without touching the settings file. */
+fn settings_with(changes: serde_json::Value) -> TbSettings {
+    let mut value = serde_json::to_value(TbSettings::load_defaults()).unwrap();
+    for (key, change) in changes.as_object().unwrap() {
+        value[key] = change.clone();
+    }
+    serde_json::from_value(value).unwrap()
+}
+
+fn start() -> Harness {
+    start_with(TbSettings::load_defaults())
+}
+
+fn start_with(settings: TbSettings) -> Harness {
+    start_on(SimulatedShell::with_standard_taskbar(DISPLAY, 48), settings)
+}
+
+fn start_on(shell: SimulatedShell, settings: TbSettings) -> Harness {
+    let clock = ManualClock::new();
+    let mut taskbars =
+        Taskbars::with_clock(settings.clone(), shell.clone(), Box::new(clock.clone()));
+    let scheduler = Scheduler::new(&settings);
+    scheduler.init(&settings, &mut taskbars, &Signaling::default());
+    Harness {
+        shell,
+        clock,
+        taskbars,
+        scheduler,
+        settings,
+        signaling: Signaling::default(),
+    }
+}
+
+#[test]
+fn finds_all_wanted_handles() {
+    let tb = start();
+    assert!(!tb.taskbar().contains_none());
+    let desktop = tb.shell.desktop();
+    assert!(desktop.window_by_class("Shell_TrayWnd").unwrap().layered);
+}
+
+#[test]
+fn hides_when_cursor_leaves_and_shows_on_bottom_edge() {
+    let mut tb = start();
+
+    tb.shell.set_cursor_pos(Some(Point::new(960, 500)));
+    tb.tick_through_fade();
+    assert!(tb.taskbar().is_hidden());
+    assert_eq!(tb.shell.desktop().taskbar_alpha(), Some(0));
+
+    /* the hidden detection strip is only the bottom pixel row */
+    tb.shell.set_cursor_pos(Some(Point::new(960, 1070)));
+    tb.tick();
+    assert!(tb.taskbar().is_hidden());
+
+    tb.shell.set_cursor_pos(Some(Point::new(960, 1079)));
+    tb.tick_through_fade();
+    assert!(!tb.taskbar().is_hidden());
+    assert_eq!(tb.shell.desktop().taskbar_alpha(), Some(255));
+
+    /* once shown, the whole island keeps the taskbar visible */
+    tb.shell.set_cursor_pos(Some(Point::new(960, 1040)));
+    tb.tick_through_fade();
+    assert!(!tb.taskbar().is_hidden());
+}
+
+#[test]
+fn fade_does_not_block_and_reverses_on_hover() {
+    let mut tb = start();
+
+    tb.shell.set_cursor_pos(Some(Point::new(960, 500)));
+    tb.tick();
+    assert!(tb.taskbar().is_animating());
+    tb.clock.advance(FADE / 2);
+    tb.tick();
+    assert_eq!(tb.shell.desktop().taskbar_alpha(), Some(128));
+    assert!(!tb.taskbar().is_hidden());
+
+    /* hovering back onto the fading taskbar reverses the fade from the current alpha */
+    tb.shell.set_cursor_pos(Some(Point::new(960, 1040)));
+    tb.tick();
+    assert!(tb.taskbar().is_animating());
+    tb.clock.advance(FADE / 2);
+    tb.tick();
+    assert!(!tb.taskbar().is_animating());
+    assert_eq!(tb.shell.desktop().taskbar_alpha(), Some(255));
+    assert!(!tb.taskbar().is_hidden());
+}
+
+#[test]
+fn start_menu_keeps_taskbar_visible() {
+    let mut tb = start();
+    tb.shell.set_cursor_pos(Some(Point::new(960, 500)));
+    tb.tick_through_fade();
+    assert!(tb.taskbar().is_hidden());
+
+    tb.shell.set_start_menu_open(true);
+    tb.tick_through_fade();
+    assert!(!tb.taskbar().is_hidden());
+    assert_eq!(tb.shell.desktop().taskbar_alpha(), Some(255));
+}
+
+#[test]
+fn tray_hover_extends_the_dynamic_region() {
+    let mut tb = start();
+    tb.shell.set_cursor_pos(Some(Point::new(1800, 1079)));
+    tb.tick();
+    assert!(!tb.taskbar().is_hidden());
+    let combines = tb.shell.desktop().region.clone().unwrap().spec.combines;
+    assert_eq!(combines.len(), 1);
+
+    tb.shell.set_cursor_pos(Some(Point::new(960, 1079)));
+    tb.tick();
+    let combines = tb.shell.desktop().region.clone().unwrap().spec.combines;
+    assert!(combines.is_empty());
+}
+
+#[test]
+fn work_area_is_set_for_autohide_and_restored_on_clean_up() {
+    let mut tb = start();
+    tb.shell.set_cursor_pos(Some(Point::new(960, 500)));
+    tb.tick_through_fade();
+    assert_eq!(tb.shell.desktop().work_area, Rect::new(0, 0, 1920, 1079));
+    assert!(tb.shell.desktop().broadcasts > 0);
+
+    tb.taskbars.clean_up();
+    let desktop = tb.shell.desktop();
+    assert_eq!(desktop.work_area, Rect::new(0, 0, 1920, 1032));
+    assert_eq!(desktop.taskbar_alpha(), Some(255));
+    assert!(!desktop.window_by_class("Shell_TrayWnd").unwrap().layered);
+}
+
+fn taskbar_rect(tb: &Harness) -> Rect {
+    tb.shell
+        .desktop()
+        .window_by_class("Shell_TrayWnd")
+        .unwrap()
+        .rect
+}
+
+#[test]
+fn slide_mode_moves_the_taskbar_off_screen_instead_of_fading() {
+    let mut tb = start_with(settings_with(json!({ "hide_mode": "slide" })));
+    let docked = taskbar_rect(&tb);
+
+    tb.shell.set_cursor_pos(Some(Point::new(960, 500)));
+    tb.tick();
+    tb.clock.advance(FADE / 2);
+    tb.tick();
+    assert_eq!(taskbar_rect(&tb), docked.offset(0, 24));
+
+    tb.clock.advance(FADE / 2);
+    tb.tick();
+    assert!(tb.taskbar().is_hidden());
+    assert_eq!(taskbar_rect(&tb), docked.offset(0, 48));
+    assert_eq!(tb.shell.desktop().taskbar_alpha(), Some(255));
+    let tray = tb
+        .shell
+        .desktop()
+        .window_by_class("TrayNotifyWnd")
+        .unwrap()
+        .rect;
+    assert_eq!(tray.top, DISPLAY.bottom);
+
+    /* the detection strip stays at the docked bottom edge */
+    tb.shell.set_cursor_pos(Some(Point::new(960, 1079)));
+    tb.tick_through_fade();
+    assert!(!tb.taskbar().is_hidden());
+    assert_eq!(taskbar_rect(&tb), docked);
+}
+
+#[test]
+fn clean_up_restores_a_slid_away_taskbar() {
+    let mut tb = start_with(settings_with(json!({ "hide_mode": "slide" })));
+    let docked = taskbar_rect(&tb);
+    tb.shell.set_cursor_pos(Some(Point::new(960, 500)));
+    tb.tick_through_fade();
+    assert_ne!(taskbar_rect(&tb), docked);
+
+    tb.taskbars.clean_up();
+    assert_eq!(taskbar_rect(&tb), docked);
+}
+
+#[test]
+fn reveal_delay_requires_the_cursor_to_dwell_on_the_edge() {
+    let mut tb = start_with(settings_with(json!({ "reveal_delay_in_ms": 300 })));
+    tb.shell.set_cursor_pos(Some(Point::new(960, 500)));
+    tb.tick_through_fade();
+    assert!(tb.taskbar().is_hidden());
+
+    /* brushing the edge does not reveal */
+    tb.shell.set_cursor_pos(Some(Point::new(960, 1079)));
+    tb.tick();
+    tb.clock.advance(Duration::from_millis(200));
+    tb.tick();
+    assert!(!tb.taskbar().is_animating());
+    tb.shell.set_cursor_pos(Some(Point::new(960, 500)));
+    tb.tick();
+
+    /* the dwell time starts again on the next visit */
+    tb.shell.set_cursor_pos(Some(Point::new(960, 1079)));
+    tb.tick();
+    tb.clock.advance(Duration::from_millis(200));
+    tb.tick();
+    assert!(tb.taskbar().is_hidden());
+    tb.clock.advance(Duration::from_millis(100));
+    tb.tick_through_fade();
+    assert!(!tb.taskbar().is_hidden());
+    assert_eq!(tb.shell.desktop().taskbar_alpha(), Some(255));
+}
+
+#[test]
+fn hide_delay_keeps_the_taskbar_after_leaving() {
+    let mut tb = start_with(settings_with(json!({ "hide_delay_in_ms": 500 })));
+    tb.shell.set_cursor_pos(Some(Point::new(960, 500)));
+    tb.tick();
+    tb.clock.advance(Duration::from_millis(400));
+    tb.tick();
+    assert!(!tb.taskbar().is_animating());
+    assert_eq!(tb.shell.desktop().taskbar_alpha(), Some(255));
+
+    /* coming back within the grace period cancels the hide */
+    tb.shell.set_cursor_pos(Some(Point::new(960, 1040)));
+    tb.tick();
+    tb.shell.set_cursor_pos(Some(Point::new(960, 500)));
+    tb.tick();
+    tb.clock.advance(Duration::from_millis(400));
+    tb.tick();
+    assert!(!tb.taskbar().is_animating());
+
+    tb.clock.advance(Duration::from_millis(100));
+    tb.tick_through_fade();
+    assert!(tb.taskbar().is_hidden());
+}
+
+#[test]
+fn push_to_reveal_needs_time_pinned_to_the_edge() {
+    let mut tb = start_with(settings_with(json!({
+        "push_to_reveal": true,
+        "push_reveal_time_in_ms": 300,
+    })));
+    tb.shell.set_cursor_pos(Some(Point::new(960, 500)));
+    tb.tick_through_fade();
+    assert!(tb.taskbar().is_hidden());
+
+    tb.shell.set_cursor_pos(Some(Point::new(960, 1079)));
+    tb.tick();
+    tb.clock.advance(Duration::from_millis(200));
+    tb.tick();
+    assert!(!tb.taskbar().is_animating());
+
+    tb.clock.advance(Duration::from_millis(100));
+    tb.tick_through_fade();
+    assert!(!tb.taskbar().is_hidden());
+}
+
+#[test]
+fn push_to_reveal_keeps_the_pressure_along_the_edge() {
+    let mut tb = start_with(settings_with(json!({
+        "push_to_reveal": true,
+        "push_reveal_time_in_ms": 300,
+    })));
+    tb.shell.set_cursor_pos(Some(Point::new(960, 500)));
+    tb.tick_through_fade();
+
+    /* sweeping along the edge does not build pressure on its own */
+    for x in [800, 900, 1000, 1100] {
+        tb.shell.set_cursor_pos(Some(Point::new(x, 1079)));
+        tb.tick();
+    }
+    assert!(!tb.taskbar().is_animating());
+
+    /* but it does not release the pressure either */
+    tb.clock.advance(Duration::from_millis(200));
+    tb.shell.set_cursor_pos(Some(Point::new(900, 1079)));
+    tb.tick();
+    assert!(!tb.taskbar().is_animating());
+    tb.clock.advance(Duration::from_millis(100));
+    tb.tick_through_fade();
+    assert!(!tb.taskbar().is_hidden());
+}
+
+#[test]
+fn push_to_reveal_starts_over_when_moving_away_from_the_edge() {
+    let mut tb = start_with(settings_with(json!({
+        "push_to_reveal": true,
+        "push_reveal_time_in_ms": 300,
+        "tb_rect_detection_size_in_pixel": 4,
+    })));
+    tb.shell.set_cursor_pos(Some(Point::new(960, 500)));
+    tb.tick_through_fade();
+
+    tb.shell.set_cursor_pos(Some(Point::new(960, 1079)));
+    tb.tick();
+    tb.clock.advance(Duration::from_millis(200));
+    /* still on the detection strip, but away from the edge */
+    tb.shell.set_cursor_pos(Some(Point::new(960, 1077)));
+    tb.tick();
+    tb.clock.advance(Duration::from_millis(200));
+    tb.shell.set_cursor_pos(Some(Point::new(960, 1079)));
+    tb.tick();
+    assert!(!tb.taskbar().is_animating());
+
+    tb.clock.advance(Duration::from_millis(100));
+    tb.tick_through_fade();
+    assert!(!tb.taskbar().is_hidden());
+}
+
+#[test]
+fn leaving_the_island_uses_the_leave_margin() {
+    let mut tb = start_with(settings_with(json!({ "hover_leave_margin_in_pixel": 10 })));
+    tb.shell.set_cursor_pos(Some(Point::new(960, 1040)));
+    tb.tick();
+
+    /* the applist island ends at 1204, the leave zone 10 pixels later */
+    tb.shell.set_cursor_pos(Some(Point::new(1210, 1040)));
+    tb.tick();
+    assert!(!tb.taskbar().is_animating());
+
+    tb.shell.set_cursor_pos(Some(Point::new(1220, 1040)));
+    tb.tick_through_fade();
+    assert!(tb.taskbar().is_hidden());
+}
+
+#[test]
+fn entering_the_island_does_not_use_the_leave_margin() {
+    let mut tb = start_with(settings_with(json!({ "hover_leave_margin_in_pixel": 10 })));
+    tb.shell.set_cursor_pos(Some(Point::new(1210, 1040)));
+    tb.tick();
+    assert!(tb.taskbar().is_animating());
+}
+
+#[test]
+fn tray_hover_uses_the_leave_margin() {
+    let mut tb = start_with(settings_with(json!({ "hover_leave_margin_in_pixel": 10 })));
+    tb.shell.set_cursor_pos(Some(Point::new(1800, 1079)));
+    tb.tick();
+    assert_eq!(
+        tb.shell
+            .desktop()
+            .region
+            .clone()
+            .unwrap()
+            .spec
+            .combines
+            .len(),
+        1
+    );
+
+    /* the tray island starts at 1720 */
+    tb.shell.set_cursor_pos(Some(Point::new(1715, 1060)));
+    tb.tick();
+    assert_eq!(
+        tb.shell
+            .desktop()
+            .region
+            .clone()
+            .unwrap()
+            .spec
+            .combines
+            .len(),
+        1
+    );
+
+    tb.shell.set_cursor_pos(Some(Point::new(1705, 1060)));
+    tb.tick();
+    assert!(tb
+        .shell
+        .desktop()
+        .region
+        .clone()
+        .unwrap()
+        .spec
+        .combines
+        .is_empty());
+}
+
+#[test]
+fn intellihide_only_hides_while_a_window_overlaps() {
+    let mut tb = start_with(settings_with(json!({
+        "autohide_policy": "intellihide",
+        "infrequent_count": 1,
+    })));
+    tb.shell.set_cursor_pos(Some(Point::new(960, 500)));
+    tb.shell
+        .desktop()
+        .add_window(None, "Notepad", Rect::new(100, 100, 900, 700));
+    tb.tick_through_fade();
+    assert!(!tb.taskbar().is_hidden());
+    assert_eq!(tb.shell.desktop().taskbar_alpha(), Some(255));
+
+    let maximized = tb
+        .shell
+        .desktop()
+        .add_window(None, "Notepad", Rect::new(0, 0, 1920, 1079));
+    tb.tick_through_fade();
+    assert!(tb.taskbar().is_hidden());
+
+    /* hovering still reveals while the window overlaps */
+    tb.shell.set_cursor_pos(Some(Point::new(960, 1079)));
+    tb.tick_through_fade();
+    assert!(!tb.taskbar().is_hidden());
+
+    tb.shell.set_cursor_pos(Some(Point::new(960, 500)));
+    tb.shell.desktop().remove_window(maximized);
+    tb.tick_through_fade();
+    assert!(!tb.taskbar().is_hidden());
+}
+
+#[test]
+fn intellihide_ignores_overlays_and_taskbars() {
+    let mut tb = start_with(settings_with(json!({
+        "autohide_policy": "intellihide",
+        "infrequent_count": 1,
+    })));
+    tb.shell.set_cursor_pos(Some(Point::new(960, 500)));
+    {
+        let mut desktop = tb.shell.desktop();
+        let overlay = desktop.add_window(None, "RecordingBorder", Rect::new(0, 0, 1920, 1080));
+        desktop.window_mut(overlay).unwrap().overlay = true;
+        desktop.add_window(
+            None,
+            "Shell_SecondaryTrayWnd",
+            Rect::new(0, 1000, 1920, 1080),
+        );
+    }
+    tb.tick_through_fade();
+    assert!(!tb.taskbar().is_hidden());
+}
+
+#[test]
+fn intellihide_checks_the_island_with_dynamic_borders() {
+    let mut tb = start_with(settings_with(json!({
+        "autohide_policy": "intellihide",
+        "infrequent_count": 1,
+    })));
+    tb.shell.set_cursor_pos(Some(Point::new(300, 500)));
+    /* overlaps the taskbar left of the applist island */
+    let window = tb
+        .shell
+        .desktop()
+        .add_window(None, "Notepad", Rect::new(0, 0, 600, 1079));
+    tb.tick_through_fade();
+    assert!(!tb.taskbar().is_hidden());
+
+    tb.shell.desktop().window_mut(window).unwrap().visible = false;
+    tb.shell
+        .desktop()
+        .add_window(None, "Notepad", Rect::new(800, 900, 1000, 1040));
+    tb.tick_through_fade();
+    assert!(tb.taskbar().is_hidden());
+}
+
+#[test]
+fn intellihide_checks_the_windows_in_the_infrequent_routine() {
+    let mut tb = start_with(settings_with(json!({
+        "autohide_policy": "intellihide",
+        "infrequent_count": 5,
+    })));
+    tb.shell.set_cursor_pos(Some(Point::new(960, 500)));
+    tb.tick();
+    tb.shell
+        .desktop()
+        .add_window(None, "Notepad", Rect::new(0, 0, 1920, 1079));
+    tb.tick_through_fade();
+    assert!(!tb.taskbar().is_hidden());
+
+    /* every fifth tick runs the infrequent routine, the sixth tick is the next one */
+    tb.tick();
+    tb.tick();
+    tb.tick_through_fade();
+    assert!(tb.taskbar().is_hidden());
+}
+
+fn focus_app(tb: &Harness, exe_name: &str) {
+    let mut desktop = tb.shell.desktop();
+    let app = desktop.add_window(None, "App", Rect::new(100, 100, 900, 700));
+    desktop.window_mut(app).unwrap().exe_name = Some(exe_name.to_string());
+    desktop.foreground = Some(app);
+}
+
+#[test]
+fn foreground_rule_keeps_the_taskbar_visible() {
+    let mut tb = start_with(settings_with(json!({
+        "foreground_rules": [{ "exe_name": "debugger.exe", "action": "always_show" }],
+    })));
+    focus_app(&tb, "debugger.exe");
+    tb.shell.set_cursor_pos(Some(Point::new(960, 500)));
+    tb.tick_through_fade();
+    assert!(!tb.taskbar().is_hidden());
+
+    focus_app(&tb, "notepad.exe");
+    tb.tick_through_fade();
+    assert!(tb.taskbar().is_hidden());
+}
+
+#[test]
+fn foreground_rule_hides_the_taskbar_on_hover() {
+    let mut tb = start_with(settings_with(json!({
+        "foreground_rules": [{ "exe_name": "slides.exe", "action": "always_hide" }],
+    })));
+    focus_app(&tb, "slides.exe");
+    tb.shell.set_cursor_pos(Some(Point::new(960, 1040)));
+    tb.tick_through_fade();
+    assert!(tb.taskbar().is_hidden());
+
+    tb.shell.set_start_menu_open(true);
+    tb.tick_through_fade();
+    assert!(!tb.taskbar().is_hidden());
+}
+
+#[test]
+fn foreground_rule_disables_dynamic_borders() {
+    let mut tb = start_with(settings_with(json!({
+        "foreground_rules": [{ "exe_name": "game.exe", "action": "disable_dynamic_borders" }],
+    })));
+    tb.shell.set_cursor_pos(Some(Point::new(960, 1040)));
+    tb.tick();
+    let island = tb.shell.desktop().region.clone().unwrap().spec.base.rect;
+    assert_ne!(island.left, 0);
+
+    focus_app(&tb, "game.exe");
+    tb.tick();
+    let full = tb.shell.desktop().region.clone().unwrap().spec;
+    assert_eq!(full.base.rect, Rect::new(0, 0, 1921, 49));
+    assert!(full.combines.is_empty());
+
+    /* the whole taskbar counts as hover zone while the borders are disabled */
+    tb.shell.set_cursor_pos(Some(Point::new(100, 1040)));
+    tb.tick();
+    assert!(!tb.taskbar().is_animating());
+
+    focus_app(&tb, "notepad.exe");
+    tb.tick();
+    assert_eq!(
+        tb.shell.desktop().region.clone().unwrap().spec.base.rect,
+        island
+    );
+}
+
+#[test]
+fn foreground_process_is_only_queried_when_the_foreground_window_changes() {
+    let shell = SimulatedShell::with_standard_taskbar(DISPLAY, 48);
+    shell.add_secondary_taskbar(SECOND_DISPLAY, 48);
+    let mut tb = start_on(
+        shell,
+        settings_with(json!({
+            "foreground_rules": [{ "exe_name": "debugger.exe", "action": "always_show" }],
+        })),
+    );
+    focus_app(&tb, "debugger.exe");
+    for _ in 0..3 {
+        tb.tick();
+    }
+    assert_eq!(tb.shell.desktop().process_queries, 1);
+
+    focus_app(&tb, "notepad.exe");
+    tb.tick();
+    tb.tick();
+    assert_eq!(tb.shell.desktop().process_queries, 2);
+}
+
+fn alpha_of(tb: &Harness, hwnd: WindowHandle) -> u8 {
+    tb.shell.desktop().window(hwnd).unwrap().alpha
+}
+
+#[test]
+fn secondary_taskbar_hides_and_shows_independently() {
+    let shell = SimulatedShell::with_standard_taskbar(DISPLAY, 48);
+    let secondary = shell.add_secondary_taskbar(SECOND_DISPLAY, 48);
+    let mut tb = start_on(shell, TbSettings::load_defaults());
+    assert_eq!(tb.taskbars.secondaries().len(), 1);
+    assert!(tb.shell.desktop().window(secondary).unwrap().layered);
+
+    tb.shell.set_cursor_pos(Some(Point::new(960, 500)));
+    tb.tick_through_fade();
+    assert_eq!(tb.shell.desktop().taskbar_alpha(), Some(0));
+    assert_eq!(alpha_of(&tb, secondary), 0);
+
+    tb.shell.set_cursor_pos(Some(Point::new(2880, 1079)));
+    tb.tick_through_fade();
+    assert_eq!(alpha_of(&tb, secondary), 255);
+    assert!(!tb.taskbars.secondaries()[0].is_hidden());
+    assert!(tb.taskbar().is_hidden());
+}
+
+#[test]
+fn secondary_taskbar_region_is_relative_to_its_window() {
+    let shell = SimulatedShell::with_standard_taskbar(DISPLAY, 48);
+    let secondary = shell.add_secondary_taskbar(SECOND_DISPLAY, 48);
+    let tb = start_on(shell, TbSettings::load_defaults());
+    let region = tb
+        .shell
+        .desktop()
+        .window(secondary)
+        .unwrap()
+        .region
+        .clone()
+        .unwrap();
+    /* the task list spans 2680..3080, secondary taskbars have no tray island */
+    assert_eq!(region.base.rect.left, 760);
+    assert_eq!(region.base.rect.right, 1162);
+    assert!(region.combines.is_empty());
+}
+
+#[test]
+fn secondary_taskbars_follow_the_monitors() {
+    let mut tb = start_with(settings_with(json!({ "infrequent_count": 1 })));
+    assert!(tb.taskbars.secondaries().is_empty());
+
+    let secondary = tb.shell.add_secondary_taskbar(SECOND_DISPLAY, 48);
+    tb.tick();
+    assert_eq!(tb.taskbars.secondaries().len(), 1);
+
+    {
+        let mut desktop = tb.shell.desktop();
+        desktop.monitors.retain(|monitor| monitor.is_primary());
+        desktop.remove_window(secondary);
+    }
+    tb.tick();
+    assert!(tb.taskbars.secondaries().is_empty());
+}
+
+#[test]
+fn secondary_taskbar_is_restored_when_its_monitor_changes_geometry() {
+    let mut tb = start_with(settings_with(json!({ "infrequent_count": 1 })));
+    let secondary = tb.shell.add_secondary_taskbar(SECOND_DISPLAY, 48);
+    tb.tick();
+    tb.shell.set_cursor_pos(Some(Point::new(960, 500)));
+    tb.tick_through_fade();
+    assert_eq!(alpha_of(&tb, secondary), 0);
+
+    /* the monitor grows upwards, the taskbar window stays on its bottom edge */
+    let resized = Rect::new(1920, -120, 3840, 1080);
+    for monitor in tb.shell.desktop().monitors.iter_mut() {
+        if monitor.get_display() == SECOND_DISPLAY {
+            *monitor = Monitor::new(resized, false);
+        }
+    }
+    tb.tick();
+    assert_eq!(tb.taskbars.secondaries().len(), 1);
+    let taskbar = &tb.taskbars.secondaries()[0];
+    assert_eq!(taskbar.get_location(), TaskbarLocation::Secondary(resized));
+    assert!(!taskbar.is_hidden());
+    assert_eq!(alpha_of(&tb, secondary), 255);
+}
+
+#[test]
+fn clean_up_restores_secondary_taskbars() {
+    let shell = SimulatedShell::with_standard_taskbar(DISPLAY, 48);
+    let secondary = shell.add_secondary_taskbar(SECOND_DISPLAY, 48);
+    let mut tb = start_on(shell, TbSettings::load_defaults());
+    tb.shell.set_cursor_pos(Some(Point::new(960, 500)));
+    tb.tick_through_fade();
+
+    tb.taskbars.clean_up();
+    let desktop = tb.shell.desktop();
+    let window = desktop.window(secondary).unwrap();
+    assert_eq!(window.alpha, 255);
+    assert!(!window.layered);
+    assert!(window.region.is_none());
+    assert_eq!(desktop.work_area, Rect::new(0, 0, 1920, 1032));
+}
+
+#[test]
+fn slid_away_secondary_taskbar_is_found_again_after_a_reload() {
+    let shell = SimulatedShell::with_standard_taskbar(DISPLAY, 48);
+    let secondary = shell.add_secondary_taskbar(SECOND_DISPLAY, 48);
+    let mut tb = start_on(shell, settings_with(json!({ "hide_mode": "slide" })));
+    let docked = Rect::new(1920, 1032, 3840, 1080);
+    let secondary_rect = |tb: &Harness| tb.shell.desktop().window(secondary).unwrap().rect;
+    tb.shell.set_cursor_pos(Some(Point::new(960, 500)));
+    tb.tick_through_fade();
+    assert_eq!(secondary_rect(&tb), docked.offset(0, 48));
+
+    /* the wanted handles change, the handles are looked up again while the taskbar is slid away */
+    tb.reload(json!({ "hide_mode": "slide", "enable_dynamic_borders": false }));
+    tb.shell.set_cursor_pos(Some(Point::new(2880, 1079)));
+    tb.tick_through_fade();
+    assert!(!tb.taskbars.secondaries()[0].is_hidden());
+    assert_eq!(secondary_rect(&tb), docked);
+
+    tb.shell.set_cursor_pos(Some(Point::new(960, 500)));
+    tb.tick_through_fade();
+    assert_eq!(secondary_rect(&tb), docked.offset(0, 48));
+    tb.taskbars.clean_up();
+    assert_eq!(secondary_rect(&tb), docked);
+}
+
+fn start_docked(edge: Edge, settings: TbSettings) -> Harness {
+    start_on(SimulatedShell::with_taskbar_on(DISPLAY, edge, 48), settings)
+}
+
+/* hides the taskbar, checks that {inside} the hidden strip does not reveal it and {edge_pos} does. */
+fn assert_reveals_only_on_the_edge(tb: &mut Harness, inside: Point, edge_pos: Point) {
+    tb.shell.set_cursor_pos(Some(Point::new(960, 500)));
+    tb.tick_through_fade();
+    assert!(tb.taskbar().is_hidden());
+
+    tb.shell.set_cursor_pos(Some(inside));
+    tb.tick();
+    assert!(tb.taskbar().is_hidden());
+
+    tb.shell.set_cursor_pos(Some(edge_pos));
+    tb.tick_through_fade();
+    assert!(!tb.taskbar().is_hidden());
+
+    /* once shown, the whole island keeps the taskbar visible */
+    tb.shell.set_cursor_pos(Some(inside));
+    tb.tick_through_fade();
+    assert!(!tb.taskbar().is_hidden());
+}
+
+#[test]
+fn top_taskbar_reveals_on_the_top_edge() {
+    let mut tb = start_docked(Edge::Top, TbSettings::load_defaults());
+    assert_eq!(tb.taskbar().get_edge(), Edge::Top);
+    assert_reveals_only_on_the_edge(&mut tb, Point::new(960, 10), Point::new(960, 0));
+    assert_eq!(tb.shell.desktop().work_area, Rect::new(0, 1, 1920, 1080));
+
+    tb.taskbars.clean_up();
+    assert_eq!(tb.shell.desktop().work_area, Rect::new(0, 48, 1920, 1080));
+}
+
+#[test]
+fn left_taskbar_reveals_on_the_left_edge() {
+    let mut tb = start_docked(Edge::Left, TbSettings::load_defaults());
+    assert_eq!(tb.taskbar().get_edge(), Edge::Left);
+    assert_reveals_only_on_the_edge(&mut tb, Point::new(10, 540), Point::new(0, 540));
+    assert_eq!(tb.shell.desktop().work_area, Rect::new(1, 0, 1920, 1080));
+
+    tb.taskbars.clean_up();
+    assert_eq!(tb.shell.desktop().work_area, Rect::new(48, 0, 1920, 1080));
+}
+
+#[test]
+fn right_taskbar_reveals_on_the_right_edge() {
+    let mut tb = start_docked(Edge::Right, TbSettings::load_defaults());
+    assert_eq!(tb.taskbar().get_edge(), Edge::Right);
+    assert_reveals_only_on_the_edge(&mut tb, Point::new(1910, 540), Point::new(1919, 540));
+    assert_eq!(tb.shell.desktop().work_area, Rect::new(0, 0, 1919, 1080));
+
+    tb.taskbars.clean_up();
+    assert_eq!(tb.shell.desktop().work_area, Rect::new(0, 0, 1872, 1080));
+}
+
+#[test]
+fn vertical_taskbar_island_runs_along_the_edge() {
+    let mut tb = start_docked(Edge::Left, TbSettings::load_defaults());
+    let region = tb.shell.desktop().region.clone().unwrap().spec;
+    /* the applist spans 340..740 on the y axis, the margins are applied across the taskbar */
+    assert_eq!(region.base.rect.top, 340);
+    assert_eq!(region.base.rect.bottom, 742);
+    assert!(region.base.rect.right <= 49);
+
+    /* hovering beside the island on the taskbar does not keep it visible */
+    tb.shell.set_cursor_pos(Some(Point::new(10, 100)));
+    tb.tick_through_fade();
+    assert!(tb.taskbar().is_hidden());
+}
+
+#[test]
+fn slide_mode_moves_the_taskbar_off_its_docked_edge() {
+    let settings = settings_with(json!({ "hide_mode": "slide" }));
+    for (edge, offset) in [
+        (Edge::Left, (-48, 0)),
+        (Edge::Top, (0, -48)),
+        (Edge::Right, (48, 0)),
+    ] {
+        let mut tb = start_docked(edge, settings.clone());
+        let docked = taskbar_rect(&tb);
+        tb.shell.set_cursor_pos(Some(Point::new(960, 500)));
+        tb.tick_through_fade();
+        assert!(tb.taskbar().is_hidden());
+        assert_eq!(taskbar_rect(&tb), docked.offset(offset.0, offset.1));
+        /* the rects are still reported at the docked position */
+        assert_eq!(tb.taskbar().get_edge(), edge);
+
+        tb.taskbars.clean_up();
+        assert_eq!(taskbar_rect(&tb), docked);
+    }
+}
+
+#[test]
+fn secondary_taskbar_uses_the_dpi_of_its_monitor() {
+    let shell = SimulatedShell::with_standard_taskbar(DISPLAY, 48);
+    let secondary = shell.add_secondary_taskbar(SECOND_DISPLAY, 84);
+    shell.desktop().set_monitor_dpi(SECOND_DISPLAY, Dpi(168));
+    let mut tb = start_on(
+        shell,
+        settings_with(json!({
+            "rounded_corners_size": 12,
+            "tb_rect_detection_size_in_pixel": 4,
+        })),
+    );
+    assert_eq!(tb.taskbars.secondaries()[0].get_dpi(), Dpi(168));
+    {
+        let desktop = tb.shell.desktop();
+        let primary = desktop.window_by_class("Shell_TrayWnd").unwrap();
+        assert_eq!(
+            primary.region.as_ref().unwrap().base.corner_width,
+            Physical(12)
+        );
+        let region = desktop.window(secondary).unwrap().region.clone().unwrap();
+        assert_eq!(region.base.corner_width, Physical(21));
+    }
+
+    tb.shell.set_cursor_pos(Some(Point::new(960, 500)));
+    tb.tick_through_fade();
+    assert_eq!(alpha_of(&tb, secondary), 0);
+
+    /* the detection strip and the bottom offset are 5 logical pixels, 9 physical pixels at 175% */
+    tb.shell.set_cursor_pos(Some(Point::new(960, 1074)));
+    tb.tick_through_fade();
+    assert!(tb.taskbar().is_hidden());
+    tb.shell.set_cursor_pos(Some(Point::new(2880, 1074)));
+    tb.tick_through_fade();
+    assert_eq!(alpha_of(&tb, secondary), 255);
+}
+
+impl Harness {
+    /* applies changed settings like the main loop does after the settings file changed. */
+    fn reload(&mut self, changes: serde_json::Value) {
+        let settings = settings_with(changes);
+        self.scheduler.apply_settings(&settings, &mut self.taskbars);
+        self.settings = settings;
+    }
+}
+
+#[test]
+fn reloading_settings_keeps_the_hidden_state() {
+    let mut tb = start();
+    tb.shell.set_cursor_pos(Some(Point::new(960, 500)));
+    tb.tick_through_fade();
+    assert!(tb.taskbar().is_hidden());
+
+    tb.reload(json!({
+        "animation_steps": 4,
+        "show_animation_duration_in_ms": 40,
+        "tb_rect_detection_size_in_pixel": 10,
+    }));
+    tb.tick();
+    assert!(tb.taskbar().is_hidden());
+    assert_eq!(tb.shell.desktop().taskbar_alpha(), Some(0));
+
+    /* the larger detection strip and the shorter fade are used right away */
+    tb.shell.set_cursor_pos(Some(Point::new(960, 1072)));
+    tb.tick();
+    tb.clock.advance(Duration::from_millis(40));
+    tb.tick();
+    assert!(!tb.taskbar().is_hidden());
+    assert_eq!(tb.shell.desktop().taskbar_alpha(), Some(255));
+}
+
+#[test]
+fn reloading_without_autohide_shows_the_taskbar_and_restores_the_work_area() {
+    let mut tb = start();
+    tb.shell.set_cursor_pos(Some(Point::new(960, 500)));
+    tb.tick_through_fade();
+    assert_eq!(tb.shell.desktop().work_area, Rect::new(0, 0, 1920, 1079));
+
+    tb.reload(json!({ "autohide": false }));
+    tb.tick_through_fade();
+    assert!(!tb.taskbar().is_hidden());
+    let desktop = tb.shell.desktop();
+    assert_eq!(desktop.taskbar_alpha(), Some(255));
+    assert_eq!(desktop.work_area, Rect::new(0, 0, 1920, 1032));
+}
+
+#[test]
+fn reloading_another_hide_mode_moves_a_hidden_taskbar_instead() {
+    let mut tb = start();
+    let docked = taskbar_rect(&tb);
+    tb.shell.set_cursor_pos(Some(Point::new(960, 500)));
+    tb.tick_through_fade();
+
+    tb.reload(json!({ "hide_mode": "slide" }));
+    assert!(tb.taskbar().is_hidden());
+    assert_eq!(tb.shell.desktop().taskbar_alpha(), Some(255));
+    assert_eq!(taskbar_rect(&tb), docked.offset(0, 48));
+}
+
+#[test]
+fn reloading_dynamic_borders_updates_the_region() {
+    let mut tb = start_with(settings_with(json!({ "enable_dynamic_borders": false })));
+    assert!(tb.shell.desktop().region.is_none());
+
+    tb.reload(json!({}));
+    tb.tick();
+    /* the applist spans 760..1160, widened by an icon on each side by the windows 11 bugfix */
+    let region = tb.shell.desktop().region.clone().unwrap().spec;
+    assert_eq!(region.base.rect.left, 716);
+
+    tb.reload(json!({ "enable_dynamic_borders": false }));
+    let region = tb.shell.desktop().region.clone().unwrap().spec;
+    assert_eq!(region.base.rect, Rect::new(0, 0, 1921, 49));
+}
+
+#[test]
+fn switching_to_a_pinned_profile_shows_the_taskbar_right_away() {
+    let settings = settings_with(json!({
+        "active_profile": "focus",
+        "profiles": {
+            "presenting": { "autohide": false, "enable_dynamic_borders": false }
+        }
+    }));
+    let mut tb = start_with(settings.clone());
+    tb.shell.set_cursor_pos(Some(Point::new(960, 500)));
+    tb.tick_through_fade();
+    assert!(tb.taskbar().is_hidden());
+
+    let presenting = settings.with_profile("presenting").unwrap();
+    tb.scheduler.apply_settings(&presenting, &mut tb.taskbars);
+    tb.settings = presenting;
+    tb.tick_through_fade();
+    assert!(!tb.taskbar().is_hidden());
+    let desktop = tb.shell.desktop();
+    assert_eq!(desktop.taskbar_alpha(), Some(255));
+    assert_eq!(desktop.work_area, Rect::new(0, 0, 1920, 1032));
+    assert_eq!(
+        desktop.region.clone().unwrap().spec.base.rect,
+        Rect::new(0, 0, 1921, 49)
+    );
+}
+
+impl Harness {
+    /* answers a request of the control channel like the main loop does. */
+    fn request(&mut self, request: &Request) -> Response {
+        self.scheduler.handle_request(
+            request,
+            &mut self.settings,
+            &mut self.taskbars,
+            &self.signaling,
+        )
+    }
+}
+
+fn preview(changes: serde_json::Value) -> Request {
+    Request::Preview {
+        values: changes.as_object().unwrap().clone(),
+    }
+}
+
+fn corner_width(tb: &Harness) -> Physical {
+    tb.shell
+        .desktop()
+        .region
+        .clone()
+        .unwrap()
+        .spec
+        .base
+        .corner_width
+}
+
+#[test]
+fn previews_apply_over_the_saved_settings_until_reverted() {
+    let mut tb = start();
+    assert_eq!(corner_width(&tb), Physical(4));
+
+    let request = preview(json!({ "rounded_corners_size": 20 }));
+    assert!(tb.request(&request).ok);
+    tb.tick();
+    assert_eq!(corner_width(&tb), Physical(20));
+
+    /* each preview starts from the saved settings, the corners are no longer previewed */
+    let request = preview(json!({ "margin_top": 6 }));
+    assert!(tb.request(&request).ok);
+    tb.tick();
+    assert_eq!(corner_width(&tb), Physical(4));
+    assert_eq!(tb.settings.get_margin_top(), Logical(6));
+
+    assert!(tb.request(&Request::Revert).ok);
+    tb.tick();
+    assert_eq!(tb.settings.get_margin_top(), Logical(2));
+    assert_eq!(corner_width(&tb), Physical(4));
+}
+
+#[test]
+fn invalid_previews_are_rejected_and_keep_the_applied_settings() {
+    let mut tb = start();
+    let request = preview(json!({ "rounded_corners_size": 20 }));
+    tb.request(&request);
+
+    let request = preview(json!({ "animation_steps": 0 }));
+    let response = tb.request(&request);
+    assert!(!response.ok);
+    assert!(response.error.unwrap().contains("animation_steps"));
+    tb.tick();
+    assert_eq!(corner_width(&tb), Physical(20));
+    assert_eq!(tb.settings.get_animation_steps(), 8);
+}
+
+#[test]
+fn pinned_taskbars_stay_shown_until_unpinned() {
+    let mut tb = start();
+    tb.shell.set_cursor_pos(Some(Point::new(960, 500)));
+    tb.tick_through_fade();
+    assert!(tb.taskbar().is_hidden());
+
+    assert!(tb.request(&Request::Pin).ok);
+    tb.tick_through_fade();
+    tb.tick_through_fade();
+    assert!(!tb.taskbar().is_hidden());
+    assert_eq!(tb.shell.desktop().taskbar_alpha(), Some(255));
+
+    assert!(tb.request(&Request::Unpin).ok);
+    tb.tick_through_fade();
+    assert!(tb.taskbar().is_hidden());
+}
+
+#[test]
+fn show_and_hide_last_until_the_cursor_enters_or_leaves() {
+    let mut tb = start();
+    tb.shell.set_cursor_pos(Some(Point::new(960, 500)));
+    tb.tick_through_fade();
+
+    tb.request(&Request::Show);
+    tb.tick_through_fade();
+    tb.tick_through_fade();
+    assert!(!tb.taskbar().is_hidden());
+
+    /* hovering and leaving again hands the taskbar back to autohide */
+    tb.shell.set_cursor_pos(Some(Point::new(960, 1060)));
+    tb.tick_through_fade();
+    tb.shell.set_cursor_pos(Some(Point::new(960, 500)));
+    tb.tick_through_fade();
+    assert!(tb.taskbar().is_hidden());
+
+    tb.shell.set_cursor_pos(Some(Point::new(960, 1079)));
+    tb.tick_through_fade();
+    assert!(!tb.taskbar().is_hidden());
+    tb.request(&Request::Hide);
+    tb.tick_through_fade();
+    assert!(tb.taskbar().is_hidden());
+}
+
+#[test]
+fn status_reports_the_running_state() {
+    let mut tb = start();
+    tb.request(&Request::Pin);
+    let status = tb.request(&Request::Status).status.unwrap();
+    assert_eq!(status.active_profile, "default");
+    assert!(status.pinned);
+    assert!(!status.hidden);
+    assert!(!status.previewing);
+    assert_eq!(status.taskbars, 1);
+}
+
+#[test]
+fn set_and_profile_change_the_saved_settings() {
+    let settings = settings_with(json!({
+        "profiles": { "focus": { "margin_top": 7 } }
+    }));
+    let mut tb = start_with(settings);
+    let set = Request::Set {
+        key: "rounded_corners_size".to_string(),
+        value: json!(12),
+    };
+    assert!(tb.request(&set).ok);
+    tb.tick();
+    assert_eq!(corner_width(&tb), Physical(12));
+
+    let invalid = Request::Set {
+        key: "rounded_corners_size".to_string(),
+        value: json!("round"),
+    };
+    assert_eq!(tb.request(&invalid).code, Some(ErrorCode::InvalidValue));
+    let unknown = Request::Profile {
+        name: "gaming".to_string(),
+    };
+    assert_eq!(tb.request(&unknown).code, Some(ErrorCode::UnknownProfile));
+
+    let focus = Request::Profile {
+        name: "focus".to_string(),
+    };
+    assert!(tb.request(&focus).ok);
+    assert_eq!(tb.settings.get_active_profile(), "focus");
+    assert_eq!(tb.settings.get_margin_top(), Logical(7));
+}
+
+#[test]
+fn quit_ends_the_main_loop() {
+    let mut tb = start();
+    assert!(tb.request(&Request::Quit).ok);
+    assert!(tb.signaling.get_exit_called());
+}
@@ -0,0 +1,333 @@
+use std::fs;
+use std::path::PathBuf;
+
+use hidden_tb::geometry::Logical;
+use hidden_tb::settings_registry;
+use hidden_tb::settings_validation::{self, SettingsError};
+use hidden_tb::tb_settings::{TbSettings, DEFAULT_PROFILE, SETTINGS_VERSION};
+
+/* a settings.json from before versioning, it has no version field and a single animation time. */
+const VERSION_1: &str = r#"{
+  "autohide": true,
+  "workspace_offset_top": 0,
+  "merge_tray": false,
+  "merge_widgets": false,
+  "sleep_time_in_ms": 10,
+  "animation_time_in_ms": 20,
+  "animation_steps": 4,
+  "infrequent_count": 60,
+  "tb_rect_bottom_offset": 1,
+  "tb_rect_detection_size_in_pixel": 3,
+  "enable_dynamic_borders": true,
+  "dynamic_borders_show_tray": false,
+  "dynamic_borders_show_tray_if_disabled_on_hover": true,
+  "dynamic_borders_show_widgets": false,
+  "dynamic_borders_show_widgets_if_disabled_on_hover": false,
+  "rounded_corners_size": 10,
+  "margin_offset_left": 12,
+  "margin_offset_right": 14,
+  "margin_left": 1,
+  "margin_right": 2,
+  "margin_bottom": 5,
+  "margin_top": 6,
+  "windows_11_bugfix": false,
+  "restart_executables": ["explorer.exe"]
+}"#;
+
+fn settings_file(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!(
+        "hidden_tb_settings_{}_{}.json",
+        name,
+        std::process::id()
+    ));
+    fs::remove_file(&path).ok();
+    fs::remove_file(TbSettings::get_backup_path(&path)).ok();
+    path
+}
+
+#[test]
+fn migrates_a_version_1_file_and_keeps_every_value() {
+    let settings = TbSettings::from_json(VERSION_1).unwrap();
+    assert_eq!(settings.get_version(), SETTINGS_VERSION);
+    /* 4 steps of 20 ms */
+    assert_eq!(settings.get_show_animation_duration_in_ms(), 80);
+    assert_eq!(settings.get_hide_animation_duration_in_ms(), 80);
+    assert_eq!(settings.get_animation_steps(), 4);
+    assert_eq!(settings.get_tb_rect_detection_size_in_pixel(), Logical(3));
+    assert_eq!(settings.get_rounded_corners_size(), Logical(10));
+    assert_eq!(settings.get_margin_offset_left(), Logical(12));
+    assert_eq!(settings.get_margin_top(), Logical(6));
+    assert!(!settings.get_windows_11_bugfix());
+    assert_eq!(settings.get_restart_executables(), vec!["explorer.exe"]);
+}
+
+#[test]
+fn missing_fields_take_their_default() {
+    let settings = TbSettings::from_json(r#"{ "version": 2, "margin_top": 7 }"#).unwrap();
+    let defaults = TbSettings::load_defaults();
+    assert_eq!(settings.get_margin_top(), Logical(7));
+    assert_eq!(settings.get_margin_bottom(), defaults.get_margin_bottom());
+    assert_eq!(
+        settings.get_infrequent_count(),
+        defaults.get_infrequent_count()
+    );
+    assert_eq!(
+        settings.get_hover_leave_margin_in_pixel(),
+        defaults.get_hover_leave_margin_in_pixel()
+    );
+}
+
+#[test]
+fn a_newer_version_keeps_the_known_fields() {
+    let settings =
+        TbSettings::from_json(r#"{ "version": 99, "margin_left": 9, "a_future_setting": true }"#)
+            .unwrap();
+    assert_eq!(settings.get_margin_left(), Logical(9));
+}
+
+#[test]
+fn load_writes_a_migrated_file_back() {
+    let path = settings_file("migrated");
+    fs::write(&path, VERSION_1).unwrap();
+
+    let (settings, warning) = TbSettings::load_or_backup(&path);
+    assert!(warning.is_none());
+    assert_eq!(settings.get_margin_top(), Logical(6));
+    let written: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+    assert_eq!(written["version"], SETTINGS_VERSION);
+    assert!(written.get("animation_time_in_ms").is_none());
+    assert!(!TbSettings::get_backup_path(&path).exists());
+    fs::remove_file(&path).ok();
+}
+
+#[test]
+fn a_migrated_file_with_invalid_values_is_written_back_with_their_defaults() {
+    let path = settings_file("migrated_invalid");
+    fs::write(
+        &path,
+        VERSION_1.replace("\"animation_steps\": 4", "\"animation_steps\": 0"),
+    )
+    .unwrap();
+
+    let (settings, warning) = TbSettings::load_or_backup(&path);
+    assert!(warning.unwrap().contains("animation_steps"));
+    assert_eq!(settings.get_animation_steps(), 8);
+    let written = TbSettings::load_from(&path).unwrap();
+    assert_eq!(written.get_version(), SETTINGS_VERSION);
+    assert_eq!(written.get_animation_steps(), 8);
+    assert_eq!(written.get_margin_top(), Logical(6));
+    fs::remove_file(&path).ok();
+}
+
+#[test]
+fn a_migrated_file_drops_foreground_rules_with_an_invalid_regex() {
+    let path = settings_file("migrated_regex");
+    fs::write(
+        &path,
+        VERSION_1.replace(
+            "\"autohide\": true,",
+            r#""autohide": true,
+  "foreground_rules": [
+    { "title_regex": "(unclosed", "action": "always_show" },
+    { "exe_name": "game.exe", "action": "always_hide" }
+  ],"#,
+        ),
+    )
+    .unwrap();
+
+    let (settings, warning) = TbSettings::load_or_backup(&path);
+    assert!(warning.unwrap().contains("foreground_rules[0].title_regex"));
+    assert_eq!(settings.get_foreground_rules().len(), 1);
+    assert_eq!(settings.get_foreground_rules()[0].exe_name, "game.exe");
+
+    let (written, warning) = TbSettings::load_or_backup(&path);
+    assert!(warning.is_none());
+    assert_eq!(written.get_version(), SETTINGS_VERSION);
+    assert_eq!(written.get_foreground_rules().len(), 1);
+    assert!(written.validate().is_ok());
+    fs::remove_file(&path).ok();
+}
+
+#[test]
+fn unparsable_file_is_backed_up_instead_of_deleted() {
+    let path = settings_file("broken");
+    fs::write(&path, "{ \"margin_top\": 6,").unwrap();
+
+    let (settings, warning) = TbSettings::load_or_backup(&path);
+    let backup = TbSettings::get_backup_path(&path);
+    assert_eq!(
+        settings.get_margin_top(),
+        TbSettings::load_defaults().get_margin_top()
+    );
+    assert!(warning.unwrap().contains(&backup.display().to_string()));
+    assert_eq!(fs::read_to_string(&backup).unwrap(), "{ \"margin_top\": 6,");
+    /* the defaults replace the broken file */
+    assert!(TbSettings::load_from(&path).is_ok());
+    fs::remove_file(&path).ok();
+    fs::remove_file(&backup).ok();
+}
+
+#[test]
+fn read_only_load_leaves_the_file_alone() {
+    let path = settings_file("read_only");
+    let (settings, warning) = TbSettings::load_read_only(&path);
+    assert!(warning.is_none());
+    assert_eq!(
+        settings.get_margin_top(),
+        TbSettings::load_defaults().get_margin_top()
+    );
+    assert!(!path.exists());
+
+    let invalid = VERSION_1.replace("\"animation_steps\": 4", "\"animation_steps\": 0");
+    fs::write(&path, &invalid).unwrap();
+    let (settings, warning) = TbSettings::load_read_only(&path);
+    assert!(warning.unwrap().contains("animation_steps"));
+    assert_eq!(settings.get_animation_steps(), 8);
+    assert_eq!(settings.get_margin_top(), Logical(6));
+    assert_eq!(fs::read_to_string(&path).unwrap(), invalid);
+
+    fs::write(&path, "{ \"margin_top\": 6,").unwrap();
+    let (_, warning) = TbSettings::load_read_only(&path);
+    assert!(warning.is_some());
+    assert_eq!(fs::read_to_string(&path).unwrap(), "{ \"margin_top\": 6,");
+    assert!(!TbSettings::get_backup_path(&path).exists());
+    fs::remove_file(&path).ok();
+}
+
+#[test]
+fn wrong_types_are_reported() {
+    assert!(TbSettings::from_json(r#"{ "margin_top": "six" }"#).is_err());
+    assert!(TbSettings::from_json(r#"{ "version": "2" }"#).is_err());
+    assert!(TbSettings::from_json("[]").is_err());
+}
+
+#[test]
+fn validation_reports_every_invalid_field() {
+    let settings = TbSettings::from_json(
+        r#"{
+            "version": 2,
+            "animation_steps": 0,
+            "infrequent_count": 0,
+            "foreground_rules": [
+                { "title_regex": "(unclosed", "action": "always_show" }
+            ]
+        }"#,
+    )
+    .unwrap();
+    let errors = settings.validate().unwrap_err();
+    let fields: Vec<_> = errors.iter().map(|error| error.field.as_str()).collect();
+    assert_eq!(
+        fields,
+        vec![
+            "animation_steps",
+            "infrequent_count",
+            "foreground_rules[0].title_regex"
+        ]
+    );
+    assert_eq!(
+        errors[0],
+        SettingsError {
+            field: "animation_steps".to_string(),
+            value: "0".to_string(),
+            allowed: "1..=255".to_string(),
+            reason: "the fade needs at least one step".to_string(),
+        }
+    );
+    assert_eq!(errors[1].allowed, "1..");
+    assert!(TbSettings::load_defaults().validate().is_ok());
+}
+
+#[test]
+fn numbers_are_checked_with_the_type_of_their_setting() {
+    let settings =
+        TbSettings::from_json(r#"{ "version": 2, "sleep_time_in_ms": 18446744073709551615 }"#)
+            .unwrap();
+    assert_eq!(settings.get_sleep_time_in_ms(), u64::MAX);
+    assert!(settings.validate().is_ok());
+
+    let sleep_time = settings_registry::find("sleep_time_in_ms").unwrap();
+    let errors = settings_validation::check(sleep_time, &serde_json::json!(-1));
+    assert_eq!(errors.len(), 1);
+    assert!(!errors[0].reason.is_empty());
+}
+
+#[test]
+fn load_replaces_invalid_values_by_their_defaults() {
+    let path = settings_file("invalid");
+    fs::write(
+        &path,
+        r#"{ "version": 2, "animation_steps": 0, "margin_top": 6 }"#,
+    )
+    .unwrap();
+
+    let (settings, warning) = TbSettings::load_or_backup(&path);
+    assert_eq!(
+        settings.get_animation_steps(),
+        TbSettings::load_defaults().get_animation_steps()
+    );
+    assert_eq!(settings.get_margin_top(), Logical(6));
+    assert!(warning.unwrap().contains("animation_steps = 0"));
+    /* the file is kept as it is, so the value can be fixed in the settings */
+    assert!(TbSettings::load_from(&path).unwrap().validate().is_err());
+    fs::remove_file(&path).ok();
+}
+
+#[test]
+fn switching_profiles_keeps_the_values_of_the_previous_one() {
+    let settings = TbSettings::from_json(
+        r#"{
+            "version": 2,
+            "active_profile": "focus",
+            "margin_top": 6,
+            "profiles": {
+                "presenting": { "autohide": false, "enable_dynamic_borders": false }
+            }
+        }"#,
+    )
+    .unwrap();
+    assert_eq!(settings.get_profile_names(), vec!["focus", "presenting"]);
+
+    let presenting = settings.with_profile("presenting").unwrap();
+    assert_eq!(presenting.get_active_profile(), "presenting");
+    assert!(!presenting.get_autohide());
+    assert!(!presenting.get_enable_dynamic_borders());
+    /* settings the profile does not list take their default */
+    assert_eq!(
+        presenting.get_margin_top(),
+        TbSettings::load_defaults().get_margin_top()
+    );
+
+    let focus = presenting.with_profile("focus").unwrap();
+    assert_eq!(focus.get_active_profile(), "focus");
+    assert!(focus.get_autohide());
+    assert_eq!(focus.get_margin_top(), Logical(6));
+    assert_eq!(focus.get_profile_names(), vec!["focus", "presenting"]);
+}
+
+#[test]
+fn unknown_and_invalid_profiles_are_refused() {
+    let settings = TbSettings::from_json(
+        r#"{ "version": 2, "profiles": { "broken": { "animation_steps": 0 } } }"#,
+    )
+    .unwrap();
+    assert_eq!(settings.get_active_profile(), DEFAULT_PROFILE);
+
+    let errors = settings.with_profile("missing").unwrap_err();
+    assert_eq!(errors[0].field, "active_profile");
+    assert_eq!(errors[0].allowed, "broken, default");
+
+    let errors = settings.with_profile("broken").unwrap_err();
+    assert_eq!(errors[0].field, "animation_steps");
+}
+
+#[test]
+fn a_new_profile_starts_with_the_current_values() {
+    let settings = TbSettings::from_json(r#"{ "version": 2, "margin_top": 6 }"#).unwrap();
+    let focus = settings.with_new_profile("focus").unwrap();
+    assert_eq!(focus.get_active_profile(), "focus");
+    assert_eq!(focus.get_margin_top(), Logical(6));
+    assert_eq!(focus.get_profile_names(), vec!["default", "focus"]);
+    assert!(focus.with_new_profile("default").is_err());
+    assert!(focus.with_new_profile(" ").is_err());
+}